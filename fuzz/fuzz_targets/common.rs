@@ -0,0 +1,8 @@
+use directxtex::LoadLimits;
+
+// Keeps allocations well under libFuzzer's default malloc limit, so huge but valid headers aren't
+// reported as crashes
+pub const LIMITS: LoadLimits = LoadLimits {
+    max_bytes: 1 << 26,
+    ..LoadLimits::UNLIMITED
+};
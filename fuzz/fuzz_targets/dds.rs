@@ -0,0 +1,11 @@
+#![no_main]
+
+use directxtex::dds;
+use directxtex::flags::DDS_FLAGS;
+use libfuzzer_sys::fuzz_target;
+
+#[path = "common.rs"] mod common;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = dds::load_from_memory_with_limits(data, DDS_FLAGS::default(), &common::LIMITS);
+});
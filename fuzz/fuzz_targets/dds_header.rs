@@ -0,0 +1,17 @@
+#![no_main]
+
+use directxtex::dds::header;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok((metadata, _)) = header::parse(data) else { return };
+    assert_eq!(header::metadata_from_memory(data).ok(), Some(metadata));
+
+    // Whatever parses has to survive being written and read back
+    if let Ok(encoded) = header::encode(&metadata) {
+        let (reparsed, offset) = header::parse(&encoded).expect("Failed to parse encoded header");
+        assert_eq!(offset, encoded.len());
+        assert_eq!(reparsed.format, metadata.format);
+        assert_eq!((reparsed.width, reparsed.height), (metadata.width, metadata.height));
+    }
+});
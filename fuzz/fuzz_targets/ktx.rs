@@ -0,0 +1,13 @@
+#![no_main]
+
+use directxtex::ktx;
+use libfuzzer_sys::fuzz_target;
+
+#[path = "common.rs"] mod common;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(info) = ktx::metadata_from_memory(data) {
+        let _ = info.format_name();
+    }
+    let _ = ktx::load_from_memory_with_limits(data, &common::LIMITS);
+});
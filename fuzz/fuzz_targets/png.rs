@@ -0,0 +1,10 @@
+#![no_main]
+
+use directxtex::png;
+use libfuzzer_sys::fuzz_target;
+
+#[path = "common.rs"] mod common;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = png::load_from_memory_with_limits(data, &common::LIMITS);
+});
@@ -0,0 +1,23 @@
+#![no_main]
+
+use directxtex::dds;
+use directxtex::flags::DDS_FLAGS;
+use libfuzzer_sys::fuzz_target;
+
+#[path = "common.rs"] mod common;
+
+// Anything that loads has to save and load back to the same pixels
+fuzz_target!(|data: &[u8]| {
+    let Ok(image) = dds::load_from_memory_with_limits(data, DDS_FLAGS::default(), &common::LIMITS)
+    else {
+        return;
+    };
+
+    let saved = image
+        .save_dds_to_memory(DDS_FLAGS::default())
+        .expect("Failed to save a loaded image");
+    let reloaded =
+        dds::load_from_memory(saved.buffer(), DDS_FLAGS::default()).expect("Failed to reload");
+    assert_eq!(reloaded.metadata(), image.metadata());
+    assert_eq!(reloaded.buffer(), image.buffer());
+});
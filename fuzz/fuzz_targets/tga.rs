@@ -0,0 +1,11 @@
+#![no_main]
+
+use directxtex::flags::TGA_FLAGS;
+use directxtex::tga;
+use libfuzzer_sys::fuzz_target;
+
+#[path = "common.rs"] mod common;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = tga::load_from_memory_with_limits(data, TGA_FLAGS::default(), &common::LIMITS);
+});
@@ -0,0 +1,21 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[cfg(windows)]
+#[path = "common.rs"]
+mod common;
+
+// WIC only exists on Windows, elsewhere this target does nothing
+fuzz_target!(|data: &[u8]| {
+    #[cfg(windows)]
+    {
+        use directxtex::flags::WIC_FLAGS;
+        use directxtex::wic;
+
+        let _ = directxtex::util::initialize_com();
+        let _ = wic::load_from_memory_with_limits(data, WIC_FLAGS::default(), &common::LIMITS);
+    }
+    #[cfg(not(windows))]
+    let _ = data;
+});
@@ -0,0 +1,477 @@
+#[cfg(feature = "serde")] use serde::{Deserialize, Serialize};
+
+use directxtex_sys::{TexMetadata, DXGI_FORMAT, TEX_DIMENSION};
+
+use crate::flags::{TEX_COMPRESS_FLAGS, TEX_FILTER_FLAGS};
+use crate::ops::plane::{float_format, Plane};
+use crate::preset::MipPolicy;
+use crate::util::is_compressed;
+use crate::{error, ops, Image, Result, ScratchImage};
+
+// Sprites are packed into cells: the sprite with `padding` pixels of its own edges extruded around
+// it, rounded up to a multiple of 2^(mips - 1) pixels, 4 * 2^(mips - 1) for block compressed
+// formats, and placed on that grid. Every mip of a cell is built from the cell alone and lands on
+// whole pixels and blocks, so sprites never bleed into each other, whatever the mip.
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Packer {
+    // Best short side fit, the tighter of the two
+    #[default]
+    MaxRects,
+    // Bottom left, faster with many sprites of similar heights
+    Skyline,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AtlasOptions {
+    pub packer:         Packer,
+    // Pixels of extruded edge around each sprite
+    pub padding:        usize,
+    pub power_of_two:   bool,
+    pub max_width:      usize,
+    pub max_height:     usize,
+    // Full stops once the smallest sprite with its padding is down to a pixel, and so does Levels
+    pub mips:           MipPolicy,
+    pub filter:         TEX_FILTER_FLAGS,
+    // The first sprite's format, decompressed, when None. Compressed formats go through `compress`.
+    pub format:         Option<DXGI_FORMAT>,
+    pub compress_flags: TEX_COMPRESS_FLAGS,
+}
+
+impl Default for AtlasOptions {
+    fn default() -> Self {
+        Self {
+            packer:         Packer::default(),
+            padding:        2,
+            power_of_two:   false,
+            max_width:      16384,
+            max_height:     16384,
+            mips:           MipPolicy::None,
+            filter:         TEX_FILTER_FLAGS::default(),
+            format:         None,
+            compress_flags: TEX_COMPRESS_FLAGS::default(),
+        }
+    }
+}
+
+// Where a sprite ended up, without its padding. UVs have (0, 0) at the top left of the atlas.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AtlasRect {
+    pub x:      usize,
+    pub y:      usize,
+    pub width:  usize,
+    pub height: usize,
+    pub u0:     f32,
+    pub v0:     f32,
+    pub u1:     f32,
+    pub v1:     f32,
+}
+
+// `rects` are in the order the sprites were given
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AtlasLayout {
+    pub width:      usize,
+    pub height:     usize,
+    pub mip_levels: usize,
+    pub rects:      Vec<AtlasRect>,
+}
+
+#[derive(Debug)]
+pub struct Atlas {
+    pub image:  ScratchImage,
+    pub layout: AtlasLayout,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Rect {
+    x:      usize,
+    y:      usize,
+    width:  usize,
+    height: usize,
+}
+
+impl Rect {
+    const fn right(&self) -> usize { self.x + self.width }
+
+    const fn bottom(&self) -> usize { self.y + self.height }
+
+    const fn intersects(&self, other: &Self) -> bool {
+        self.x < other.right()
+            && other.x < self.right()
+            && self.y < other.bottom()
+            && other.y < self.bottom()
+    }
+
+    const fn contains(&self, other: &Self) -> bool {
+        other.x >= self.x
+            && other.y >= self.y
+            && other.right() <= self.right()
+            && other.bottom() <= self.bottom()
+    }
+}
+
+// Largest first, which both packers do best with
+fn packing_order(sizes: &[(usize, usize)]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|&index| {
+        let (width, height) = sizes[index];
+        std::cmp::Reverse((width.max(height), width.min(height)))
+    });
+    order
+}
+
+fn pack_max_rects(sizes: &[(usize, usize)], width: usize, height: usize) -> Option<Vec<Rect>> {
+    let mut free = vec![Rect { x: 0, y: 0, width, height }];
+    let mut placed = vec![Rect { x: 0, y: 0, width: 0, height: 0 }; sizes.len()];
+
+    for index in packing_order(sizes) {
+        let (sprite_width, sprite_height) = sizes[index];
+        let best = free
+            .iter()
+            .filter(|rect| rect.width >= sprite_width && rect.height >= sprite_height)
+            .min_by_key(|rect| {
+                let (left_x, left_y) = (rect.width - sprite_width, rect.height - sprite_height);
+                (left_x.min(left_y), left_x.max(left_y))
+            })?;
+        let rect = Rect {
+            x:      best.x,
+            y:      best.y,
+            width:  sprite_width,
+            height: sprite_height,
+        };
+        placed[index] = rect;
+
+        let mut split = Vec::with_capacity(free.len() + 4);
+        for other in free.drain(..) {
+            if !other.intersects(&rect) {
+                split.push(other);
+                continue;
+            }
+            if rect.x > other.x {
+                split.push(Rect { width: rect.x - other.x, ..other });
+            }
+            if rect.right() < other.right() {
+                split.push(Rect {
+                    x: rect.right(),
+                    width: other.right() - rect.right(),
+                    ..other
+                });
+            }
+            if rect.y > other.y {
+                split.push(Rect { height: rect.y - other.y, ..other });
+            }
+            if rect.bottom() < other.bottom() {
+                split.push(Rect {
+                    y: rect.bottom(),
+                    height: other.bottom() - rect.bottom(),
+                    ..other
+                });
+            }
+        }
+        free = split
+            .iter()
+            .enumerate()
+            .filter(|&(i, rect)| {
+                !split.iter().enumerate().any(|(j, other)| {
+                    i != j && other.contains(rect) && (other != rect || j < i)
+                })
+            })
+            .map(|(_, rect)| *rect)
+            .collect();
+    }
+    Some(placed)
+}
+
+// Segments of the skyline as (x, y, width), left to right
+fn pack_skyline(sizes: &[(usize, usize)], width: usize, height: usize) -> Option<Vec<Rect>> {
+    let mut skyline = vec![(0, 0, width)];
+    let mut placed = vec![Rect { x: 0, y: 0, width: 0, height: 0 }; sizes.len()];
+
+    for index in packing_order(sizes) {
+        let (sprite_width, sprite_height) = sizes[index];
+        // Lowest top edge, then leftmost
+        let mut best: Option<(usize, usize, usize)> = None;
+        for start in 0..skyline.len() {
+            let x = skyline[start].0;
+            if x + sprite_width > width {
+                break;
+            }
+            let y = skyline[start..]
+                .iter()
+                .take_while(|&&(segment_x, _, _)| segment_x < x + sprite_width)
+                .map(|&(_, segment_y, _)| segment_y)
+                .max()
+                .unwrap_or(0);
+            if y + sprite_height <= height
+                && best.map_or(true, |(best_y, best_x, _)| (y, x) < (best_y, best_x))
+            {
+                best = Some((y, x, start));
+            }
+        }
+        let (y, x, start) = best?;
+        placed[index] = Rect {
+            x,
+            y,
+            width: sprite_width,
+            height: sprite_height,
+        };
+
+        // Segments under the sprite are replaced, one sticking out on the right is cut short
+        let right = x + sprite_width;
+        let mut end = start;
+        while end < skyline.len() && skyline[end].0 < right {
+            end += 1;
+        }
+        let (last_x, last_y, last_width) = skyline[end - 1];
+        let mut replacement = vec![(x, y + sprite_height, sprite_width)];
+        if last_x + last_width > right {
+            replacement.push((right, last_y, last_x + last_width - right));
+        }
+        skyline.splice(start..end, replacement);
+
+        let mut merged: Vec<(usize, usize, usize)> = Vec::with_capacity(skyline.len());
+        for segment in skyline.drain(..) {
+            match merged.last_mut() {
+                Some(last) if last.1 == segment.1 => last.2 += segment.2,
+                _ => merged.push(segment),
+            }
+        }
+        skyline = merged;
+    }
+    Some(placed)
+}
+
+fn pack_cells(
+    packer: Packer,
+    sizes: &[(usize, usize)],
+    width: usize,
+    height: usize,
+) -> Option<Vec<Rect>> {
+    match packer {
+        Packer::MaxRects => pack_max_rects(sizes, width, height),
+        Packer::Skyline => pack_skyline(sizes, width, height),
+    }
+}
+
+// Grows the atlas from the smallest size that could hold every cell until they fit, sizes are in
+// `block` units
+fn find_layout(
+    options: &AtlasOptions,
+    sizes: &[(usize, usize)],
+    block: usize,
+) -> Result<(usize, usize, Vec<Rect>)> {
+    let round = |size: usize| if options.power_of_two { size.next_power_of_two() } else { size };
+    let limit = |max: usize| {
+        let max = if options.power_of_two && max > 0 { 1 << max.ilog2() } else { max };
+        max / block
+    };
+    let (max_width, max_height) = (limit(options.max_width), limit(options.max_height));
+
+    let area: usize = sizes.iter().map(|(width, height)| width * height).sum();
+    let widest = sizes.iter().map(|&(width, _)| width).max().unwrap_or(1);
+    let tallest = sizes.iter().map(|&(_, height)| height).max().unwrap_or(1);
+    if widest > max_width || tallest > max_height {
+        return Err(error::invalid_arg());
+    }
+    let side = (area as f64).sqrt().ceil() as usize;
+    let mut width = round(widest.max(side)).min(max_width);
+    let mut height = round(tallest.max(area.div_ceil(width))).min(max_height);
+
+    loop {
+        if let Some(cells) = pack_cells(options.packer, sizes, width, height) {
+            return Ok((width, height, cells));
+        }
+        let grow = |size: usize| round(size + (size / 8).max(1));
+        if width < max_width && (width <= height || height >= max_height) {
+            width = grow(width).min(max_width);
+        } else if height < max_height {
+            height = grow(height).min(max_height);
+        } else {
+            return Err(error::invalid_arg());
+        }
+    }
+}
+
+// The top image of a sprite as R32G32B32A32_FLOAT
+fn read_sprite(sprite: &Image) -> Result<Plane> {
+    let sprite = std::slice::from_ref(sprite);
+    let decompressed;
+    let (images, metadata) = if is_compressed(sprite[0].format()) {
+        decompressed = ops::decompress(sprite, &sprite[0].metadata())?;
+        (decompressed.images(), *decompressed.metadata())
+    } else {
+        (sprite, sprite[0].metadata())
+    };
+    if images[0].format() == float_format() {
+        return Ok(Plane::read(&images[0]));
+    }
+    let float = ops::convert(images, &metadata, float_format(), TEX_FILTER_FLAGS::default())?;
+    Ok(Plane::read(float.image0()))
+}
+
+fn decompressed_format(image: &Image) -> Result<DXGI_FORMAT> {
+    if is_compressed(image.format()) {
+        let image = std::slice::from_ref(image);
+        Ok(ops::decompress(image, &image[0].metadata())?.format())
+    } else {
+        Ok(image.format())
+    }
+}
+
+fn mip_levels(options: &AtlasOptions, sprites: &[&Image]) -> usize {
+    let smallest = sprites
+        .iter()
+        .map(|sprite| sprite.width().min(sprite.height()) + 2 * options.padding)
+        .min()
+        .unwrap_or(1);
+    let full = smallest.ilog2() as usize + 1;
+    match options.mips {
+        MipPolicy::None => 1,
+        MipPolicy::Full | MipPolicy::Levels(0) => full,
+        // More levels than the smallest cell has would blend neighbouring sprites
+        MipPolicy::Levels(levels) => levels.min(full),
+    }
+}
+
+// A sprite with its edges extruded over the whole cell, and the cell's mips
+fn build_cell(
+    sprite: &Plane,
+    padding: usize,
+    width: usize,
+    height: usize,
+    mip_levels: usize,
+    filter: TEX_FILTER_FLAGS,
+) -> Result<ScratchImage> {
+    let mut cell = Plane::new(width, height);
+    for y in 0..height {
+        let source_y = y.saturating_sub(padding).min(sprite.height - 1);
+        for x in 0..width {
+            let source_x = x.saturating_sub(padding).min(sprite.width - 1);
+            cell.pixels[y * width + x] = sprite.pixels[source_y * sprite.width + source_x];
+        }
+    }
+
+    let mut image = ScratchImage::initialize(&float_metadata(width, height, 1))?;
+    cell.write(&mut image, 0)?;
+    if mip_levels > 1 {
+        image = image.generate_mipmaps(mip_levels, filter)?;
+    }
+    Ok(image)
+}
+
+fn float_metadata(width: usize, height: usize, mip_levels: usize) -> TexMetadata {
+    TexMetadata {
+        width,
+        height,
+        depth: 1,
+        arraySize: 1,
+        mipLevels: mip_levels,
+        miscFlags: 0,
+        miscFlags2: 0,
+        format: float_format(),
+        dimension: TEX_DIMENSION::TEX_DIMENSION_TEXTURE2D,
+    }
+}
+
+// Copies every mip of `cell` into `atlas` with its top left corner at (x, y) in the top mip
+fn blit_cell(cell: &ScratchImage, atlas: &mut ScratchImage, x: usize, y: usize) -> Result<()> {
+    for (mip, source) in cell.images().iter().enumerate() {
+        let row_size = source.width() * 16;
+        let (x, y) = (x >> mip, y >> mip);
+        let row_pitch = atlas.images().get(mip).ok_or_else(error::fail)?.row_pitch();
+        let target = atlas.image_buffer_mut(mip).ok_or_else(error::fail)?;
+        let rows = source.buffer().chunks(source.row_pitch()).take(source.height());
+        for (row, source_row) in rows.enumerate() {
+            let start = (y + row) * row_pitch + x * 16;
+            target[start..start + row_size].copy_from_slice(&source_row[..row_size]);
+        }
+    }
+    Ok(())
+}
+
+// Packs the top image of every sprite into one atlas
+pub fn pack(sprites: &[&Image], options: &AtlasOptions) -> Result<Atlas> {
+    let first = sprites.first().ok_or_else(error::invalid_arg)?;
+    if sprites.iter().any(|sprite| sprite.width() == 0 || sprite.height() == 0) {
+        return Err(error::invalid_arg());
+    }
+    let format = match options.format {
+        Some(format) => format,
+        None => decompressed_format(first)?,
+    };
+    let mip_levels = mip_levels(options, sprites);
+    // Block compressed cells also have to cover whole 4x4 blocks in every mip
+    let texels = if is_compressed(format) { 4usize } else { 1 };
+    let block = texels.checked_shl(mip_levels as u32 - 1).ok_or_else(error::invalid_arg)?;
+
+    let cell_sizes: Vec<(usize, usize)> = sprites
+        .iter()
+        .map(|sprite| {
+            let width = (sprite.width() + 2 * options.padding).div_ceil(block);
+            let height = (sprite.height() + 2 * options.padding).div_ceil(block);
+            (width, height)
+        })
+        .collect();
+    let (width, height, cells) = find_layout(options, &cell_sizes, block)?;
+
+    // Only as big as the cells need
+    let used_width = cells.iter().map(Rect::right).max().unwrap_or(1);
+    let used_height = cells.iter().map(Rect::bottom).max().unwrap_or(1);
+    let (width, height) = if options.power_of_two {
+        (used_width.next_power_of_two().min(width), used_height.next_power_of_two().min(height))
+    } else {
+        (used_width, used_height)
+    };
+    let (width, height) = (width * block, height * block);
+
+    let mut atlas = ScratchImage::initialize(&float_metadata(width, height, mip_levels))?;
+    let mut rects = Vec::with_capacity(sprites.len());
+    for (sprite, cell) in sprites.iter().zip(&cells) {
+        let plane = read_sprite(sprite)?;
+        let (x, y) = (cell.x * block, cell.y * block);
+        let (cell_width, cell_height) = (cell.width * block, cell.height * block);
+        let image =
+            build_cell(&plane, options.padding, cell_width, cell_height, mip_levels, options.filter)?;
+        blit_cell(&image, &mut atlas, x, y)?;
+
+        let (x, y) = (x + options.padding, y + options.padding);
+        rects.push(AtlasRect {
+            x,
+            y,
+            width: sprite.width(),
+            height: sprite.height(),
+            u0: x as f32 / width as f32,
+            v0: y as f32 / height as f32,
+            u1: (x + sprite.width()) as f32 / width as f32,
+            v1: (y + sprite.height()) as f32 / height as f32,
+        });
+    }
+
+    let image = if format == float_format() {
+        atlas
+    } else if is_compressed(format) {
+        atlas.compress(format, options.compress_flags)?
+    } else {
+        atlas.convert(format, options.filter)?
+    };
+
+    Ok(Atlas {
+        image,
+        layout: AtlasLayout {
+            width,
+            height,
+            mip_levels,
+            rects,
+        },
+    })
+}
+
+// `pack` with the top image of each ScratchImage
+pub fn pack_images(images: &[ScratchImage], options: &AtlasOptions) -> Result<Atlas> {
+    let sprites: Vec<&Image> = images.iter().map(ScratchImage::image0).collect();
+    pack(&sprites, options)
+}
@@ -0,0 +1,37 @@
+use std::path::Path;
+
+use rayon::prelude::*;
+
+use crate::{Result, ScratchImage};
+
+// Each texture is loaded, processed and returned on a rayon worker thread, results are in the
+// same order as `paths`
+pub fn process<P, F>(paths: &[P], func: F) -> Vec<Result<ScratchImage>>
+where
+    P: AsRef<Path> + Sync,
+    F: Fn(ScratchImage) -> Result<ScratchImage> + Sync + Send,
+{
+    paths
+        .par_iter()
+        .map(|path| {
+            // WIC needs COM on every worker thread. Repeated calls on the same thread return
+            // S_FALSE, and a thread the caller already put in another apartment still has COM
+            #[cfg(feature = "windows")]
+            match crate::util::initialize_com() {
+                Err(err) if err.code() != windows::Win32::Foundation::RPC_E_CHANGED_MODE => {
+                    return Err(err);
+                }
+                _ => {}
+            }
+
+            crate::load(path).and_then(&func)
+        })
+        .collect()
+}
+
+pub fn process_images<F>(images: Vec<ScratchImage>, func: F) -> Vec<Result<ScratchImage>>
+where
+    F: Fn(ScratchImage) -> Result<ScratchImage> + Sync + Send,
+{
+    images.into_par_iter().map(func).collect()
+}
@@ -5,10 +5,16 @@ use std::ops::Deref;
 use crate::error::invalid_arg;
 use crate::Error;
 
-pub struct CWide<'str>(Cow<'str, [u16]>);
+// wchar_t is UTF-16 on Windows and UTF-32 everywhere else
+#[cfg(windows)]
+pub type WChar = u16;
+#[cfg(not(windows))]
+pub type WChar = u32;
+
+pub struct CWide<'str>(Cow<'str, [WChar]>);
 
 impl<'str> Deref for CWide<'str> {
-    type Target = [u16];
+    type Target = [WChar];
 
     #[inline]
     fn deref(&self) -> &Self::Target { &self.0 }
@@ -26,12 +32,10 @@ impl From<&OsStr> for CWide<'static> {
     }
 }
 
-#[cfg(unix)]
-impl From<&std::ffi::OsStr> for CWide<'static> {
+#[cfg(not(windows))]
+impl From<&OsStr> for CWide<'static> {
     #[inline]
-    fn from(value: &core::ffi::OsStr) -> Result<Self, Self::Error> {
-        CWide::from(value.to_string_lossy().as_ref())
-    }
+    fn from(value: &OsStr) -> Self { CWide::from(value.to_string_lossy().as_ref()) }
 }
 
 impl From<&std::path::Path> for CWide<'static> {
@@ -45,6 +49,7 @@ impl From<std::path::PathBuf> for CWide<'static> {
     fn from(value: std::path::PathBuf) -> Self { CWide::from(value.as_os_str()) }
 }
 
+#[cfg(windows)]
 impl From<&str> for CWide<'static> {
     #[inline]
     fn from(from: &str) -> Self {
@@ -54,17 +59,27 @@ impl From<&str> for CWide<'static> {
     }
 }
 
-#[cfg(feature = "windows")]
+#[cfg(not(windows))]
+impl From<&str> for CWide<'static> {
+    #[inline]
+    fn from(from: &str) -> Self {
+        Self(Cow::Owned(
+            from.chars().map(u32::from).chain(iter::once(0)).collect(),
+        ))
+    }
+}
+
+#[cfg(all(windows, feature = "windows"))]
 impl<'str> From<&'str windows::core::HSTRING> for CWide<'str> {
     #[inline]
     fn from(hstr: &'str windows::core::HSTRING) -> Self { Self(Cow::Borrowed(hstr.as_wide())) }
 }
 
-impl<'str> TryFrom<&'str [u16]> for CWide<'str> {
+impl<'str> TryFrom<&'str [WChar]> for CWide<'str> {
     type Error = Error;
 
     #[inline]
-    fn try_from(value: &'str [u16]) -> Result<Self, Self::Error> {
+    fn try_from(value: &'str [WChar]) -> Result<Self, Self::Error> {
         if value.last() == Some(&0) {
             Ok(Self(Cow::Borrowed(value)))
         } else {
@@ -73,11 +88,11 @@ impl<'str> TryFrom<&'str [u16]> for CWide<'str> {
     }
 }
 
-impl TryFrom<Vec<u16>> for CWide<'static> {
+impl TryFrom<Vec<WChar>> for CWide<'static> {
     type Error = Error;
 
     #[inline]
-    fn try_from(value: Vec<u16>) -> Result<Self, Self::Error> {
+    fn try_from(value: Vec<WChar>) -> Result<Self, Self::Error> {
         if value.last() == Some(&0) {
             Ok(Self(Cow::Owned(value)))
         } else {
@@ -0,0 +1,171 @@
+use std::slice;
+
+use image::{DynamicImage, ImageBuffer};
+
+use crate::flags::TEX_FILTER_FLAGS;
+use crate::util::{
+    self,
+    bits_per_color,
+    bits_per_pixel,
+    format_from_raw,
+    format_to_raw,
+    is_compressed,
+    is_srgb,
+};
+use crate::{error, ops, Error, Image, Result, ScratchImage, DXGI_FORMAT};
+
+// The layouts that map directly onto a DynamicImage variant, raw so they can be matched on
+const R32G32B32A32_FLOAT: u32 = format_to_raw(util::DXGI_FORMAT_R32G32B32A32_FLOAT);
+const R32G32B32_FLOAT: u32 = format_to_raw(util::DXGI_FORMAT_R32G32B32_FLOAT);
+const R16G16B16A16_UNORM: u32 = format_to_raw(util::DXGI_FORMAT_R16G16B16A16_UNORM);
+const R8G8B8A8_UNORM: u32 = format_to_raw(util::DXGI_FORMAT_R8G8B8A8_UNORM);
+const R8G8B8A8_UNORM_SRGB: u32 = format_to_raw(util::DXGI_FORMAT_R8G8B8A8_UNORM_SRGB);
+const R16_UNORM: u32 = format_to_raw(util::DXGI_FORMAT_R16_UNORM);
+const R8_UNORM: u32 = format_to_raw(util::DXGI_FORMAT_R8_UNORM);
+
+// Formats that hold values outside of 0..1 and are best kept as floats
+const FLOAT_FORMATS: &[DXGI_FORMAT] = &[
+    util::DXGI_FORMAT_R32G32B32A32_FLOAT,
+    util::DXGI_FORMAT_R32G32B32_FLOAT,
+    util::DXGI_FORMAT_R16G16B16A16_FLOAT,
+    util::DXGI_FORMAT_R32G32_FLOAT,
+    util::DXGI_FORMAT_R11G11B10_FLOAT,
+    util::DXGI_FORMAT_R16G16_FLOAT,
+    util::DXGI_FORMAT_R32_FLOAT,
+    util::DXGI_FORMAT_R16_FLOAT,
+    util::DXGI_FORMAT_R9G9B9E5_SHAREDEXP,
+    util::DXGI_FORMAT_R10G10B10_XR_BIAS_A2_UNORM,
+    util::DXGI_FORMAT_BC6H_UF16,
+    util::DXGI_FORMAT_BC6H_SF16,
+];
+
+fn dynamic_format(format: DXGI_FORMAT) -> u32 {
+    match format_to_raw(format) {
+        raw @ (R32G32B32A32_FLOAT | R32G32B32_FLOAT | R16G16B16A16_UNORM | R8G8B8A8_UNORM
+        | R8G8B8A8_UNORM_SRGB | R16_UNORM | R8_UNORM) => raw,
+        _ if FLOAT_FORMATS.contains(&format) => R32G32B32A32_FLOAT,
+        _ if is_srgb(format) => R8G8B8A8_UNORM_SRGB,
+        _ if bits_per_color(format) > 8 => R16G16B16A16_UNORM,
+        _ => R8G8B8A8_UNORM,
+    }
+}
+
+// Copies the visible part of every row, dropping any row pitch padding
+fn packed_pixels(image: &Image) -> Vec<u8> {
+    let row_size = image.width() * bits_per_pixel(image.format()) / 8;
+    image
+        .buffer()
+        .chunks(image.row_pitch())
+        .take(image.height())
+        .flat_map(|row| &row[..row_size])
+        .copied()
+        .collect()
+}
+
+fn to_u16(bytes: &[u8]) -> Vec<u16> {
+    bytes
+        .chunks_exact(2)
+        .map(|chunk| u16::from_ne_bytes([chunk[0], chunk[1]]))
+        .collect()
+}
+
+fn to_f32(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_ne_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+fn from_pixels(image: &Image) -> Result<DynamicImage> {
+    let width = u32::try_from(image.width()).map_err(|_| error::invalid_arg())?;
+    let height = u32::try_from(image.height()).map_err(|_| error::invalid_arg())?;
+    let pixels = packed_pixels(image);
+
+    match format_to_raw(image.format()) {
+        R8G8B8A8_UNORM | R8G8B8A8_UNORM_SRGB => {
+            ImageBuffer::from_raw(width, height, pixels).map(DynamicImage::ImageRgba8)
+        }
+        R8_UNORM => ImageBuffer::from_raw(width, height, pixels).map(DynamicImage::ImageLuma8),
+        R16G16B16A16_UNORM => {
+            ImageBuffer::from_raw(width, height, to_u16(&pixels)).map(DynamicImage::ImageRgba16)
+        }
+        R16_UNORM => {
+            ImageBuffer::from_raw(width, height, to_u16(&pixels)).map(DynamicImage::ImageLuma16)
+        }
+        R32G32B32A32_FLOAT => {
+            ImageBuffer::from_raw(width, height, to_f32(&pixels)).map(DynamicImage::ImageRgba32F)
+        }
+        R32G32B32_FLOAT => {
+            ImageBuffer::from_raw(width, height, to_f32(&pixels)).map(DynamicImage::ImageRgb32F)
+        }
+        _ => None,
+    }
+    .ok_or_else(error::fail)
+}
+
+// Decompresses and converts to the closest layout the image crate has, HDR formats become 32-bit
+// floats. Red and green formats are expanded to RGBA, the image crate's two channel images are
+// luma and alpha. Values are copied without changing their transfer function: DynamicImage has no
+// color space, so _SRGB formats stay sRGB encoded and UNORM formats stay linear even though the
+// image crate treats 8-bit color as sRGB.
+impl TryFrom<&Image<'_>> for DynamicImage {
+    type Error = Error;
+
+    fn try_from(image: &Image<'_>) -> Result<Self> {
+        let decompressed;
+        let image = if is_compressed(image.format()) {
+            decompressed = ops::decompress(slice::from_ref(image), &image.metadata())?;
+            decompressed.image0()
+        } else {
+            image
+        };
+
+        let target = format_from_raw(dynamic_format(image.format()));
+        if image.format() == target {
+            return from_pixels(image);
+        }
+
+        let converted = ops::convert(
+            slice::from_ref(image),
+            &image.metadata(),
+            target,
+            TEX_FILTER_FLAGS::default(),
+        )?;
+        from_pixels(converted.image0())
+    }
+}
+
+impl TryFrom<&ScratchImage> for DynamicImage {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(image: &ScratchImage) -> Result<Self> { Self::try_from(image.image0()) }
+}
+
+// 8-bit color and luma are treated as sRGB like everywhere else in the image crate and become
+// R8G8B8A8_UNORM_SRGB, since DXGI has no single channel _SRGB format. 16-bit luma becomes
+// R16_UNORM, the rest of 16-bit color and floats become linear RGBA formats: DXGI has no 24 or
+// 48-bit formats and no luma and alpha formats.
+impl TryFrom<&DynamicImage> for ScratchImage {
+    type Error = Error;
+
+    fn try_from(image: &DynamicImage) -> Result<Self> {
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let new_2d = |format: u32, data: &[u8]| {
+            ScratchImage::new_2d(format_from_raw(format), width, height, 1, 1, data)
+        };
+
+        match image {
+            DynamicImage::ImageRgba8(_) => new_2d(R8G8B8A8_UNORM_SRGB, image.as_bytes()),
+            DynamicImage::ImageLuma16(_) => new_2d(R16_UNORM, image.as_bytes()),
+            DynamicImage::ImageRgba16(_) => new_2d(R16G16B16A16_UNORM, image.as_bytes()),
+            DynamicImage::ImageRgb32F(_) => new_2d(R32G32B32_FLOAT, image.as_bytes()),
+            DynamicImage::ImageRgba32F(_) => new_2d(R32G32B32A32_FLOAT, image.as_bytes()),
+            DynamicImage::ImageRgb16(_) | DynamicImage::ImageLumaA16(_) => {
+                let rgba = DynamicImage::ImageRgba16(image.to_rgba16());
+                new_2d(R16G16B16A16_UNORM, rgba.as_bytes())
+            }
+            _ => new_2d(R8G8B8A8_UNORM_SRGB, &image.to_rgba8()),
+        }
+    }
+}
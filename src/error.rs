@@ -1,10 +1,32 @@
 #![cfg(feature = "windows")]
 
 pub use windows::core::{Error, Result};
- use windows::Win32::Foundation::{E_FAIL, E_INVALIDARG};
+use windows::core::HRESULT;
+use windows::Win32::Foundation::{E_ABORT, E_FAIL, E_INVALIDARG, ERROR_NOT_SUPPORTED};
+
+// The customer bit is set, so neither Windows nor DirectXTex return this code
+const E_LIMIT_EXCEEDED: HRESULT = HRESULT(0xA004_0001_u32 as i32);
 
 pub fn invalid_arg() -> Error { E_INVALIDARG.into() }
 pub fn fail() -> Error { E_FAIL.into() }
+pub fn aborted() -> Error { E_ABORT.into() }
+pub fn not_supported() -> Error { ERROR_NOT_SUPPORTED.to_hresult().into() }
+pub fn limit_exceeded() -> Error { E_LIMIT_EXCEEDED.into() }
+
+pub fn is_limit_exceeded(error: &Error) -> bool { error.code() == E_LIMIT_EXCEEDED }
+
+// Keeps the code of `error`, with a description of what went wrong
+pub fn with_message(error: &Error, message: &str) -> Error {
+    Error::new(error.code(), message.into())
+}
+
+pub fn io(error: &std::io::Error) -> Error {
+    #[cfg(windows)]
+    let code = error.raw_os_error().map(|code| windows::core::HRESULT::from_win32(code as u32));
+    #[cfg(not(windows))]
+    let code = None;
+    Error::new(code.unwrap_or(E_FAIL), error.to_string().as_str().into())
+}
 
 pub fn hresult(hr: windows::core::HRESULT) -> Result<()> {
     hr.ok()
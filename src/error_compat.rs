@@ -1,30 +1,66 @@
-use directxtex_sys::HRESULT;
+// Same as directxtex_sys::HRESULT, which isn't available without the directxtex feature
+#[allow(clippy::upper_case_acronyms)]
+pub type HRESULT = i32;
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 #[allow(overflowing_literals)]
 const E_INVALIDARG: HRESULT = 0x8007_0057;
 #[allow(overflowing_literals)]
-const E_FAIL: HRESULT = 0x8000_40005;
+const E_FAIL: HRESULT = 0x8000_4005;
+#[allow(overflowing_literals)]
+const E_ABORT: HRESULT = 0x8000_4004;
+// The customer bit is set, so neither Windows nor DirectXTex return this code
+#[allow(overflowing_literals)]
+const E_LIMIT_EXCEEDED: HRESULT = 0xA004_0001;
+#[allow(overflowing_literals)]
+const ERROR_NOT_SUPPORTED: HRESULT = 0x8007_0032;
+
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Error {
+    code:    HRESULT,
+    message: Option<Box<str>>,
+}
+
+impl Error {
+    pub fn new(code: HRESULT, message: &str) -> Self {
+        Self {
+            code,
+            message: Some(message.into()),
+        }
+    }
 
-#[derive(Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
-#[repr(transparent)]
-pub struct Error(pub HRESULT);
+    #[inline]
+    #[must_use]
+    pub const fn code(&self) -> HRESULT { self.code }
+
+    #[inline]
+    #[must_use]
+    pub fn message(&self) -> &str { self.message.as_deref().unwrap_or_default() }
+}
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Error code 0x{:08x}", self.0)
+        match &self.message {
+            Some(message) => write!(f, "{message} (0x{:08x})", self.code),
+            None => write!(f, "Error code 0x{:08x}", self.code),
+        }
     }
 }
 
 impl std::fmt::Debug for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "HRESULT(0x{:08x})", self.0)
+        match &self.message {
+            Some(message) => write!(f, "HRESULT(0x{:08x}, {message:?})", self.code),
+            None => write!(f, "HRESULT(0x{:08x})", self.code),
+        }
     }
 }
 
+impl std::error::Error for Error {}
+
 impl From<HRESULT> for Error {
-    fn from(hr: HRESULT) -> Self { Self(hr) }
+    fn from(code: HRESULT) -> Self { Self { code, message: None } }
 }
 
 impl From<std::convert::Infallible> for Error {
@@ -33,7 +69,18 @@ impl From<std::convert::Infallible> for Error {
 
 pub fn invalid_arg() -> Error { E_INVALIDARG.into() }
 pub fn fail() -> Error { E_FAIL.into() }
+pub fn aborted() -> Error { E_ABORT.into() }
+pub fn not_supported() -> Error { ERROR_NOT_SUPPORTED.into() }
+pub fn limit_exceeded() -> Error { E_LIMIT_EXCEEDED.into() }
+
+pub fn is_limit_exceeded(error: &Error) -> bool { error.code == E_LIMIT_EXCEEDED }
+
+// Keeps the code of `error`, with a description of what went wrong
+pub fn with_message(error: &Error, message: &str) -> Error { Error::new(error.code, message) }
+
+// There are no Win32 error codes for OS errors here, the message keeps the details
+pub fn io(error: &std::io::Error) -> Error { Error::new(E_FAIL, &error.to_string()) }
 
-pub const fn hresult(hr: directxtex_sys::HRESULT) -> Result<(), Error> {
-    if hr == 0 { Ok(()) } else { Err(Error(hr)) }
+pub fn hresult(hr: HRESULT) -> Result<(), Error> {
+    if hr == 0 { Ok(()) } else { Err(hr.into()) }
 }
@@ -0,0 +1,264 @@
+// Every subresource of a ScratchImage as its own file, for formats that only hold one image.
+// File names come from a pattern with these placeholders:
+// - {mip}: the mip level
+// - {item}: the array item, cube faces count as items like in DirectXTex
+// - {face}, {cube}: item % 6 and item / 6, only for cubemaps
+// - {slice}: the depth slice, only for volumes
+// Multi-frame containers (TIFF and GIF through WIC) hold every item or slice of a mip in one file
+// when the pattern doesn't tell them apart.
+
+use std::path::{Path, PathBuf};
+
+use directxtex_sys::{TexMetadata, TEX_DIMENSION};
+
+use crate::util::{image_index, TEX_MISC_TEXTURECUBE};
+use crate::{error, FileType, LoadLimits, LoadOptions, Result, ScratchImage, DXGI_FORMAT};
+
+const MIP: &str = "{mip}";
+const ITEM: &str = "{item}";
+const FACE: &str = "{face}";
+const CUBE: &str = "{cube}";
+const SLICE: &str = "{slice}";
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FilePattern {
+    pattern: String,
+}
+
+impl FilePattern {
+    // The pattern has to be valid UTF-8
+    pub fn new(pattern: impl AsRef<Path>) -> Result<Self> {
+        let pattern = pattern.as_ref().to_str().ok_or_else(error::invalid_arg)?;
+        Ok(Self {
+            pattern: pattern.to_owned(),
+        })
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn as_str(&self) -> &str { &self.pattern }
+
+    #[must_use]
+    pub fn path(&self, mip: usize, item: usize, slice: usize) -> PathBuf {
+        PathBuf::from(
+            self.pattern
+                .replace(MIP, &mip.to_string())
+                .replace(ITEM, &item.to_string())
+                .replace(FACE, &(item % 6).to_string())
+                .replace(CUBE, &(item / 6).to_string())
+                .replace(SLICE, &slice.to_string()),
+        )
+    }
+
+    fn has(&self, placeholder: &str) -> bool { self.pattern.contains(placeholder) }
+
+    // Nothing tells the items or slices of a mip apart
+    fn is_per_mip(&self) -> bool { ![ITEM, FACE, CUBE, SLICE].iter().any(|name| self.has(name)) }
+}
+
+#[cfg(windows)]
+fn is_multi_frame(file_type: FileType) -> bool {
+    use crate::wic::{WIC_CODEC_GIF, WIC_CODEC_TIFF};
+
+    matches!(file_type, FileType::Wic(codec) if codec == WIC_CODEC_TIFF || codec == WIC_CODEC_GIF)
+}
+
+#[cfg(not(windows))]
+fn is_multi_frame(_: FileType) -> bool { false }
+
+fn is_volume(metadata: &TexMetadata) -> bool {
+    metadata.dimension == TEX_DIMENSION::TEX_DIMENSION_TEXTURE3D
+}
+
+// Items, or depth slices for volumes, in `mip`
+fn layers(metadata: &TexMetadata, mip: usize) -> usize {
+    if is_volume(metadata) {
+        (metadata.depth >> mip).max(1)
+    } else {
+        metadata.arraySize
+    }
+}
+
+fn item_and_slice(metadata: &TexMetadata, layer: usize) -> (usize, usize) {
+    if is_volume(metadata) { (0, layer) } else { (layer, 0) }
+}
+
+// Errors when the pattern would give two subresources the same name, or names a cube face or
+// slice the image doesn't have
+fn validate(pattern: &FilePattern, metadata: &TexMetadata, multi_frame: bool) -> Result<()> {
+    let is_cubemap = metadata.miscFlags & TEX_MISC_TEXTURECUBE != 0;
+    if (pattern.has(FACE) || pattern.has(CUBE)) && !is_cubemap
+        || pattern.has(SLICE) && !is_volume(metadata)
+        || metadata.mipLevels > 1 && !pattern.has(MIP)
+    {
+        return Err(error::invalid_arg());
+    }
+
+    let layers_named = if multi_frame && pattern.is_per_mip() {
+        true
+    } else if is_volume(metadata) {
+        metadata.depth == 1 || pattern.has(SLICE)
+    } else {
+        metadata.arraySize == 1
+            || pattern.has(ITEM)
+            || pattern.has(FACE) && (metadata.arraySize == 6 || pattern.has(CUBE))
+    };
+    if layers_named { Ok(()) } else { Err(error::invalid_arg()) }
+}
+
+// Writes every subresource of `image` as `file_type` and returns the paths written
+pub fn save_all(
+    image: &ScratchImage,
+    pattern: impl AsRef<Path>,
+    file_type: FileType,
+) -> Result<Vec<PathBuf>> {
+    let pattern = FilePattern::new(pattern)?;
+    let metadata = image.metadata();
+    let frames = is_multi_frame(file_type) && pattern.is_per_mip();
+    validate(&pattern, metadata, frames)?;
+
+    let mut written = Vec::new();
+    for mip in 0..metadata.mipLevels {
+        if frames {
+            let path = pattern.path(mip, 0, 0);
+            save_frames(image, mip, &path, file_type)?;
+            written.push(path);
+            continue;
+        }
+        for layer in 0..layers(metadata, mip) {
+            let (item, slice) = item_and_slice(metadata, layer);
+            let source = image.image(mip, item, slice).ok_or_else(error::fail)?;
+            let path = pattern.path(mip, item, slice);
+            crate::save_as(&path, file_type, source, metadata)?;
+            written.push(path);
+        }
+    }
+    Ok(written)
+}
+
+#[cfg(windows)]
+fn save_frames(image: &ScratchImage, mip: usize, path: &Path, file_type: FileType) -> Result<()> {
+    use crate::flags::WIC_FLAGS;
+
+    let FileType::Wic(container) = file_type else { return Err(error::invalid_arg()) };
+    let metadata = image.metadata();
+    let frames = (0..layers(metadata, mip))
+        .map(|layer| {
+            let (item, slice) = item_and_slice(metadata, layer);
+            let frame = image.image(mip, item, slice).ok_or_else(error::fail)?;
+            // Safety: The copies don't outlive `image`, which owns the pixels
+            Ok(unsafe { crate::Image::from_sys(*frame.as_ptr()) })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    crate::wic::save_slice(path, container, &frames, WIC_FLAGS::default())
+}
+
+#[cfg(not(windows))]
+fn save_frames(_: &ScratchImage, _: usize, _: &Path, _: FileType) -> Result<()> {
+    Err(error::not_supported())
+}
+
+// Without limits like `load`, converted to `format` when given
+fn load_file(path: &Path, format: Option<DXGI_FORMAT>) -> Result<ScratchImage> {
+    let mut options = LoadOptions::new().limits(LoadLimits::UNLIMITED);
+    #[cfg(windows)]
+    {
+        options = options.all_frames();
+    }
+    if let Some(format) = format {
+        options = options.format(format);
+    }
+    crate::load_with(path, &options)
+}
+
+// Counts 0, 1, 2... while `exists`, at least 1
+fn count(exists: impl Fn(usize) -> bool) -> usize { 1 + (1..).take_while(|&n| exists(n)).count() }
+
+// Reassembles the files written by `save_all`. The layout comes from the placeholders and the
+// files that exist: mips and items are counted up from 0, {face} makes a cubemap and {slice} a
+// volume. With no placeholder for items, the items are the frames of each file. Files in a
+// different format than the first are converted to it.
+pub fn load_all(pattern: impl AsRef<Path>) -> Result<ScratchImage> {
+    let pattern = FilePattern::new(pattern)?;
+    let exists = |mip, item, slice| pattern.path(mip, item, slice).exists();
+    let first = load_file(&pattern.path(0, 0, 0), None)?;
+
+    let mip_levels = if pattern.has(MIP) { count(|mip| exists(mip, 0, 0)) } else { 1 };
+    let (array_size, depth, misc_flags) = if pattern.has(SLICE) {
+        (1, count(|slice| exists(0, 0, slice)), 0)
+    } else if pattern.has(ITEM) {
+        (count(|item| exists(0, item, 0)), 1, 0)
+    } else if pattern.has(FACE) {
+        let cubes = if pattern.has(CUBE) { count(|cube| exists(0, cube * 6, 0)) } else { 1 };
+        (cubes * 6, 1, TEX_MISC_TEXTURECUBE)
+    } else {
+        (first.array_size(), 1, 0)
+    };
+    let metadata = TexMetadata {
+        depth,
+        arraySize: array_size,
+        mipLevels: mip_levels,
+        miscFlags: misc_flags,
+        dimension: if pattern.has(SLICE) {
+            TEX_DIMENSION::TEX_DIMENSION_TEXTURE3D
+        } else {
+            first.metadata().dimension
+        },
+        ..*first.metadata()
+    };
+
+    let load = |mip, item, slice| {
+        load_file(&pattern.path(mip, item, slice), Some(metadata.format))
+    };
+    let mut out = ScratchImage::initialize(&metadata)?;
+    for mip in 0..mip_levels {
+        if pattern.is_per_mip() {
+            let loaded;
+            let frames = if mip == 0 {
+                &first
+            } else {
+                loaded = load(mip, 0, 0)?;
+                &loaded
+            };
+            for layer in 0..layers(&metadata, mip) {
+                let (item, slice) = item_and_slice(&metadata, layer);
+                copy_image(frames, layer, &mut out, image_index(&metadata, mip, item, slice))?;
+            }
+            continue;
+        }
+        for layer in 0..layers(&metadata, mip) {
+            let (item, slice) = item_and_slice(&metadata, layer);
+            let loaded;
+            let source = if mip == 0 && layer == 0 {
+                &first
+            } else {
+                loaded = load(mip, item, slice)?;
+                &loaded
+            };
+            copy_image(source, 0, &mut out, image_index(&metadata, mip, item, slice))?;
+        }
+    }
+    Ok(out)
+}
+
+// Copies the top mip of `item` in `source` into image `index` of `out`, which has to have the
+// same dimensions
+fn copy_image(
+    source: &ScratchImage,
+    item: usize,
+    out: &mut ScratchImage,
+    index: usize,
+) -> Result<()> {
+    let image = source.image(0, item, 0).ok_or_else(error::invalid_arg)?;
+    let target = out.images().get(index).ok_or_else(error::fail)?;
+    let layout = (image.format(), image.width(), image.height());
+    if layout != (target.format(), target.width(), target.height()) {
+        return Err(error::invalid_arg());
+    }
+    let buffer = out.image_buffer_mut(index).ok_or_else(error::fail)?;
+    if buffer.len() != image.buffer().len() {
+        return Err(error::invalid_arg());
+    }
+    buffer.copy_from_slice(image.buffer());
+    Ok(())
+}
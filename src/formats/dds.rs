@@ -1,10 +1,15 @@
+use std::path::Path;
 use std::ptr;
 
 use directxtex_sys::{self as sys, TexMetadata, DDS_FLAGS};
 
 use crate::error::hresult;
+use crate::formats::read_file_with_limits;
+use crate::limits::LoadLimits;
 use crate::{Blob, CWide, Error, Image, Result, ScratchImage};
 
+#[cfg(feature = "pure-rust-dds")] pub mod header;
+
 #[inline]
 pub fn metadata<'file_name, CWIDE>(file_name: CWIDE, dds_flags: DDS_FLAGS) -> Result<TexMetadata>
 where
@@ -57,6 +62,25 @@ pub fn load_from_memory(buffer: &[u8], dds_flags: DDS_FLAGS) -> Result<ScratchIm
     }
 }
 
+// `load` after checking `limits` against the header
+pub fn load_with_limits(
+    file_name: impl AsRef<Path>,
+    dds_flags: DDS_FLAGS,
+    limits: &LoadLimits,
+) -> Result<ScratchImage> {
+    let buffer = read_file_with_limits(file_name.as_ref(), limits)?;
+    load_from_memory_with_limits(&buffer, dds_flags, limits)
+}
+
+pub fn load_from_memory_with_limits(
+    buffer: &[u8],
+    dds_flags: DDS_FLAGS,
+    limits: &LoadLimits,
+) -> Result<ScratchImage> {
+    limits.check(&metadata_from_memory(buffer, dds_flags)?)?;
+    load_from_memory(buffer, dds_flags)
+}
+
 #[inline]
 pub fn save<'file_name, CWIDE>(file_name: CWIDE, image: &Image, dds_flags: DDS_FLAGS) -> Result<()>
 where
@@ -94,7 +118,7 @@ where
 pub fn save_to_memory(image: &Image, dds_flags: DDS_FLAGS) -> Result<Blob> {
     let mut blob = sys::Blob::default();
     hresult(unsafe { sys::SaveToDDSMemory(image.as_ptr(), dds_flags, &mut blob) })
-        .map(|_| Blob(blob))
+        .map(|_| Blob::owned(blob))
 }
 
 #[inline]
@@ -113,5 +137,5 @@ pub fn save_slice_to_memory(
             &mut blob,
         )
     })
-    .map(|_| Blob(blob))
+    .map(|_| Blob::owned(blob))
 }
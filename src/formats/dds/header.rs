@@ -0,0 +1,539 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::util::{format_bits_per_pixel, format_from_raw, format_to_raw, TEX_MISC_TEXTURECUBE};
+use crate::{error, Result, TexMetadata, TEX_DIMENSION};
+
+// Parses and writes DDS headers, including the DX10 extension, without going through DirectXTex.
+// Follows DirectXTex's reading of legacy headers, where formats that DirectXTex expands on load
+// (24-bit RGB, 3:3:2, palettized, ...) report the format they are expanded to.
+
+pub const MAGIC: [u8; 4] = *b"DDS ";
+// Magic and DDS_HEADER
+pub const HEADER_SIZE: usize = 128;
+pub const DX10_HEADER_SIZE: usize = 20;
+
+const DDS_HEADER_SIZE: u32 = 124;
+const DDS_PIXELFORMAT_SIZE: u32 = 32;
+
+const DDSD_CAPS: u32 = 0x1;
+const DDSD_HEIGHT: u32 = 0x2;
+const DDSD_WIDTH: u32 = 0x4;
+const DDSD_PITCH: u32 = 0x8;
+const DDSD_PIXELFORMAT: u32 = 0x1000;
+const DDSD_MIPMAPCOUNT: u32 = 0x2_0000;
+const DDSD_LINEARSIZE: u32 = 0x8_0000;
+const DDSD_DEPTH: u32 = 0x80_0000;
+
+const DDPF_ALPHAPIXELS: u32 = 0x1;
+const DDPF_ALPHA: u32 = 0x2;
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_PAL8: u32 = 0x20;
+const DDPF_RGB: u32 = 0x40;
+const DDPF_LUMINANCE: u32 = 0x2_0000;
+const DDPF_BUMPDUDV: u32 = 0x8_0000;
+
+const DDSCAPS_COMPLEX: u32 = 0x8;
+const DDSCAPS_TEXTURE: u32 = 0x1000;
+const DDSCAPS_MIPMAP: u32 = 0x40_0000;
+
+const DDSCAPS2_CUBEMAP: u32 = 0x200;
+const DDSCAPS2_CUBEMAP_ALLFACES: u32 = 0xfc00;
+const DDSCAPS2_VOLUME: u32 = 0x20_0000;
+
+const DDS_RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
+const DDS_MISC_FLAGS2_ALPHA_MODE_MASK: u32 = 0x7;
+
+// TEX_ALPHA_MODE_PREMULTIPLIED
+const TEX_ALPHA_MODE_PREMULTIPLIED: u32 = 2;
+
+const fn fourcc(code: &[u8; 4]) -> u32 { u32::from_le_bytes(*code) }
+
+const FOURCC_DX10: u32 = fourcc(b"DX10");
+
+enum Legacy {
+    FourCC(u32),
+    // DDPF flags without DDPF_ALPHAPIXELS, bit count and the R, G, B and A masks
+    Masks(u32, u32, [u32; 4]),
+}
+
+// The reading side of DirectXTex's legacy format map, as (DXGI_FORMAT, premultiplied, pixel format)
+#[rustfmt::skip]
+static LEGACY_FORMATS: &[(u32, bool, Legacy)] = &[
+    (71, false, Legacy::FourCC(fourcc(b"DXT1"))),
+    (74, false, Legacy::FourCC(fourcc(b"DXT3"))),
+    (77, false, Legacy::FourCC(fourcc(b"DXT5"))),
+    (74, true, Legacy::FourCC(fourcc(b"DXT2"))),
+    (77, true, Legacy::FourCC(fourcc(b"DXT4"))),
+    (80, false, Legacy::FourCC(fourcc(b"BC4U"))),
+    (81, false, Legacy::FourCC(fourcc(b"BC4S"))),
+    (83, false, Legacy::FourCC(fourcc(b"BC5U"))),
+    (84, false, Legacy::FourCC(fourcc(b"BC5S"))),
+    (80, false, Legacy::FourCC(fourcc(b"ATI1"))),
+    (83, false, Legacy::FourCC(fourcc(b"ATI2"))),
+    (95, false, Legacy::FourCC(fourcc(b"BC6H"))),
+    (98, false, Legacy::FourCC(fourcc(b"BC7L"))),
+    (98, false, Legacy::FourCC(fourcc(b"BC70"))),
+    (68, false, Legacy::FourCC(fourcc(b"RGBG"))),
+    (69, false, Legacy::FourCC(fourcc(b"GRGB"))),
+    (107, false, Legacy::FourCC(fourcc(b"YUY2"))),
+    (107, false, Legacy::FourCC(fourcc(b"UYVY"))),
+    // D3DFORMAT values used as FourCCs
+    (11, false, Legacy::FourCC(36)),
+    (13, false, Legacy::FourCC(110)),
+    (54, false, Legacy::FourCC(111)),
+    (34, false, Legacy::FourCC(112)),
+    (10, false, Legacy::FourCC(113)),
+    (41, false, Legacy::FourCC(114)),
+    (16, false, Legacy::FourCC(115)),
+    (2, false, Legacy::FourCC(116)),
+    (87, false, Legacy::Masks(DDPF_RGB, 32, [0x00ff_0000, 0x0000_ff00, 0x0000_00ff, 0xff00_0000])),
+    (88, false, Legacy::Masks(DDPF_RGB, 32, [0x00ff_0000, 0x0000_ff00, 0x0000_00ff, 0])),
+    (28, false, Legacy::Masks(DDPF_RGB, 32, [0x0000_00ff, 0x0000_ff00, 0x00ff_0000, 0xff00_0000])),
+    (28, false, Legacy::Masks(DDPF_RGB, 32, [0x0000_00ff, 0x0000_ff00, 0x00ff_0000, 0])),
+    (35, false, Legacy::Masks(DDPF_RGB, 32, [0x0000_ffff, 0xffff_0000, 0, 0])),
+    // D3DX writes A2B10G10R10 with the masks swapped
+    (24, false, Legacy::Masks(DDPF_RGB, 32, [0x3ff0_0000, 0x000f_fc00, 0x0000_03ff, 0xc000_0000])),
+    (24, false, Legacy::Masks(DDPF_RGB, 32, [0x0000_03ff, 0x000f_fc00, 0x3ff0_0000, 0xc000_0000])),
+    (41, false, Legacy::Masks(DDPF_RGB, 32, [0xffff_ffff, 0, 0, 0])),
+    (87, false, Legacy::Masks(DDPF_RGB, 24, [0x00ff_0000, 0x0000_ff00, 0x0000_00ff, 0])),
+    (85, false, Legacy::Masks(DDPF_RGB, 16, [0xf800, 0x07e0, 0x001f, 0])),
+    (86, false, Legacy::Masks(DDPF_RGB, 16, [0x7c00, 0x03e0, 0x001f, 0x8000])),
+    (86, false, Legacy::Masks(DDPF_RGB, 16, [0x7c00, 0x03e0, 0x001f, 0])),
+    (28, false, Legacy::Masks(DDPF_RGB, 16, [0x00e0, 0x001c, 0x0003, 0xff00])),
+    (85, false, Legacy::Masks(DDPF_RGB, 8, [0xe0, 0x1c, 0x03, 0])),
+    (115, false, Legacy::Masks(DDPF_RGB, 16, [0x0f00, 0x00f0, 0x000f, 0xf000])),
+    (115, false, Legacy::Masks(DDPF_RGB, 16, [0x0f00, 0x00f0, 0x000f, 0])),
+    // NVTT writes luminance as RGB
+    (61, false, Legacy::Masks(DDPF_RGB, 8, [0xff, 0, 0, 0])),
+    (56, false, Legacy::Masks(DDPF_RGB, 16, [0xffff, 0, 0, 0])),
+    (49, false, Legacy::Masks(DDPF_RGB, 16, [0x00ff, 0, 0, 0xff00])),
+    (61, false, Legacy::Masks(DDPF_LUMINANCE, 8, [0xff, 0, 0, 0])),
+    (56, false, Legacy::Masks(DDPF_LUMINANCE, 16, [0xffff, 0, 0, 0])),
+    (49, false, Legacy::Masks(DDPF_LUMINANCE, 16, [0x00ff, 0, 0, 0xff00])),
+    (49, false, Legacy::Masks(DDPF_LUMINANCE, 8, [0x00ff, 0, 0, 0xff00])),
+    (115, false, Legacy::Masks(DDPF_LUMINANCE, 8, [0x0f, 0, 0, 0xf0])),
+    (65, false, Legacy::Masks(DDPF_ALPHA, 8, [0, 0, 0, 0xff])),
+    (28, false, Legacy::Masks(DDPF_PAL8, 8, [0, 0, 0, 0])),
+    (28, false, Legacy::Masks(DDPF_PAL8, 16, [0, 0, 0, 0])),
+    (51, false, Legacy::Masks(DDPF_BUMPDUDV, 16, [0x00ff, 0xff00, 0, 0])),
+    (31, false, Legacy::Masks(DDPF_BUMPDUDV, 32, [0x0000_00ff, 0x0000_ff00, 0x00ff_0000, 0xff00_0000])),
+    (37, false, Legacy::Masks(DDPF_BUMPDUDV, 32, [0x0000_ffff, 0xffff_0000, 0, 0])),
+    (24, false, Legacy::Masks(DDPF_BUMPDUDV, 32, [0x3ff0_0000, 0x000f_fc00, 0x0000_03ff, 0xc000_0000])),
+];
+
+fn read_u32(buffer: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([buffer[at], buffer[at + 1], buffer[at + 2], buffer[at + 3]])
+}
+
+// `pixel_format` is the 32 bytes of DDS_PIXELFORMAT
+fn legacy_format(pixel_format: &[u8]) -> Option<(u32, bool)> {
+    let flags = read_u32(pixel_format, 4);
+    let fourcc = read_u32(pixel_format, 8);
+    let bit_count = read_u32(pixel_format, 12);
+    let masks = [16, 20, 24, 28].map(|at| read_u32(pixel_format, at));
+
+    LEGACY_FORMATS
+        .iter()
+        .find(|(.., legacy)| match *legacy {
+            Legacy::FourCC(code) => flags & DDPF_FOURCC != 0 && fourcc == code,
+            Legacy::Masks(kind, bits, known) => {
+                flags & DDPF_FOURCC == 0
+                    && flags & !DDPF_ALPHAPIXELS == kind
+                    && bit_count == bits
+                    && masks == known
+            }
+        })
+        .map(|(format, premultiplied, _)| (*format, *premultiplied))
+}
+
+const fn max_mip_levels(width: usize, height: usize, depth: usize) -> usize {
+    let largest = if width > height { width } else { height };
+    let largest = if largest > depth { largest } else { depth };
+    (usize::BITS - largest.leading_zeros()) as usize
+}
+
+// Returns the metadata and the offset of the pixel data
+pub fn parse(buffer: &[u8]) -> Result<(TexMetadata, usize)> {
+    if buffer.len() < HEADER_SIZE || buffer[..4] != MAGIC {
+        return Err(error::fail());
+    }
+    let header = &buffer[4..HEADER_SIZE];
+    if read_u32(header, 0) != DDS_HEADER_SIZE || read_u32(header, 72) != DDS_PIXELFORMAT_SIZE {
+        return Err(error::fail());
+    }
+
+    let flags = read_u32(header, 4);
+    let height = read_u32(header, 8) as usize;
+    let width = read_u32(header, 12) as usize;
+    let depth = read_u32(header, 20) as usize;
+    let mip_levels = (read_u32(header, 24) as usize).max(1);
+    let pixel_format = &header[72..104];
+    let caps2 = read_u32(header, 108);
+
+    let mut metadata = TexMetadata {
+        width,
+        height,
+        depth: 1,
+        arraySize: 1,
+        mipLevels: mip_levels,
+        ..TexMetadata::default()
+    };
+
+    let pf_flags = read_u32(pixel_format, 4);
+    let offset = if pf_flags & DDPF_FOURCC != 0 && read_u32(pixel_format, 8) == FOURCC_DX10 {
+        let dx10 = buffer
+            .get(HEADER_SIZE..HEADER_SIZE + DX10_HEADER_SIZE)
+            .ok_or_else(error::fail)?;
+        let format = read_u32(dx10, 0);
+        let dimension = read_u32(dx10, 4);
+        let misc_flag = read_u32(dx10, 8);
+        let array_size = read_u32(dx10, 12) as usize;
+
+        if array_size == 0 {
+            return Err(error::fail());
+        }
+        // Palettized formats can't be stored in a DX10 header
+        if format_bits_per_pixel(format_from_raw(format)) == 0 || (111..=114).contains(&format) {
+            return Err(error::not_supported());
+        }
+
+        metadata.format = format_from_raw(format);
+        metadata.arraySize = array_size;
+        metadata.miscFlags2 = read_u32(dx10, 16);
+
+        match dimension {
+            2 => {
+                // D3DX writes 1D textures with a height of 1
+                if flags & DDSD_HEIGHT != 0 && height != 1 {
+                    return Err(error::fail());
+                }
+                metadata.height = 1;
+                metadata.dimension = TEX_DIMENSION::TEX_DIMENSION_TEXTURE1D;
+            }
+            3 => {
+                if misc_flag & DDS_RESOURCE_MISC_TEXTURECUBE != 0 {
+                    metadata.miscFlags |= TEX_MISC_TEXTURECUBE;
+                    metadata.arraySize = array_size.checked_mul(6).ok_or_else(error::fail)?;
+                }
+                metadata.dimension = TEX_DIMENSION::TEX_DIMENSION_TEXTURE2D;
+            }
+            4 => {
+                if flags & DDSD_DEPTH == 0 {
+                    return Err(error::fail());
+                }
+                if array_size > 1 {
+                    return Err(error::not_supported());
+                }
+                metadata.depth = depth;
+                metadata.dimension = TEX_DIMENSION::TEX_DIMENSION_TEXTURE3D;
+            }
+            _ => return Err(error::not_supported()),
+        }
+
+        HEADER_SIZE + DX10_HEADER_SIZE
+    } else {
+        let (format, premultiplied) =
+            legacy_format(pixel_format).ok_or_else(error::not_supported)?;
+        metadata.format = format_from_raw(format);
+        if premultiplied {
+            metadata.miscFlags2 |= TEX_ALPHA_MODE_PREMULTIPLIED;
+        }
+
+        if flags & DDSD_DEPTH != 0 {
+            metadata.depth = depth;
+            metadata.dimension = TEX_DIMENSION::TEX_DIMENSION_TEXTURE3D;
+        } else {
+            if caps2 & DDSCAPS2_CUBEMAP != 0 {
+                // Partial cubemaps aren't supported
+                if caps2 & DDSCAPS2_CUBEMAP_ALLFACES != DDSCAPS2_CUBEMAP_ALLFACES {
+                    return Err(error::not_supported());
+                }
+                metadata.arraySize = 6;
+                metadata.miscFlags |= TEX_MISC_TEXTURECUBE;
+            }
+            metadata.dimension = TEX_DIMENSION::TEX_DIMENSION_TEXTURE2D;
+        }
+
+        HEADER_SIZE
+    };
+
+    if metadata.mipLevels > max_mip_levels(metadata.width, metadata.height, metadata.depth) {
+        return Err(error::fail());
+    }
+
+    Ok((metadata, offset))
+}
+
+#[inline]
+pub fn metadata_from_memory(buffer: &[u8]) -> Result<TexMetadata> {
+    parse(buffer).map(|(metadata, _)| metadata)
+}
+
+// Only reads as much of the file as the headers take up
+pub fn metadata(file_name: impl AsRef<Path>) -> Result<TexMetadata> {
+    let file = File::open(file_name).map_err(|_| error::fail())?;
+    let mut buffer = Vec::with_capacity(HEADER_SIZE + DX10_HEADER_SIZE);
+    file.take((HEADER_SIZE + DX10_HEADER_SIZE) as u64)
+        .read_to_end(&mut buffer)
+        .map_err(|_| error::fail())?;
+    metadata_from_memory(&buffer)
+}
+
+// Row pitch and slice pitch of the top level, as ComputePitch calculates them by default. None
+// when they don't fit in a usize.
+fn compute_pitch(format: u32, width: usize, height: usize) -> Option<(usize, usize)> {
+    let (row, rows) = match format {
+        // BC1 and BC4 are 8 bytes per block
+        70..=72 | 79..=81 => (width.div_ceil(4).max(1).checked_mul(8)?, height.div_ceil(4).max(1)),
+        73..=78 | 82..=84 | 94..=99 => {
+            (width.div_ceil(4).max(1).checked_mul(16)?, height.div_ceil(4).max(1))
+        }
+        // Packed, two pixels share their chroma
+        68 | 69 | 107 => (width.div_ceil(2).checked_mul(4)?, height),
+        108 | 109 => (width.div_ceil(2).checked_mul(8)?, height),
+        // Planar, with the chroma planes following the luma plane
+        103 | 106 => (width.div_ceil(2).checked_mul(2)?, height.checked_add(height.div_ceil(2))?),
+        104 | 105 => (width.div_ceil(2).checked_mul(4)?, height.checked_add(height.div_ceil(2))?),
+        110 => (width.div_ceil(4).checked_mul(4)?, height.checked_mul(2)?),
+        130 => (width.div_ceil(2).checked_mul(2)?, height.checked_mul(2)?),
+        _ => {
+            let bits = width.checked_mul(format_bits_per_pixel(format_from_raw(format)))?;
+            (bits.div_ceil(8), height)
+        }
+    };
+    Some((row, row.checked_mul(rows)?))
+}
+
+// Always writes a DX10 header, which every reader since Direct3D 10 understands and which
+// can describe every format
+pub fn encode(metadata: &TexMetadata) -> Result<Vec<u8>> {
+    let format = format_to_raw(metadata.format);
+    if format_bits_per_pixel(metadata.format) == 0 || (111..=114).contains(&format) {
+        return Err(error::not_supported());
+    }
+    if metadata.mipLevels == 0 || metadata.arraySize == 0 {
+        return Err(error::invalid_arg());
+    }
+    let field = |value: usize| u32::try_from(value).map_err(|_| error::invalid_arg());
+
+    let is_cube = metadata.miscFlags & TEX_MISC_TEXTURECUBE != 0;
+    let is_volume = metadata.dimension == TEX_DIMENSION::TEX_DIMENSION_TEXTURE3D;
+    let (dimension, array_size) = match metadata.dimension {
+        TEX_DIMENSION::TEX_DIMENSION_TEXTURE1D => (2, metadata.arraySize),
+        TEX_DIMENSION::TEX_DIMENSION_TEXTURE2D if is_cube => {
+            if metadata.arraySize % 6 != 0 {
+                return Err(error::invalid_arg());
+            }
+            (3, metadata.arraySize / 6)
+        }
+        TEX_DIMENSION::TEX_DIMENSION_TEXTURE2D => (3, metadata.arraySize),
+        TEX_DIMENSION::TEX_DIMENSION_TEXTURE3D if metadata.arraySize == 1 => (4, 1),
+        _ => return Err(error::invalid_arg()),
+    };
+
+    let is_block_compressed = matches!(format, 70..=84 | 94..=99);
+    let (row_pitch, slice_pitch) =
+        compute_pitch(format, metadata.width, metadata.height).ok_or_else(error::invalid_arg)?;
+
+    let mut flags = DDSD_CAPS | DDSD_HEIGHT | DDSD_WIDTH | DDSD_PIXELFORMAT | DDSD_MIPMAPCOUNT;
+    flags |= if is_block_compressed { DDSD_LINEARSIZE } else { DDSD_PITCH };
+    let mut caps = DDSCAPS_TEXTURE;
+    let mut caps2 = 0;
+    if metadata.mipLevels > 1 {
+        caps |= DDSCAPS_COMPLEX | DDSCAPS_MIPMAP;
+    }
+    if is_cube {
+        caps |= DDSCAPS_COMPLEX;
+        caps2 |= DDSCAPS2_CUBEMAP | DDSCAPS2_CUBEMAP_ALLFACES;
+    }
+    if is_volume {
+        flags |= DDSD_DEPTH;
+        caps |= DDSCAPS_COMPLEX;
+        caps2 |= DDSCAPS2_VOLUME;
+    }
+
+    let mut header = [0_u32; 31];
+    header[0] = DDS_HEADER_SIZE;
+    header[1] = flags;
+    header[2] = field(metadata.height)?;
+    header[3] = field(metadata.width)?;
+    header[4] = field(if is_block_compressed { slice_pitch } else { row_pitch })?;
+    header[5] = if is_volume { field(metadata.depth)? } else { 0 };
+    header[6] = field(metadata.mipLevels)?;
+    // DDS_PIXELFORMAT
+    header[18] = DDS_PIXELFORMAT_SIZE;
+    header[19] = DDPF_FOURCC;
+    header[20] = FOURCC_DX10;
+    header[26] = caps;
+    header[27] = caps2;
+
+    let dx10 = [
+        format,
+        dimension,
+        if is_cube { DDS_RESOURCE_MISC_TEXTURECUBE } else { 0 },
+        field(array_size)?,
+        metadata.miscFlags2 & DDS_MISC_FLAGS2_ALPHA_MODE_MASK,
+    ];
+
+    let mut buffer = Vec::with_capacity(HEADER_SIZE + DX10_HEADER_SIZE);
+    buffer.extend_from_slice(&MAGIC);
+    buffer.extend(header.iter().chain(&dx10).flat_map(|value| value.to_le_bytes()));
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const R8G8B8A8_UNORM: u32 = 28;
+    const BC1_UNORM: u32 = 71;
+    const BC7_UNORM: u32 = 98;
+
+    fn metadata_2d(format: u32, width: usize, height: usize) -> TexMetadata {
+        TexMetadata {
+            width,
+            height,
+            depth: 1,
+            arraySize: 1,
+            mipLevels: 1,
+            format: format_from_raw(format),
+            dimension: TEX_DIMENSION::TEX_DIMENSION_TEXTURE2D,
+            ..TexMetadata::default()
+        }
+    }
+
+    // A legacy header with the given DDS_PIXELFORMAT flags, FourCC, bit count and masks
+    fn legacy(pf_flags: u32, fourcc: u32, bits: u32, masks: [u32; 4], caps2: u32) -> Vec<u8> {
+        let mut header = [0_u32; 31];
+        header[0] = DDS_HEADER_SIZE;
+        header[1] = DDSD_CAPS | DDSD_HEIGHT | DDSD_WIDTH | DDSD_PIXELFORMAT;
+        header[2] = 16;
+        header[3] = 8;
+        header[18] = DDS_PIXELFORMAT_SIZE;
+        header[19] = pf_flags;
+        header[20] = fourcc;
+        header[21] = bits;
+        header[22..26].copy_from_slice(&masks);
+        header[26] = DDSCAPS_TEXTURE;
+        header[27] = caps2;
+        MAGIC.into_iter().chain(header.iter().flat_map(|value| value.to_le_bytes())).collect()
+    }
+
+    #[test]
+    fn test_files() {
+        let (metadata, offset) = parse(include_bytes!("../../../sys/tests/test.dds")).unwrap();
+        assert_eq!(metadata, metadata_2d(R8G8B8A8_UNORM, 512, 768));
+        assert_eq!(offset, HEADER_SIZE);
+
+        let (metadata, _) = parse(include_bytes!("../../../sys/tests/test_1d.dds")).unwrap();
+        assert_eq!((metadata.width, metadata.height), (512, 1));
+
+        let (metadata, offset) =
+            parse(include_bytes!("../../../sys/tests/test_complex.dds")).unwrap();
+        assert_eq!(
+            metadata,
+            TexMetadata {
+                arraySize: 6,
+                mipLevels: 10,
+                ..metadata_2d(BC7_UNORM, 512, 768)
+            }
+        );
+        assert_eq!(offset, HEADER_SIZE + DX10_HEADER_SIZE);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let cases = [
+            metadata_2d(R8G8B8A8_UNORM, 4, 4),
+            TexMetadata {
+                mipLevels: 3,
+                miscFlags2: TEX_ALPHA_MODE_PREMULTIPLIED,
+                ..metadata_2d(BC1_UNORM, 5, 7)
+            },
+            TexMetadata {
+                arraySize: 12,
+                miscFlags: TEX_MISC_TEXTURECUBE,
+                ..metadata_2d(BC7_UNORM, 16, 16)
+            },
+            TexMetadata {
+                height: 1,
+                arraySize: 3,
+                dimension: TEX_DIMENSION::TEX_DIMENSION_TEXTURE1D,
+                ..metadata_2d(R8G8B8A8_UNORM, 32, 1)
+            },
+            TexMetadata {
+                depth: 8,
+                mipLevels: 4,
+                dimension: TEX_DIMENSION::TEX_DIMENSION_TEXTURE3D,
+                ..metadata_2d(R8G8B8A8_UNORM, 8, 8)
+            },
+        ];
+        for metadata in cases {
+            let encoded = encode(&metadata).unwrap();
+            assert_eq!(encoded.len(), HEADER_SIZE + DX10_HEADER_SIZE);
+            assert_eq!(parse(&encoded).unwrap(), (metadata, encoded.len()));
+        }
+
+        // Pitch for uncompressed formats, linear size for block compressed ones
+        let encoded = encode(&metadata_2d(R8G8B8A8_UNORM, 5, 3)).unwrap();
+        assert_eq!(read_u32(&encoded, 20), 20);
+        let encoded = encode(&metadata_2d(BC1_UNORM, 5, 3)).unwrap();
+        assert_eq!(read_u32(&encoded, 20), 16);
+    }
+
+    #[test]
+    fn test_legacy() {
+        let dxt1 = legacy(DDPF_FOURCC, fourcc(b"DXT1"), 0, [0; 4], 0);
+        assert_eq!(metadata_from_memory(&dxt1).unwrap(), metadata_2d(BC1_UNORM, 8, 16));
+
+        let dxt2 = metadata_from_memory(&legacy(DDPF_FOURCC, fourcc(b"DXT2"), 0, [0; 4], 0));
+        assert_eq!(dxt2.unwrap().miscFlags2, TEX_ALPHA_MODE_PREMULTIPLIED);
+
+        let masks = [0x0000_00ff, 0x0000_ff00, 0x00ff_0000, 0xff00_0000];
+        let rgba = legacy(DDPF_RGB | DDPF_ALPHAPIXELS, 0, 32, masks, 0);
+        assert_eq!(metadata_from_memory(&rgba).unwrap().format, format_from_raw(R8G8B8A8_UNORM));
+
+        let all_faces = DDSCAPS2_CUBEMAP | DDSCAPS2_CUBEMAP_ALLFACES;
+        let cube = metadata_from_memory(&legacy(DDPF_RGB, 0, 32, masks, all_faces)).unwrap();
+        assert_eq!((cube.arraySize, cube.miscFlags), (6, TEX_MISC_TEXTURECUBE));
+        let partial = legacy(DDPF_RGB, 0, 32, masks, DDSCAPS2_CUBEMAP | 0x400);
+        assert!(metadata_from_memory(&partial).is_err());
+
+        // Masks nothing maps to
+        assert!(metadata_from_memory(&legacy(DDPF_RGB, 0, 32, [1, 2, 3, 4], 0)).is_err());
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(metadata_from_memory(b"DDS ").is_err());
+        assert!(metadata_from_memory(&[0; HEADER_SIZE]).is_err());
+
+        let mut encoded = encode(&metadata_2d(R8G8B8A8_UNORM, 4, 4)).unwrap();
+        // Missing DX10 header
+        assert!(metadata_from_memory(&encoded[..HEADER_SIZE]).is_err());
+        // More mips than a 4x4 texture has
+        encoded[28..32].copy_from_slice(&4_u32.to_le_bytes());
+        assert!(metadata_from_memory(&encoded).is_err());
+        // No array items
+        encoded[28..32].copy_from_slice(&1_u32.to_le_bytes());
+        encoded[HEADER_SIZE + 12..HEADER_SIZE + 16].copy_from_slice(&0_u32.to_le_bytes());
+        assert!(metadata_from_memory(&encoded).is_err());
+
+        let cube = TexMetadata {
+            arraySize: 5,
+            miscFlags: TEX_MISC_TEXTURECUBE,
+            ..metadata_2d(R8G8B8A8_UNORM, 4, 4)
+        };
+        assert!(encode(&cube).is_err());
+    }
+
+    // Found by fuzzing: the pitch of a huge texture overflowed while encoding its header
+    #[test]
+    fn test_pitch_overflow() {
+        let fuzzed = include_bytes!("../../../fuzz/corpus/dds_header/regression_pitch_overflow.dds");
+        let metadata = metadata_from_memory(fuzzed).unwrap();
+        assert!(encode(&metadata).is_err());
+    }
+}
@@ -1,8 +1,11 @@
+use std::path::Path;
 use std::ptr;
 
 use directxtex_sys::{self as sys, TexMetadata};
 
 use crate::error::hresult;
+use crate::formats::{read_file_with_limits, with_temp_file, write_temp};
+use crate::limits::LoadLimits;
 use crate::{CWide, Error, Image, Result, ScratchImage};
 
 pub fn metadata<'file_name, CWIDE>(file_name: CWIDE) -> Result<TexMetadata>
@@ -28,6 +31,17 @@ where
         .map(|_| ScratchImage(out))
 }
 
+// `load` after checking `limits` against the header. OpenEXR only reads files, so a private copy
+// is checked and loaded
+pub fn load_with_limits(file_name: impl AsRef<Path>, limits: &LoadLimits) -> Result<ScratchImage> {
+    let buffer = read_file_with_limits(file_name.as_ref(), limits)?;
+    with_temp_file("exr", |file_name| {
+        write_temp(file_name, &buffer)?;
+        limits.check(&metadata(file_name)?)?;
+        load(file_name)
+    })
+}
+
 pub fn save<'file_name, CWIDE>(file_name: CWIDE, image: &Image) -> Result<()>
 where
     CWide<'file_name>: TryFrom<CWIDE>,
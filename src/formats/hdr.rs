@@ -1,8 +1,11 @@
+use std::path::Path;
 use std::ptr;
 
 use directxtex_sys::{self as sys, TexMetadata};
 
 use crate::error::hresult;
+use crate::formats::read_file_with_limits;
+use crate::limits::LoadLimits;
 use crate::{Blob, CWide, Error, Image, Result, ScratchImage};
 
 pub fn metadata<'file_name, CWIDE>(file_name: CWIDE) -> Result<TexMetadata>
@@ -45,6 +48,17 @@ pub fn load_from_memory(buffer: &[u8]) -> Result<ScratchImage> {
     .map(|_| ScratchImage(out))
 }
 
+// `load` after checking `limits` against the header
+pub fn load_with_limits(file_name: impl AsRef<Path>, limits: &LoadLimits) -> Result<ScratchImage> {
+    let buffer = read_file_with_limits(file_name.as_ref(), limits)?;
+    load_from_memory_with_limits(&buffer, limits)
+}
+
+pub fn load_from_memory_with_limits(buffer: &[u8], limits: &LoadLimits) -> Result<ScratchImage> {
+    limits.check(&metadata_from_memory(buffer)?)?;
+    load_from_memory(buffer)
+}
+
 pub fn save<'file_name, CWIDE>(file_name: CWIDE, image: &Image) -> Result<()>
 where
     CWide<'file_name>: TryFrom<CWIDE>,
@@ -56,5 +70,5 @@ where
 
 pub fn save_to_memory(image: &Image) -> Result<Blob> {
     let mut blob = sys::Blob::default();
-    hresult(unsafe { sys::SaveToHDRMemory(image.as_ptr(), &mut blob) }).map(|_| Blob(blob))
+    hresult(unsafe { sys::SaveToHDRMemory(image.as_ptr(), &mut blob) }).map(|_| Blob::owned(blob))
 }
@@ -0,0 +1 @@
+file_codec!("jpeg", GetMetadataFromJPEGFile, LoadFromJPEGFile, SaveToJPEGFile);
@@ -0,0 +1,495 @@
+use std::path::Path;
+
+use directxtex_sys::{TexMetadata, CP_FLAGS, DXGI_FORMAT, TEX_DIMENSION};
+
+use crate::formats::{ktx2, read_file, read_file_with_limits};
+use crate::limits::LoadLimits;
+use crate::util::{
+    compute_pitch,
+    compute_scanlines,
+    format_from_raw,
+    image_index,
+    is_compressed,
+    TEX_MISC_TEXTURECUBE,
+};
+use crate::{error, Error, Result, ScratchImage};
+
+// KTX1 is read in Rust like KTX2. Rows are returned in the order they are stored, the
+// KTXorientation hint isn't applied.
+
+pub const IDENTIFIER: [u8; 12] = [
+    0xab, 0x4b, 0x54, 0x58, 0x20, 0x31, 0x31, 0xbb, 0x0d, 0x0a, 0x1a, 0x0a,
+];
+
+const HEADER_SIZE: usize = 64;
+const ENDIANNESS: u32 = 0x0403_0201;
+
+const GL_RGBA: u32 = 0x1908;
+const GL_BGRA: u32 = 0x80e1;
+const GL_UNSIGNED_BYTE: u32 = 0x1401;
+const GL_HALF_FLOAT: u32 = 0x140b;
+const GL_FLOAT: u32 = 0x1406;
+
+// Not a KTX file, or a truncated one
+fn invalid() -> Error { error::with_message(&error::fail(), "invalid or truncated KTX file") }
+
+// glInternalFormat to DXGI_FORMAT, for the layouts DirectXTex can hold. Unsized GL_RGBA depends on
+// glType, see `parse_ktx1`.
+#[rustfmt::skip]
+static GL_FORMATS: &[(u32, u32)] = &[
+    (0x83f0, 71), // COMPRESSED_RGB_S3TC_DXT1_EXT
+    (0x83f1, 71), // COMPRESSED_RGBA_S3TC_DXT1_EXT
+    (0x83f2, 74), // COMPRESSED_RGBA_S3TC_DXT3_EXT
+    (0x83f3, 77), // COMPRESSED_RGBA_S3TC_DXT5_EXT
+    (0x8c4c, 72), // COMPRESSED_SRGB_S3TC_DXT1_EXT
+    (0x8c4d, 72), // COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT
+    (0x8c4e, 75), // COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT
+    (0x8c4f, 78), // COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT
+    (0x8dbb, 80), // COMPRESSED_RED_RGTC1
+    (0x8dbc, 81), // COMPRESSED_SIGNED_RED_RGTC1
+    (0x8dbd, 83), // COMPRESSED_RG_RGTC2
+    (0x8dbe, 84), // COMPRESSED_SIGNED_RG_RGTC2
+    (0x8e8c, 98), // COMPRESSED_RGBA_BPTC_UNORM
+    (0x8e8d, 99), // COMPRESSED_SRGB_ALPHA_BPTC_UNORM
+    (0x8e8e, 96), // COMPRESSED_RGB_BPTC_SIGNED_FLOAT
+    (0x8e8f, 95), // COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT
+    (0x8058, 28), // RGBA8
+    (0x8f97, 31), // RGBA8_SNORM
+    (0x8c43, 29), // SRGB8_ALPHA8
+    (0x93a1, 87), // BGRA8_EXT
+    (0x8229, 61), // R8
+    (0x822b, 49), // RG8
+    (0x822a, 56), // R16
+    (0x822c, 35), // RG16
+    (0x805b, 11), // RGBA16
+    (0x822d, 54), // R16F
+    (0x822f, 34), // RG16F
+    (0x881a, 10), // RGBA16F
+    (0x822e, 41), // R32F
+    (0x8230, 16), // RG32F
+    (0x8814, 2),  // RGBA32F
+    (0x8059, 24), // RGB10_A2
+    (0x8c3a, 26), // R11F_G11F_B10F
+    (0x8c3d, 67), // RGB9_E5
+    (0x8d62, 85), // RGB565
+];
+
+// Formats that turn up in KTX files from mobile tools but have no DXGI equivalent
+#[rustfmt::skip]
+static GL_UNSUPPORTED_NAMES: &[(u32, &str)] = &[
+    (0x8051, "RGB8"),
+    (0x8c41, "SRGB8"),
+    (0x8d64, "ETC1_RGB8_OES"),
+    (0x9270, "COMPRESSED_R11_EAC"),
+    (0x9271, "COMPRESSED_SIGNED_R11_EAC"),
+    (0x9272, "COMPRESSED_RG11_EAC"),
+    (0x9273, "COMPRESSED_SIGNED_RG11_EAC"),
+    (0x9274, "COMPRESSED_RGB8_ETC2"),
+    (0x9275, "COMPRESSED_SRGB8_ETC2"),
+    (0x9276, "COMPRESSED_RGB8_PUNCHTHROUGH_ALPHA1_ETC2"),
+    (0x9277, "COMPRESSED_SRGB8_PUNCHTHROUGH_ALPHA1_ETC2"),
+    (0x9278, "COMPRESSED_RGBA8_ETC2_EAC"),
+    (0x9279, "COMPRESSED_SRGB8_ALPHA8_ETC2_EAC"),
+    (0x8c00, "COMPRESSED_RGB_PVRTC_4BPPV1_IMG"),
+    (0x8c01, "COMPRESSED_RGB_PVRTC_2BPPV1_IMG"),
+    (0x8c02, "COMPRESSED_RGBA_PVRTC_4BPPV1_IMG"),
+    (0x8c03, "COMPRESSED_RGBA_PVRTC_2BPPV1_IMG"),
+];
+
+#[rustfmt::skip]
+static VK_UNSUPPORTED_NAMES: &[(u32, &str)] = &[
+    (147, "ETC2_R8G8B8_UNORM_BLOCK"),
+    (148, "ETC2_R8G8B8_SRGB_BLOCK"),
+    (149, "ETC2_R8G8B8A1_UNORM_BLOCK"),
+    (150, "ETC2_R8G8B8A1_SRGB_BLOCK"),
+    (151, "ETC2_R8G8B8A8_UNORM_BLOCK"),
+    (152, "ETC2_R8G8B8A8_SRGB_BLOCK"),
+    (153, "EAC_R11_UNORM_BLOCK"),
+    (154, "EAC_R11_SNORM_BLOCK"),
+    (155, "EAC_R11G11_UNORM_BLOCK"),
+    (156, "EAC_R11G11_SNORM_BLOCK"),
+];
+
+// The ASTC block sizes run in the same order in the GL and the Vulkan enums
+fn astc_name(index: u32, srgb: bool) -> Option<&'static str> {
+    #[rustfmt::skip]
+    static GL_NAMES: [[&str; 14]; 2] = [
+        [
+            "COMPRESSED_RGBA_ASTC_4x4_KHR", "COMPRESSED_RGBA_ASTC_5x4_KHR",
+            "COMPRESSED_RGBA_ASTC_5x5_KHR", "COMPRESSED_RGBA_ASTC_6x5_KHR",
+            "COMPRESSED_RGBA_ASTC_6x6_KHR", "COMPRESSED_RGBA_ASTC_8x5_KHR",
+            "COMPRESSED_RGBA_ASTC_8x6_KHR", "COMPRESSED_RGBA_ASTC_8x8_KHR",
+            "COMPRESSED_RGBA_ASTC_10x5_KHR", "COMPRESSED_RGBA_ASTC_10x6_KHR",
+            "COMPRESSED_RGBA_ASTC_10x8_KHR", "COMPRESSED_RGBA_ASTC_10x10_KHR",
+            "COMPRESSED_RGBA_ASTC_12x10_KHR", "COMPRESSED_RGBA_ASTC_12x12_KHR",
+        ],
+        [
+            "COMPRESSED_SRGB8_ALPHA8_ASTC_4x4_KHR", "COMPRESSED_SRGB8_ALPHA8_ASTC_5x4_KHR",
+            "COMPRESSED_SRGB8_ALPHA8_ASTC_5x5_KHR", "COMPRESSED_SRGB8_ALPHA8_ASTC_6x5_KHR",
+            "COMPRESSED_SRGB8_ALPHA8_ASTC_6x6_KHR", "COMPRESSED_SRGB8_ALPHA8_ASTC_8x5_KHR",
+            "COMPRESSED_SRGB8_ALPHA8_ASTC_8x6_KHR", "COMPRESSED_SRGB8_ALPHA8_ASTC_8x8_KHR",
+            "COMPRESSED_SRGB8_ALPHA8_ASTC_10x5_KHR", "COMPRESSED_SRGB8_ALPHA8_ASTC_10x6_KHR",
+            "COMPRESSED_SRGB8_ALPHA8_ASTC_10x8_KHR", "COMPRESSED_SRGB8_ALPHA8_ASTC_10x10_KHR",
+            "COMPRESSED_SRGB8_ALPHA8_ASTC_12x10_KHR", "COMPRESSED_SRGB8_ALPHA8_ASTC_12x12_KHR",
+        ],
+    ];
+    GL_NAMES[usize::from(srgb)].get(index as usize).copied()
+}
+
+fn vk_astc_name(index: u32, srgb: bool) -> Option<&'static str> {
+    #[rustfmt::skip]
+    static VK_NAMES: [[&str; 14]; 2] = [
+        [
+            "ASTC_4x4_UNORM_BLOCK", "ASTC_5x4_UNORM_BLOCK", "ASTC_5x5_UNORM_BLOCK",
+            "ASTC_6x5_UNORM_BLOCK", "ASTC_6x6_UNORM_BLOCK", "ASTC_8x5_UNORM_BLOCK",
+            "ASTC_8x6_UNORM_BLOCK", "ASTC_8x8_UNORM_BLOCK", "ASTC_10x5_UNORM_BLOCK",
+            "ASTC_10x6_UNORM_BLOCK", "ASTC_10x8_UNORM_BLOCK", "ASTC_10x10_UNORM_BLOCK",
+            "ASTC_12x10_UNORM_BLOCK", "ASTC_12x12_UNORM_BLOCK",
+        ],
+        [
+            "ASTC_4x4_SRGB_BLOCK", "ASTC_5x4_SRGB_BLOCK", "ASTC_5x5_SRGB_BLOCK",
+            "ASTC_6x5_SRGB_BLOCK", "ASTC_6x6_SRGB_BLOCK", "ASTC_8x5_SRGB_BLOCK",
+            "ASTC_8x6_SRGB_BLOCK", "ASTC_8x8_SRGB_BLOCK", "ASTC_10x5_SRGB_BLOCK",
+            "ASTC_10x6_SRGB_BLOCK", "ASTC_10x8_SRGB_BLOCK", "ASTC_10x10_SRGB_BLOCK",
+            "ASTC_12x10_SRGB_BLOCK", "ASTC_12x12_SRGB_BLOCK",
+        ],
+    ];
+    VK_NAMES[usize::from(srgb)].get(index as usize).copied()
+}
+
+#[must_use]
+pub fn gl_format_name(gl_internal_format: u32) -> Option<&'static str> {
+    match gl_internal_format {
+        0x93b0..=0x93bd => astc_name(gl_internal_format - 0x93b0, false),
+        0x93d0..=0x93dd => astc_name(gl_internal_format - 0x93d0, true),
+        _ => GL_UNSUPPORTED_NAMES
+            .iter()
+            .find(|(value, _)| *value == gl_internal_format)
+            .map(|(_, name)| *name),
+    }
+}
+
+#[must_use]
+pub fn vk_format_name(vk_format: u32) -> Option<&'static str> {
+    match vk_format {
+        157..=184 => vk_astc_name((vk_format - 157) / 2, (vk_format - 157) % 2 == 1),
+        _ => VK_UNSUPPORTED_NAMES
+            .iter()
+            .find(|(value, _)| *value == vk_format)
+            .map(|(_, name)| *name),
+    }
+}
+
+// The dimensions of any KTX or KTX2 file, whether or not the payload can be loaded
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KtxMetadata {
+    pub version:            u8,
+    pub width:              usize,
+    // Zero for 1D textures
+    pub height:             usize,
+    // Zero for anything but volumes
+    pub depth:              usize,
+    // Zero when the texture isn't an array
+    pub layers:             usize,
+    pub faces:              usize,
+    pub mip_levels:         usize,
+    pub gl_internal_format: Option<u32>,
+    pub vk_format:          Option<u32>,
+    // The DXGI equivalent when the payload can be loaded
+    pub format:             Option<DXGI_FORMAT>,
+}
+
+impl KtxMetadata {
+    #[must_use]
+    pub fn format_name(&self) -> Option<&'static str> {
+        match (self.gl_internal_format, self.vk_format) {
+            (Some(gl_internal_format), _) => gl_format_name(gl_internal_format),
+            (_, Some(vk_format)) => vk_format_name(vk_format),
+            _ => None,
+        }
+    }
+
+    // Formats that can't be loaded are reported as DXGI_FORMAT_UNKNOWN
+    #[must_use]
+    pub fn to_metadata(&self) -> TexMetadata {
+        let dimension = match (self.height, self.depth) {
+            (0, 0) => TEX_DIMENSION::TEX_DIMENSION_TEXTURE1D,
+            (_, 0) => TEX_DIMENSION::TEX_DIMENSION_TEXTURE2D,
+            _ => TEX_DIMENSION::TEX_DIMENSION_TEXTURE3D,
+        };
+        TexMetadata {
+            width: self.width,
+            height: self.height.max(1),
+            depth: self.depth.max(1),
+            arraySize: self.layers.max(1) * self.faces,
+            mipLevels: self.mip_levels.max(1),
+            miscFlags: if self.faces == 6 { TEX_MISC_TEXTURECUBE } else { 0 },
+            miscFlags2: 0,
+            format: self.format.unwrap_or(format_from_raw(0)),
+            dimension,
+        }
+    }
+
+    fn unsupported(&self) -> Error {
+        let name = self.format_name().map(|name| format!(" ({name})")).unwrap_or_default();
+        let message = match (self.gl_internal_format, self.vk_format) {
+            (_, Some(vk_format)) => format!("unsupported vkFormat {vk_format}{name}"),
+            (gl_internal_format, _) => format!(
+                "unsupported glInternalFormat 0x{:04x}{name}",
+                gl_internal_format.unwrap_or_default()
+            ),
+        };
+        error::with_message(&error::not_supported(), &message)
+    }
+}
+
+struct Header {
+    metadata:       KtxMetadata,
+    big_endian:     bool,
+    gl_type_size:   usize,
+    key_value_size: usize,
+}
+
+fn read_u32(buffer: &[u8], at: usize, big_endian: bool) -> Result<u32> {
+    let bytes = buffer.get(at..at + 4).ok_or_else(invalid)?;
+    let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+    Ok(if big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) })
+}
+
+fn parse_ktx1(buffer: &[u8]) -> Result<Header> {
+    let big_endian = match read_u32(buffer, 12, false)? {
+        ENDIANNESS => false,
+        value if value.swap_bytes() == ENDIANNESS => true,
+        _ => return Err(invalid()),
+    };
+    let field = |index: usize| read_u32(buffer, 16 + index * 4, big_endian).map(|v| v as usize);
+
+    let gl_type = field(0)? as u32;
+    let gl_type_size = field(1)?;
+    let gl_format = field(2)? as u32;
+    let gl_internal_format = field(3)? as u32;
+
+    let format = match (gl_internal_format, gl_type) {
+        (GL_RGBA, GL_UNSIGNED_BYTE) => Some(28),
+        (GL_RGBA, GL_HALF_FLOAT) => Some(10),
+        (GL_RGBA, GL_FLOAT) => Some(2),
+        (GL_RGBA, _) => None,
+        _ => GL_FORMATS
+            .iter()
+            .find(|(value, _)| *value == gl_internal_format)
+            .map(|(_, format)| *format),
+    }
+    .map(|format| match format {
+        // RGBA8 uploaded from BGRA data
+        28 if gl_format == GL_BGRA => 87,
+        format => format,
+    })
+    .map(format_from_raw);
+
+    let metadata = KtxMetadata {
+        version: 1,
+        width: field(5)?,
+        height: field(6)?,
+        depth: field(7)?,
+        layers: field(8)?,
+        faces: field(9)?,
+        mip_levels: field(10)?,
+        gl_internal_format: Some(gl_internal_format),
+        vk_format: None,
+        format,
+    };
+
+    Ok(Header {
+        metadata,
+        big_endian,
+        gl_type_size,
+        key_value_size: field(11)?,
+    })
+}
+
+fn parse_ktx2(buffer: &[u8]) -> Result<KtxMetadata> {
+    let field = |at: usize| read_u32(buffer, at, false).map(|value| value as usize);
+    let vk_format = field(12)? as u32;
+
+    Ok(KtxMetadata {
+        version: 2,
+        width: field(20)?,
+        height: field(24)?,
+        depth: field(28)?,
+        layers: field(32)?,
+        faces: field(36)?,
+        mip_levels: field(40)?,
+        gl_internal_format: None,
+        vk_format: Some(vk_format),
+        format: ktx2::dxgi_format(vk_format),
+    })
+}
+
+fn validate(metadata: &KtxMetadata) -> Result<()> {
+    // No more levels than the full mip chain, so the sizes of every level can be shifted out
+    let largest = metadata.width.max(metadata.height).max(metadata.depth);
+    let is_valid = metadata.width > 0
+        && (metadata.faces == 1 || metadata.faces == 6)
+        && (metadata.depth == 0 || (metadata.faces == 1 && metadata.layers == 0))
+        && metadata.mip_levels <= (usize::BITS - largest.leading_zeros()) as usize;
+    if is_valid { Ok(()) } else { Err(invalid()) }
+}
+
+pub fn metadata_from_memory(buffer: &[u8]) -> Result<KtxMetadata> {
+    let metadata = match buffer.get(..IDENTIFIER.len()) {
+        Some(identifier) if identifier == IDENTIFIER => parse_ktx1(buffer)?.metadata,
+        Some(identifier) if identifier == ktx2::IDENTIFIER => parse_ktx2(buffer)?,
+        _ => return Err(invalid()),
+    };
+    validate(&metadata)?;
+    Ok(metadata)
+}
+
+pub fn metadata(file_name: impl AsRef<Path>) -> Result<KtxMetadata> {
+    use std::io::Read;
+
+    let file = std::fs::File::open(file_name).map_err(|error| error::io(&error))?;
+    let mut buffer = Vec::with_capacity(HEADER_SIZE);
+    file.take(HEADER_SIZE as u64)
+        .read_to_end(&mut buffer)
+        .map_err(|error| error::io(&error))?;
+    metadata_from_memory(&buffer)
+}
+
+// Where a level starts in the file and how its rows are laid out there
+struct Level {
+    offset:     usize,
+    row_size:   usize,
+    row_stride: usize,
+    rows:       usize,
+    slices:     usize,
+}
+
+fn load_ktx1(buffer: &[u8]) -> Result<ScratchImage> {
+    let header = parse_ktx1(buffer)?;
+    let info = &header.metadata;
+    validate(info)?;
+    let format = info.format.ok_or_else(|| info.unsupported())?;
+
+    let metadata = TexMetadata {
+        format,
+        ..info.to_metadata()
+    };
+    let is_volume = metadata.dimension == TEX_DIMENSION::TEX_DIMENSION_TEXTURE3D;
+    let items = if is_volume { 1 } else { metadata.arraySize };
+    // Non-array cubemaps store each face separately, with the image size of a single face
+    let is_single_cube = info.faces == 6 && info.layers == 0;
+    let swap_size =
+        if header.big_endian && !is_compressed(format) { header.gl_type_size } else { 1 };
+
+    // Every level has to agree with the header and fit the buffer before the pixels are
+    // allocated, so a short or lying file can't make us allocate for data it doesn't have
+    let mut offset = HEADER_SIZE
+        .checked_add(header.key_value_size)
+        .ok_or_else(invalid)?;
+    let mut levels = Vec::with_capacity(metadata.mipLevels);
+    for mip in 0..metadata.mipLevels {
+        let image_size = read_u32(buffer, offset, header.big_endian)? as usize;
+        offset += 4;
+
+        let width = (metadata.width >> mip).max(1);
+        let height = (metadata.height >> mip).max(1);
+        let slices = if is_volume { (metadata.depth >> mip).max(1) } else { 1 };
+        let (row_size, _) = compute_pitch(format, width, height, CP_FLAGS::default())?;
+        let rows = compute_scanlines(format, height);
+        // Rows are padded to 4 bytes like GL_UNPACK_ALIGNMENT
+        let row_stride = row_size.next_multiple_of(4);
+
+        let expected = [items, slices, rows]
+            .into_iter()
+            .try_fold(row_stride, usize::checked_mul)
+            .ok_or_else(invalid)?;
+        let (stored, level_size) = if is_single_cube {
+            (image_size.checked_mul(6), image_size.next_multiple_of(4).checked_mul(6))
+        } else {
+            (Some(image_size), Some(image_size))
+        };
+        if stored != Some(expected) {
+            return Err(invalid());
+        }
+        let level_size = level_size.ok_or_else(invalid)?;
+        if offset.checked_add(level_size).map_or(true, |end| end > buffer.len()) {
+            return Err(invalid());
+        }
+
+        levels.push(Level {
+            offset,
+            row_size,
+            row_stride,
+            rows,
+            slices,
+        });
+        offset += level_size.next_multiple_of(4);
+    }
+
+    let mut image = ScratchImage::initialize(&metadata)?;
+    for (mip, level) in levels.iter().enumerate() {
+        let mut source = level.offset;
+        for item in 0..items {
+            for slice in 0..level.slices {
+                let index = image_index(&metadata, mip, item, slice);
+                let row_pitch = image.images()[index].row_pitch();
+                let pixels = image.image_buffer_mut(index).ok_or_else(invalid)?;
+                for row in 0..level.rows {
+                    let target = &mut pixels[row * row_pitch..][..level.row_size];
+                    let start = source + row * level.row_stride;
+                    target.copy_from_slice(&buffer[start..start + level.row_size]);
+                    if swap_size > 1 {
+                        for element in target.chunks_exact_mut(swap_size) {
+                            element.reverse();
+                        }
+                    }
+                }
+                source += level.rows * level.row_stride;
+                // Faces of a non-array cubemap are padded to 4 bytes
+                if is_single_cube {
+                    source = source.next_multiple_of(4);
+                }
+            }
+        }
+    }
+
+    Ok(image)
+}
+
+// Loads KTX1 files and hands KTX2 files to `ktx2::load_from_memory`
+pub fn load_from_memory(buffer: &[u8]) -> Result<ScratchImage> {
+    match buffer.get(..IDENTIFIER.len()) {
+        Some(identifier) if identifier == IDENTIFIER => load_ktx1(buffer),
+        Some(identifier) if identifier == ktx2::IDENTIFIER => {
+            let metadata = parse_ktx2(buffer)?;
+            validate(&metadata)?;
+            if metadata.format.is_none() {
+                return Err(metadata.unsupported());
+            }
+            ktx2::load_from_memory(buffer)
+        }
+        _ => Err(invalid()),
+    }
+}
+
+pub fn load(file_name: impl AsRef<Path>) -> Result<ScratchImage> {
+    load_from_memory(&read_file(file_name.as_ref())?)
+}
+
+// `load_from_memory` after checking `limits` against the header. Formats that can't be loaded
+// are reported as unsupported rather than over the limits.
+pub fn load_from_memory_with_limits(buffer: &[u8], limits: &LoadLimits) -> Result<ScratchImage> {
+    let metadata = metadata_from_memory(buffer)?;
+    validate(&metadata)?;
+    if metadata.format.is_none() {
+        return Err(metadata.unsupported());
+    }
+    limits.check(&metadata.to_metadata())?;
+    load_from_memory(buffer)
+}
+
+pub fn load_with_limits(file_name: impl AsRef<Path>, limits: &LoadLimits) -> Result<ScratchImage> {
+    let buffer = read_file_with_limits(file_name.as_ref(), limits)?;
+    load_from_memory_with_limits(&buffer, limits)
+}
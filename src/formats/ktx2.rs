@@ -0,0 +1,572 @@
+use std::borrow::Cow;
+use std::path::Path;
+
+use directxtex_sys::{TexMetadata, CP_FLAGS, TEX_DIMENSION};
+
+use crate::formats::{read_file, read_file_with_limits};
+use crate::limits::LoadLimits;
+use crate::util::{
+    compute_pitch,
+    compute_scanlines,
+    format_from_raw,
+    format_to_raw,
+    image_index,
+    TEX_MISC_TEXTURECUBE,
+};
+use crate::{error, Blob, Image, Result, ScratchImage};
+
+// KTX2 is written and read in Rust, DirectXTex doesn't know about it. Levels hold every layer,
+// face and depth slice of a mip with tightly packed rows, smallest mip first in the file.
+
+pub const IDENTIFIER: [u8; 12] = [
+    0xab, 0x4b, 0x54, 0x58, 0x20, 0x32, 0x30, 0xbb, 0x0d, 0x0a, 0x1a, 0x0a,
+];
+
+// Identifier, header and the fixed part of the index
+const HEADER_SIZE: usize = 80;
+const LEVEL_INDEX_ENTRY_SIZE: usize = 24;
+
+const SUPERCOMPRESSION_NONE: u32 = 0;
+const SUPERCOMPRESSION_ZSTD: u32 = 2;
+
+const TEX_MISC2_ALPHA_MODE_MASK: u32 = 0x7;
+const TEX_ALPHA_MODE_PREMULTIPLIED: u32 = 2;
+
+const KHR_DF_FLAG_ALPHA_PREMULTIPLIED: u8 = 1;
+
+const WRITER: &str = concat!("directxtex-rs ", env!("CARGO_PKG_VERSION"));
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Supercompression {
+    #[default]
+    None,
+    #[cfg(feature = "zstd")]
+    Zstd { level: i32 },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Sample {
+    Unorm,
+    Snorm,
+    UFloat,
+    SFloat,
+}
+
+// KHR_DF_MODEL_*
+const MODEL_RGBSDA: u8 = 1;
+const MODEL_BC1A: u8 = 128;
+const MODEL_BC2: u8 = 129;
+const MODEL_BC3: u8 = 130;
+const MODEL_BC4: u8 = 131;
+const MODEL_BC5: u8 = 132;
+const MODEL_BC6H: u8 = 133;
+const MODEL_BC7: u8 = 134;
+
+// KHR_DF_CHANNEL_*
+const R: u8 = 0;
+const G: u8 = 1;
+const B: u8 = 2;
+const A: u8 = 15;
+const BC_COLOR: u8 = 0;
+const BC1_ALPHA_PRESENT: u8 = 1;
+const BC_ALPHA: u8 = 15;
+
+struct Format {
+    dxgi:      u32,
+    vk:        u32,
+    type_size: u32,
+    model:     u8,
+    srgb:      bool,
+    sample:    Sample,
+    // Channel and bit count of each sample in the order they are stored
+    channels:  &'static [(u8, u8)],
+}
+
+impl Format {
+    const fn new(
+        dxgi: u32,
+        vk: u32,
+        type_size: u32,
+        model: u8,
+        srgb: bool,
+        sample: Sample,
+        channels: &'static [(u8, u8)],
+    ) -> Self {
+        Self {
+            dxgi,
+            vk,
+            type_size,
+            model,
+            srgb,
+            sample,
+            channels,
+        }
+    }
+
+    const fn is_block_compressed(&self) -> bool { self.model != MODEL_RGBSDA }
+
+    fn block_size(&self) -> usize {
+        self.channels.iter().map(|(_, bits)| usize::from(*bits)).sum::<usize>() / 8
+    }
+}
+
+#[rustfmt::skip]
+static FORMATS: &[Format] = &[
+    Format::new(71, 133, 1, MODEL_BC1A, false, Sample::Unorm, &[(BC1_ALPHA_PRESENT, 64)]),
+    Format::new(72, 134, 1, MODEL_BC1A, true, Sample::Unorm, &[(BC1_ALPHA_PRESENT, 64)]),
+    Format::new(74, 135, 1, MODEL_BC2, false, Sample::Unorm, &[(BC_ALPHA, 64), (BC_COLOR, 64)]),
+    Format::new(75, 136, 1, MODEL_BC2, true, Sample::Unorm, &[(BC_ALPHA, 64), (BC_COLOR, 64)]),
+    Format::new(77, 137, 1, MODEL_BC3, false, Sample::Unorm, &[(BC_ALPHA, 64), (BC_COLOR, 64)]),
+    Format::new(78, 138, 1, MODEL_BC3, true, Sample::Unorm, &[(BC_ALPHA, 64), (BC_COLOR, 64)]),
+    Format::new(80, 139, 1, MODEL_BC4, false, Sample::Unorm, &[(R, 64)]),
+    Format::new(81, 140, 1, MODEL_BC4, false, Sample::Snorm, &[(R, 64)]),
+    Format::new(83, 141, 1, MODEL_BC5, false, Sample::Unorm, &[(R, 64), (G, 64)]),
+    Format::new(84, 142, 1, MODEL_BC5, false, Sample::Snorm, &[(R, 64), (G, 64)]),
+    Format::new(95, 143, 1, MODEL_BC6H, false, Sample::UFloat, &[(BC_COLOR, 128)]),
+    Format::new(96, 144, 1, MODEL_BC6H, false, Sample::SFloat, &[(BC_COLOR, 128)]),
+    Format::new(98, 145, 1, MODEL_BC7, false, Sample::Unorm, &[(BC_COLOR, 128)]),
+    Format::new(99, 146, 1, MODEL_BC7, true, Sample::Unorm, &[(BC_COLOR, 128)]),
+    Format::new(61, 9, 1, MODEL_RGBSDA, false, Sample::Unorm, &[(R, 8)]),
+    Format::new(49, 16, 1, MODEL_RGBSDA, false, Sample::Unorm, &[(R, 8), (G, 8)]),
+    Format::new(28, 37, 1, MODEL_RGBSDA, false, Sample::Unorm, &[(R, 8), (G, 8), (B, 8), (A, 8)]),
+    Format::new(31, 38, 1, MODEL_RGBSDA, false, Sample::Snorm, &[(R, 8), (G, 8), (B, 8), (A, 8)]),
+    Format::new(29, 43, 1, MODEL_RGBSDA, true, Sample::Unorm, &[(R, 8), (G, 8), (B, 8), (A, 8)]),
+    Format::new(87, 44, 1, MODEL_RGBSDA, false, Sample::Unorm, &[(B, 8), (G, 8), (R, 8), (A, 8)]),
+    Format::new(91, 50, 1, MODEL_RGBSDA, true, Sample::Unorm, &[(B, 8), (G, 8), (R, 8), (A, 8)]),
+    Format::new(11, 91, 2, MODEL_RGBSDA, false, Sample::Unorm, &[(R, 16), (G, 16), (B, 16), (A, 16)]),
+    Format::new(54, 76, 2, MODEL_RGBSDA, false, Sample::SFloat, &[(R, 16)]),
+    Format::new(34, 83, 2, MODEL_RGBSDA, false, Sample::SFloat, &[(R, 16), (G, 16)]),
+    Format::new(10, 97, 2, MODEL_RGBSDA, false, Sample::SFloat, &[(R, 16), (G, 16), (B, 16), (A, 16)]),
+    Format::new(41, 100, 4, MODEL_RGBSDA, false, Sample::SFloat, &[(R, 32)]),
+    Format::new(16, 103, 4, MODEL_RGBSDA, false, Sample::SFloat, &[(R, 32), (G, 32)]),
+    Format::new(2, 109, 4, MODEL_RGBSDA, false, Sample::SFloat, &[(R, 32), (G, 32), (B, 32), (A, 32)]),
+];
+
+fn by_dxgi(dxgi: u32) -> Option<&'static Format> { FORMATS.iter().find(|format| format.dxgi == dxgi) }
+
+fn by_vk(vk: u32) -> Option<&'static Format> { FORMATS.iter().find(|format| format.vk == vk) }
+
+#[must_use]
+pub fn vk_format(format: crate::DXGI_FORMAT) -> Option<u32> {
+    by_dxgi(format_to_raw(format)).map(|format| format.vk)
+}
+
+#[must_use]
+pub fn dxgi_format(vk_format: u32) -> Option<crate::DXGI_FORMAT> {
+    by_vk(vk_format).map(|format| format_from_raw(format.dxgi))
+}
+
+fn push_u32(buffer: &mut Vec<u8>, value: u32) { buffer.extend_from_slice(&value.to_le_bytes()); }
+
+fn push_u64(buffer: &mut Vec<u8>, value: u64) { buffer.extend_from_slice(&value.to_le_bytes()); }
+
+fn pad_to(buffer: &mut Vec<u8>, alignment: usize) {
+    buffer.resize(buffer.len().next_multiple_of(alignment), 0);
+}
+
+fn read_u32(buffer: &[u8], at: usize) -> Result<u32> {
+    let bytes = buffer.get(at..at + 4).ok_or_else(error::fail)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_u64(buffer: &[u8], at: usize) -> Result<u64> {
+    Ok(u64::from(read_u32(buffer, at)?) | (u64::from(read_u32(buffer, at + 4)?) << 32))
+}
+
+fn to_u32(value: usize) -> Result<u32> { u32::try_from(value).map_err(|_| error::invalid_arg()) }
+
+fn to_usize(value: u64) -> Result<usize> { usize::try_from(value).map_err(|_| error::fail()) }
+
+// Basic data format descriptor, see the Khronos Data Format Specification
+fn data_format_descriptor(format: &Format, premultiplied: bool, supercompressed: bool) -> Vec<u8> {
+    let block_size = 24 + 16 * format.channels.len();
+    let mut dfd = Vec::with_capacity(4 + block_size);
+
+    push_u32(&mut dfd, (4 + block_size) as u32);
+    // Khronos vendor, basic descriptor type
+    push_u32(&mut dfd, 0);
+    // Version 1.3 and the block size
+    push_u32(&mut dfd, 2 | ((block_size as u32) << 16));
+    // BT.709 primaries, linear or sRGB transfer
+    dfd.extend_from_slice(&[
+        format.model,
+        1,
+        if format.srgb { 2 } else { 1 },
+        if premultiplied { KHR_DF_FLAG_ALPHA_PREMULTIPLIED } else { 0 },
+    ]);
+    // Texel block dimensions minus one
+    let block_dimension = if format.is_block_compressed() { 3 } else { 0 };
+    dfd.extend_from_slice(&[block_dimension, block_dimension, 0, 0]);
+    // Bytes per plane, which has to be zero for supercompressed data
+    let bytes_plane = if supercompressed { 0 } else { format.block_size() as u8 };
+    dfd.extend_from_slice(&[bytes_plane, 0, 0, 0, 0, 0, 0, 0]);
+
+    let mut offset = 0_u16;
+    for &(channel, bits) in format.channels {
+        let mut qualifiers = match format.sample {
+            Sample::Unorm => 0,
+            Sample::Snorm => 0x40,
+            Sample::UFloat => 0x80,
+            Sample::SFloat => 0xc0,
+        };
+        // Alpha stays linear in sRGB formats
+        if format.srgb && channel == A && !format.is_block_compressed() {
+            qualifiers |= 0x10;
+        }
+        let (lower, upper) = match format.sample {
+            Sample::Unorm if format.is_block_compressed() => (0, u32::MAX),
+            Sample::Unorm => (0, u32::MAX >> (32 - u32::from(bits))),
+            Sample::Snorm if format.is_block_compressed() => (0x8000_0000, 0x7fff_ffff),
+            Sample::Snorm => {
+                let max = (1_i32 << (bits - 1)) - 1;
+                (-max as u32, max as u32)
+            }
+            Sample::UFloat => (0, 1.0_f32.to_bits()),
+            Sample::SFloat => ((-1.0_f32).to_bits(), 1.0_f32.to_bits()),
+        };
+
+        dfd.extend_from_slice(&offset.to_le_bytes());
+        dfd.extend_from_slice(&[bits - 1, channel | qualifiers, 0, 0, 0, 0]);
+        push_u32(&mut dfd, lower);
+        push_u32(&mut dfd, upper);
+        offset += u16::from(bits);
+    }
+
+    dfd
+}
+
+fn key_value_data() -> Vec<u8> {
+    let mut kvd = Vec::new();
+    let entry = [b"KTXwriter\0".as_slice(), WRITER.as_bytes(), b"\0".as_slice()].concat();
+    push_u32(&mut kvd, entry.len() as u32);
+    kvd.extend_from_slice(&entry);
+    pad_to(&mut kvd, 4);
+    kvd
+}
+
+// Rows of every layer, face and depth slice of one mip, without any row pitch padding
+fn level_data(images: &[Image], metadata: &TexMetadata, mip: usize) -> Result<Vec<u8>> {
+    let width = (metadata.width >> mip).max(1);
+    let height = (metadata.height >> mip).max(1);
+    let (row_size, slice_size) =
+        compute_pitch(metadata.format, width, height, CP_FLAGS::default())?;
+    let rows = compute_scanlines(metadata.format, height);
+
+    let (items, slices) = if metadata.dimension == TEX_DIMENSION::TEX_DIMENSION_TEXTURE3D {
+        (1, (metadata.depth >> mip).max(1))
+    } else {
+        (metadata.arraySize, 1)
+    };
+
+    let mut level = Vec::with_capacity(items * slices * slice_size);
+    for item in 0..items {
+        for slice in 0..slices {
+            let image = images
+                .get(image_index(metadata, mip, item, slice))
+                .ok_or_else(error::invalid_arg)?;
+            if image.width() != width || image.height() != height {
+                return Err(error::invalid_arg());
+            }
+            let pixels = image.buffer();
+            for row in 0..rows {
+                let start = row * image.row_pitch();
+                let row = pixels.get(start..start + row_size).ok_or_else(error::invalid_arg)?;
+                level.extend_from_slice(row);
+            }
+        }
+    }
+
+    Ok(level)
+}
+
+pub fn save_to_memory(
+    images: &[Image],
+    metadata: &TexMetadata,
+    supercompression: Supercompression,
+) -> Result<Blob> {
+    let format = by_dxgi(format_to_raw(metadata.format)).ok_or_else(error::not_supported)?;
+    if metadata.mipLevels == 0 || metadata.arraySize == 0 {
+        return Err(error::invalid_arg());
+    }
+
+    let is_cube = metadata.miscFlags & TEX_MISC_TEXTURECUBE != 0;
+    let faces = if is_cube { 6 } else { 1 };
+    if metadata.arraySize % faces != 0 {
+        return Err(error::invalid_arg());
+    }
+    let layers = metadata.arraySize / faces;
+    let (height, depth) = match metadata.dimension {
+        TEX_DIMENSION::TEX_DIMENSION_TEXTURE1D => (0, 0),
+        TEX_DIMENSION::TEX_DIMENSION_TEXTURE2D => (metadata.height, 0),
+        TEX_DIMENSION::TEX_DIMENSION_TEXTURE3D => (metadata.height, metadata.depth),
+        _ => return Err(error::invalid_arg()),
+    };
+    let premultiplied =
+        metadata.miscFlags2 & TEX_MISC2_ALPHA_MODE_MASK == TEX_ALPHA_MODE_PREMULTIPLIED;
+
+    let (scheme, levels) = match supercompression {
+        Supercompression::None => {
+            let levels = (0..metadata.mipLevels)
+                .map(|mip| level_data(images, metadata, mip).map(|data| (data.len(), data)))
+                .collect::<Result<Vec<_>>>()?;
+            (SUPERCOMPRESSION_NONE, levels)
+        }
+        #[cfg(feature = "zstd")]
+        Supercompression::Zstd { level } => {
+            let levels = (0..metadata.mipLevels)
+                .map(|mip| {
+                    let data = level_data(images, metadata, mip)?;
+                    let compressed =
+                        zstd::bulk::compress(&data, level).map_err(|_| error::fail())?;
+                    Ok((data.len(), compressed))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            (SUPERCOMPRESSION_ZSTD, levels)
+        }
+    };
+    let supercompressed = scheme != SUPERCOMPRESSION_NONE;
+
+    let dfd = data_format_descriptor(format, premultiplied, supercompressed);
+    let kvd = key_value_data();
+    let dfd_offset = HEADER_SIZE + LEVEL_INDEX_ENTRY_SIZE * levels.len();
+    let kvd_offset = dfd_offset + dfd.len();
+
+    // Levels are stored smallest first, aligned to the texel block size and 4 bytes unless they
+    // are supercompressed
+    let alignment = if supercompressed { 1 } else { format.block_size().max(4) };
+    let mut offsets = vec![0; levels.len()];
+    let mut end = kvd_offset + kvd.len();
+    for (mip, (_, data)) in levels.iter().enumerate().rev() {
+        end = end.next_multiple_of(alignment);
+        offsets[mip] = end;
+        end += data.len();
+    }
+
+    let mut buffer = Vec::with_capacity(end);
+    buffer.extend_from_slice(&IDENTIFIER);
+    for value in [
+        format.vk,
+        format.type_size,
+        to_u32(metadata.width)?,
+        to_u32(height)?,
+        to_u32(depth)?,
+        to_u32(if layers > 1 { layers } else { 0 })?,
+        to_u32(faces)?,
+        to_u32(metadata.mipLevels)?,
+        scheme,
+        to_u32(dfd_offset)?,
+        to_u32(dfd.len())?,
+        to_u32(kvd_offset)?,
+        to_u32(kvd.len())?,
+    ] {
+        push_u32(&mut buffer, value);
+    }
+    // No supercompression global data
+    push_u64(&mut buffer, 0);
+    push_u64(&mut buffer, 0);
+
+    for ((uncompressed_size, data), offset) in levels.iter().zip(&offsets) {
+        push_u64(&mut buffer, *offset as u64);
+        push_u64(&mut buffer, data.len() as u64);
+        push_u64(&mut buffer, *uncompressed_size as u64);
+    }
+    buffer.extend_from_slice(&dfd);
+    buffer.extend_from_slice(&kvd);
+    for ((_, data), offset) in levels.iter().zip(&offsets).rev() {
+        buffer.resize(*offset, 0);
+        buffer.extend_from_slice(data);
+    }
+
+    let mut blob = Blob::new(buffer.len())?;
+    blob.buffer_mut().copy_from_slice(&buffer);
+    Ok(blob)
+}
+
+pub fn save(
+    file_name: impl AsRef<Path>,
+    images: &[Image],
+    metadata: &TexMetadata,
+    supercompression: Supercompression,
+) -> Result<()> {
+    let buffer = save_to_memory(images, metadata, supercompression)?;
+    std::fs::write(file_name, buffer.buffer()).map_err(|_| error::fail())
+}
+
+struct Header {
+    metadata:         TexMetadata,
+    supercompression: u32,
+    levels:           usize,
+}
+
+// The premultiplied alpha flag lives in the data format descriptor, after the level index
+fn parse_header(buffer: &[u8]) -> Result<Header> {
+    let dfd_offset = read_u32(buffer, 48)? as usize;
+    let dfd_flags = buffer.get(dfd_offset + 15).copied().unwrap_or_default();
+    parse_header_with_flags(buffer, dfd_flags)
+}
+
+fn parse_header_with_flags(buffer: &[u8], dfd_flags: u8) -> Result<Header> {
+    if buffer.get(..IDENTIFIER.len()) != Some(IDENTIFIER.as_slice()) {
+        return Err(error::fail());
+    }
+
+    let vk_format = read_u32(buffer, 12)?;
+    let width = read_u32(buffer, 20)? as usize;
+    let height = read_u32(buffer, 24)? as usize;
+    let depth = read_u32(buffer, 28)? as usize;
+    let layers = read_u32(buffer, 32)? as usize;
+    let faces = read_u32(buffer, 36)? as usize;
+    let levels = read_u32(buffer, 40)? as usize;
+    let supercompression = read_u32(buffer, 44)?;
+
+    let format = by_vk(vk_format).ok_or_else(error::not_supported)?;
+    if width == 0 || (faces != 1 && faces != 6) || (depth > 0 && (faces != 1 || layers > 0)) {
+        return Err(error::fail());
+    }
+    // No more levels than the full mip chain, so the sizes of every level can be shifted out
+    let full_chain = usize::BITS - width.max(height).max(depth).leading_zeros();
+    if levels > full_chain as usize {
+        return Err(error::fail());
+    }
+
+    let dimension = match (height, depth) {
+        (0, 0) => TEX_DIMENSION::TEX_DIMENSION_TEXTURE1D,
+        (_, 0) => TEX_DIMENSION::TEX_DIMENSION_TEXTURE2D,
+        _ => TEX_DIMENSION::TEX_DIMENSION_TEXTURE3D,
+    };
+    let metadata = TexMetadata {
+        width,
+        height: height.max(1),
+        depth: depth.max(1),
+        arraySize: layers.max(1).checked_mul(faces).ok_or_else(error::fail)?,
+        // A level count of zero asks the loader to generate mips, only the base level is stored
+        mipLevels: levels.max(1),
+        miscFlags: if faces == 6 { TEX_MISC_TEXTURECUBE } else { 0 },
+        miscFlags2: if dfd_flags & KHR_DF_FLAG_ALPHA_PREMULTIPLIED != 0 {
+            TEX_ALPHA_MODE_PREMULTIPLIED
+        } else {
+            0
+        },
+        format: format_from_raw(format.dxgi),
+        dimension,
+    };
+
+    Ok(Header {
+        metadata,
+        supercompression,
+        levels: levels.max(1),
+    })
+}
+
+pub fn metadata_from_memory(buffer: &[u8]) -> Result<TexMetadata> {
+    parse_header(buffer).map(|header| header.metadata)
+}
+
+// Reads the header and the one byte of the data format descriptor it needs, not the whole file
+pub fn metadata(file_name: impl AsRef<Path>) -> Result<TexMetadata> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let mut file = std::fs::File::open(file_name).map_err(|error| error::io(&error))?;
+    let mut buffer = Vec::with_capacity(HEADER_SIZE);
+    (&mut file)
+        .take(HEADER_SIZE as u64)
+        .read_to_end(&mut buffer)
+        .map_err(|error| error::io(&error))?;
+
+    let mut dfd_flags = 0;
+    file.seek(SeekFrom::Start(u64::from(read_u32(&buffer, 48)?) + 15))
+        .and_then(|_| file.read(std::slice::from_mut(&mut dfd_flags)))
+        .map_err(|error| error::io(&error))?;
+    parse_header_with_flags(&buffer, dfd_flags).map(|header| header.metadata)
+}
+
+fn decode_level(buffer: &[u8], supercompression: u32, expected: usize) -> Result<Cow<[u8]>> {
+    match supercompression {
+        SUPERCOMPRESSION_NONE if buffer.len() == expected => Ok(Cow::Borrowed(buffer)),
+        SUPERCOMPRESSION_NONE => Err(error::fail()),
+        #[cfg(feature = "zstd")]
+        SUPERCOMPRESSION_ZSTD => {
+            let level = zstd::bulk::decompress(buffer, expected).map_err(|_| error::fail())?;
+            if level.len() == expected { Ok(Cow::Owned(level)) } else { Err(error::fail()) }
+        }
+        _ => Err(error::not_supported()),
+    }
+}
+
+pub fn load_from_memory(buffer: &[u8]) -> Result<ScratchImage> {
+    let header = parse_header(buffer)?;
+    let metadata = header.metadata;
+
+    let (items, is_volume) = if metadata.dimension == TEX_DIMENSION::TEX_DIMENSION_TEXTURE3D {
+        (1, true)
+    } else {
+        (metadata.arraySize, false)
+    };
+    // Row size, row count, depth slices and the tightly packed size of a level
+    let level_size = |mip: usize| -> Result<(usize, usize, usize, usize)> {
+        let width = (metadata.width >> mip).max(1);
+        let height = (metadata.height >> mip).max(1);
+        let slices = if is_volume { (metadata.depth >> mip).max(1) } else { 1 };
+        let (row_size, slice_size) =
+            compute_pitch(metadata.format, width, height, CP_FLAGS::default())?;
+        let size = slice_size.checked_mul(items * slices).ok_or_else(error::fail)?;
+        Ok((row_size, compute_scanlines(metadata.format, height), slices, size))
+    };
+
+    // The whole level index has to fit the buffer and agree with the header before the pixels
+    // are allocated, so a short or lying file can't make us allocate for data it doesn't have
+    let levels = (0..header.levels)
+        .map(|mip| {
+            let entry = HEADER_SIZE + mip * LEVEL_INDEX_ENTRY_SIZE;
+            let offset = to_usize(read_u64(buffer, entry)?)?;
+            let length = to_usize(read_u64(buffer, entry + 8)?)?;
+            let uncompressed = to_usize(read_u64(buffer, entry + 16)?)?;
+            let data = offset
+                .checked_add(length)
+                .and_then(|end| buffer.get(offset..end))
+                .ok_or_else(error::fail)?;
+            let (_, _, _, size) = level_size(mip)?;
+            if uncompressed != size
+                || (header.supercompression == SUPERCOMPRESSION_NONE && length != size)
+            {
+                return Err(error::fail());
+            }
+            Ok(data)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut image = ScratchImage::initialize(&metadata)?;
+    for (mip, data) in levels.into_iter().enumerate() {
+        let (row_size, rows, slices, size) = level_size(mip)?;
+
+        let level = decode_level(data, header.supercompression, size)?;
+        let mut source = level.chunks_exact(row_size);
+        for item in 0..items {
+            for slice in 0..slices {
+                let index = image_index(&metadata, mip, item, slice);
+                let row_pitch = image.images()[index].row_pitch();
+                let pixels = image.image_buffer_mut(index).ok_or_else(error::fail)?;
+                for row in 0..rows {
+                    let source = source.next().ok_or_else(error::fail)?;
+                    pixels[row * row_pitch..][..row_size].copy_from_slice(source);
+                }
+            }
+        }
+    }
+
+    Ok(image)
+}
+
+pub fn load_from_memory_with_limits(buffer: &[u8], limits: &LoadLimits) -> Result<ScratchImage> {
+    limits.check(&metadata_from_memory(buffer)?)?;
+    load_from_memory(buffer)
+}
+
+pub fn load(file_name: impl AsRef<Path>) -> Result<ScratchImage> {
+    load_from_memory(&read_file(file_name.as_ref())?)
+}
+
+pub fn load_with_limits(file_name: impl AsRef<Path>, limits: &LoadLimits) -> Result<ScratchImage> {
+    let buffer = read_file_with_limits(file_name.as_ref(), limits)?;
+    load_from_memory_with_limits(&buffer, limits)
+}
@@ -1,5 +1,232 @@
-pub mod dds;
-pub mod exr;
-pub mod hdr;
-pub mod tga;
-#[cfg(windows)] pub mod wic;
+#[cfg(feature = "directxtex")] use std::ffi::OsStr;
+#[cfg(feature = "directxtex")] use std::path::Path;
+
+// png and jpeg wrap DirectXTex's file only libpng and libjpeg readers and writers the same way,
+// with memory going through temporary files
+#[cfg(any(feature = "png", feature = "jpeg"))]
+macro_rules! file_codec {
+    ($ext:literal, $metadata:ident, $load:ident, $save:ident) => {
+        use std::path::Path;
+        use std::ptr;
+
+        use directxtex_sys::{self as sys, TexMetadata};
+
+        use $crate::error::hresult;
+        use $crate::formats::{read_file_with_limits, read_temp, with_temp_file, write_temp};
+        use $crate::limits::LoadLimits;
+        use $crate::{Blob, CWide, Error, Image, Result, ScratchImage};
+
+        pub fn metadata<'file_name, CWIDE>(file_name: CWIDE) -> Result<TexMetadata>
+        where
+            CWide<'file_name>: TryFrom<CWIDE>,
+            Error: From<<CWide<'file_name> as TryFrom<CWIDE>>::Error>,
+        {
+            let file_name: CWide = file_name.try_into()?;
+            let mut metadata = TexMetadata::default();
+            hresult(unsafe { sys::$metadata(file_name.as_ptr(), &mut metadata) })?;
+            Ok(metadata)
+        }
+
+        pub fn metadata_from_memory(buffer: &[u8]) -> Result<TexMetadata> {
+            with_temp_file($ext, |file_name| {
+                write_temp(file_name, buffer)?;
+                metadata(file_name)
+            })
+        }
+
+        pub fn load<'file_name, CWIDE>(file_name: CWIDE) -> Result<ScratchImage>
+        where
+            CWide<'file_name>: TryFrom<CWIDE>,
+            Error: From<<CWide<'file_name> as TryFrom<CWIDE>>::Error>,
+        {
+            let file_name: CWide = file_name.try_into()?;
+            let mut out = sys::ScratchImage::default();
+
+            hresult(unsafe { sys::$load(file_name.as_ptr(), ptr::null_mut(), &mut out) })
+                .map(|_| ScratchImage(out))
+        }
+
+        pub fn load_from_memory(buffer: &[u8]) -> Result<ScratchImage> {
+            with_temp_file($ext, |file_name| {
+                write_temp(file_name, buffer)?;
+                load(file_name)
+            })
+        }
+
+        // `load` after checking `limits` against the header
+        pub fn load_with_limits(
+            file_name: impl AsRef<Path>,
+            limits: &LoadLimits,
+        ) -> Result<ScratchImage> {
+            let buffer = read_file_with_limits(file_name.as_ref(), limits)?;
+            load_from_memory_with_limits(&buffer, limits)
+        }
+
+        pub fn load_from_memory_with_limits(
+            buffer: &[u8],
+            limits: &LoadLimits,
+        ) -> Result<ScratchImage> {
+            limits.check(&metadata_from_memory(buffer)?)?;
+            load_from_memory(buffer)
+        }
+
+        pub fn save<'file_name, CWIDE>(file_name: CWIDE, image: &Image) -> Result<()>
+        where
+            CWide<'file_name>: TryFrom<CWIDE>,
+            Error: From<<CWide<'file_name> as TryFrom<CWIDE>>::Error>,
+        {
+            let file_name: CWide = file_name.try_into()?;
+            hresult(unsafe { sys::$save(image.as_ptr(), file_name.as_ptr()) })
+        }
+
+        pub fn save_to_memory(image: &Image) -> Result<Blob> {
+            with_temp_file($ext, |file_name| {
+                save(file_name, image)?;
+                read_temp(file_name)
+            })
+        }
+    };
+}
+
+#[cfg(feature = "directxtex")] pub mod dds;
+#[cfg(not(feature = "directxtex"))]
+pub mod dds {
+    #[cfg(feature = "pure-rust-dds")] pub mod header;
+}
+#[cfg(feature = "directxtex")] pub mod exr;
+#[cfg(feature = "directxtex")] pub mod hdr;
+#[cfg(feature = "jpeg")] pub mod jpeg;
+#[cfg(feature = "directxtex")] pub mod ktx;
+#[cfg(feature = "directxtex")] pub mod ktx2;
+#[cfg(feature = "png")] pub mod png;
+#[cfg(feature = "directxtex")] pub mod tga;
+#[cfg(all(windows, feature = "directxtex"))] pub mod wic;
+
+#[cfg(feature = "directxtex")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FileType {
+    Dds,
+    Tga,
+    Hdr,
+    Exr,
+    Ktx2,
+    #[cfg(feature = "png")]
+    Png,
+    #[cfg(feature = "jpeg")]
+    Jpeg,
+    #[cfg(windows)]
+    Wic(crate::WICCodecs),
+}
+
+#[cfg(feature = "directxtex")]
+impl FileType {
+    #[must_use]
+    pub fn from_ext(ext: impl AsRef<OsStr>) -> Option<Self> {
+        let ext: &OsStr = ext.as_ref();
+        match ext {
+            ext if ext.eq_ignore_ascii_case("dds") => Some(Self::Dds),
+            ext if ext.eq_ignore_ascii_case("tga") => Some(Self::Tga),
+            ext if ext.eq_ignore_ascii_case("hdr") => Some(Self::Hdr),
+            ext if ext.eq_ignore_ascii_case("exr") => Some(Self::Exr),
+            ext if ext.eq_ignore_ascii_case("ktx2") => Some(Self::Ktx2),
+            // WIC is preferred on Windows, libpng and libjpeg are for everywhere else
+            #[cfg(all(feature = "png", not(windows)))]
+            ext if ext.eq_ignore_ascii_case("png") => Some(Self::Png),
+            #[cfg(all(feature = "jpeg", not(windows)))]
+            ext if ext.eq_ignore_ascii_case("jpg") || ext.eq_ignore_ascii_case("jpeg") => {
+                Some(Self::Jpeg)
+            }
+            #[cfg(windows)]
+            ext => wic::wic_codec_by_ext(ext).map(Self::Wic),
+            #[cfg(not(windows))]
+            _ => None,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn from_path(file_name: impl AsRef<Path>) -> Option<Self> {
+        file_name.as_ref().extension().and_then(Self::from_ext)
+    }
+}
+
+// libpng, libjpeg and OpenEXR are only wired up to files in DirectXTex, so memory goes through a
+// temporary file. It lives in a new directory with a random name that only this user can open,
+// so nobody else can swap it out or read it.
+#[cfg(feature = "directxtex")]
+pub(crate) fn with_temp_file<T>(
+    ext: &str,
+    func: impl FnOnce(&Path) -> crate::Result<T>,
+) -> crate::Result<T> {
+    let dir = tempfile::Builder::new()
+        .prefix("directxtex-")
+        .tempdir()
+        .map_err(|error| crate::error::io(&error))?;
+    let result = func(&dir.path().join(format!("image.{ext}")));
+    let _ = dir.close();
+    result
+}
+
+#[cfg(feature = "directxtex")]
+pub(crate) fn write_temp(file_name: &Path, buffer: &[u8]) -> crate::Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+        .open(file_name)
+        .and_then(|mut file| file.write_all(buffer))
+        .map_err(|error| crate::error::io(&error))
+}
+
+#[cfg(any(feature = "png", feature = "jpeg"))]
+pub(crate) fn read_temp(file_name: &Path) -> crate::Result<crate::Blob> {
+    let buffer = std::fs::read(file_name).map_err(|error| crate::error::io(&error))?;
+    let mut blob = crate::Blob::new(buffer.len())?;
+    blob.buffer_mut().copy_from_slice(&buffer);
+    Ok(blob)
+}
+
+#[cfg(feature = "directxtex")]
+pub(crate) fn read_file(file_name: &Path) -> crate::Result<Vec<u8>> {
+    std::fs::read(file_name).map_err(|error| crate::error::io(&error))
+}
+
+// Headers and metadata a file may hold on top of its pixels
+#[cfg(feature = "directxtex")]
+const MAX_FILE_OVERHEAD: u64 = 1 << 20;
+
+// The `load_with_limits` variants read the file once and check the limits against those bytes,
+// so the file can't be swapped between the check and the load. Files bigger than `max_bytes`
+// allows are rejected before anything is read.
+#[cfg(feature = "directxtex")]
+pub(crate) fn read_file_with_limits(
+    file_name: &Path,
+    limits: &crate::LoadLimits,
+) -> crate::Result<Vec<u8>> {
+    use std::io::Read;
+
+    let max = limits.max_bytes.saturating_add(MAX_FILE_OVERHEAD);
+    let too_large = |value| crate::limits::LimitExceeded {
+        limit: crate::limits::Limit::Bytes,
+        value,
+        max,
+    };
+    let file = std::fs::File::open(file_name).map_err(|error| crate::error::io(&error))?;
+    let len = file.metadata().map_err(|error| crate::error::io(&error))?.len();
+    if len > max {
+        return Err(too_large(len).into());
+    }
+
+    // The file can still grow after the check
+    let mut buffer = Vec::with_capacity(len as usize);
+    file.take(max.saturating_add(1))
+        .read_to_end(&mut buffer)
+        .map_err(|error| crate::error::io(&error))?;
+    if buffer.len() as u64 > max {
+        return Err(too_large(buffer.len() as u64).into());
+    }
+    Ok(buffer)
+}
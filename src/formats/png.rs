@@ -0,0 +1 @@
+file_codec!("png", GetMetadataFromPNGFile, LoadFromPNGFile, SaveToPNGFile);
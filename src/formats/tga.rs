@@ -1,8 +1,11 @@
+use std::path::Path;
 use std::ptr;
 
 use directxtex_sys::{self as sys, TexMetadata, TGA_FLAGS};
 
 use crate::error::hresult;
+use crate::formats::read_file_with_limits;
+use crate::limits::LoadLimits;
 use crate::{Blob, CWide, Error, Image, Result, ScratchImage};
 
 pub fn metadata<'file_name, CWIDE>(file_name: CWIDE, tga_flags: TGA_FLAGS) -> Result<TexMetadata>
@@ -53,6 +56,25 @@ pub fn load_from_memory(buffer: &[u8], tga_flags: TGA_FLAGS) -> Result<ScratchIm
     .map(|_| ScratchImage(out))
 }
 
+// `load` after checking `limits` against the header
+pub fn load_with_limits(
+    file_name: impl AsRef<Path>,
+    tga_flags: TGA_FLAGS,
+    limits: &LoadLimits,
+) -> Result<ScratchImage> {
+    let buffer = read_file_with_limits(file_name.as_ref(), limits)?;
+    load_from_memory_with_limits(&buffer, tga_flags, limits)
+}
+
+pub fn load_from_memory_with_limits(
+    buffer: &[u8],
+    tga_flags: TGA_FLAGS,
+    limits: &LoadLimits,
+) -> Result<ScratchImage> {
+    limits.check(&metadata_from_memory(buffer, tga_flags)?)?;
+    load_from_memory(buffer, tga_flags)
+}
+
 pub fn save<'file_name, CWIDE>(
     file_name: CWIDE,
     image: &Image,
@@ -70,5 +92,5 @@ where
 pub fn save_to_memory(image: &Image, metadata: &TexMetadata, tga_flags: TGA_FLAGS) -> Result<Blob> {
     let mut blob = sys::Blob::default();
     hresult(unsafe { sys::SaveToTGAMemory(image.as_ptr(), tga_flags, &mut blob, metadata) })
-        .map(|_| Blob(blob))
+        .map(|_| Blob::owned(blob))
 }
@@ -1,10 +1,13 @@
 use std::ffi::OsStr;
+use std::path::Path;
 use std::ptr;
 
 use directxtex_sys::{self as sys, GetWICCodec, TexMetadata, WICCodecs, GUID, WIC_FLAGS};
 
 use crate::error::hresult;
-use crate::{Blob, CWide, Error, Image, Result, ScratchImage};
+use crate::formats::read_file_with_limits;
+use crate::limits::LoadLimits;
+use crate::{Blob, CWide, Error, Image, Orientation, Result, ScratchImage};
 
 pub fn metadata<'file_name, CWIDE>(file_name: CWIDE, wic_flags: WIC_FLAGS) -> Result<TexMetadata>
 where
@@ -78,6 +81,36 @@ pub fn load_from_memory(buffer: &[u8], wic_flags: WIC_FLAGS) -> Result<ScratchIm
     .map(|_| ScratchImage(out))
 }
 
+// `load` after checking `limits` against the header
+pub fn load_with_limits(
+    file_name: impl AsRef<Path>,
+    wic_flags: WIC_FLAGS,
+    limits: &LoadLimits,
+) -> Result<ScratchImage> {
+    let buffer = read_file_with_limits(file_name.as_ref(), limits)?;
+    load_from_memory_with_limits(&buffer, wic_flags, limits)
+}
+
+pub fn load_from_memory_with_limits(
+    buffer: &[u8],
+    wic_flags: WIC_FLAGS,
+    limits: &LoadLimits,
+) -> Result<ScratchImage> {
+    limits.check(&metadata_from_memory(buffer, wic_flags)?)?;
+    load_from_memory(buffer, wic_flags)
+}
+
+// Like load_from_memory but applies the JPEG Exif orientation, which WIC leaves to the caller
+pub fn load_upright_from_memory(buffer: &[u8], wic_flags: WIC_FLAGS) -> Result<ScratchImage> {
+    let image = load_from_memory(buffer, wic_flags)?;
+    match Orientation::from_jpeg(buffer) {
+        Some(orientation) if orientation != Orientation::Normal => {
+            image.normalize_orientation(orientation)
+        }
+        _ => Ok(image),
+    }
+}
+
 pub fn save<'file_name, CWIDE>(
     file_name: CWIDE,
     container: WICCodecs,
@@ -140,7 +173,7 @@ pub fn save_to_memory(container: WICCodecs, image: &Image, wic_flags: WIC_FLAGS)
             std::ptr::null_mut(),
         )
     })
-    .map(|_| Blob(blob))
+    .map(|_| Blob::owned(blob))
 }
 
 pub fn save_slice_to_memory(
@@ -161,7 +194,7 @@ pub fn save_slice_to_memory(
             std::ptr::null_mut(),
         )
     })
-    .map(|_| Blob(blob))
+    .map(|_| Blob::owned(blob))
 }
 
 #[must_use]
@@ -175,6 +208,7 @@ pub fn wic_guid(codec: WICCodecs) -> &'static GUID {
 pub fn wic_guid_by_ext(ext: &OsStr) -> Option<&'static GUID> { wic_codec_by_ext(ext).map(wic_guid) }
 
 pub const WIC_CODEC_BMP: WICCodecs = WICCodecs::WIC_CODEC_BMP;
+pub const WIC_CODEC_GIF: WICCodecs = WICCodecs::WIC_CODEC_GIF;
 pub const WIC_CODEC_WMP: WICCodecs = WICCodecs::WIC_CODEC_WMP;
 pub const WIC_CODEC_HEIF: WICCodecs = WICCodecs::WIC_CODEC_HEIF;
 pub const WIC_CODEC_JPEG: WICCodecs = WICCodecs::WIC_CODEC_JPEG;
@@ -187,6 +221,7 @@ pub fn wic_codec_by_ext(ext: impl AsRef<OsStr>) -> Option<WICCodecs> {
     let ext: &OsStr = ext.as_ref();
     match ext {
         ext if ext.eq_ignore_ascii_case("bmp") => Some(WIC_CODEC_BMP),
+        ext if ext.eq_ignore_ascii_case("gif") => Some(WIC_CODEC_GIF),
         ext if ext.eq_ignore_ascii_case("hdp") => Some(WIC_CODEC_WMP),
         ext if ext.eq_ignore_ascii_case("heic") => Some(WIC_CODEC_HEIF),
         ext if ext.eq_ignore_ascii_case("heif") => Some(WIC_CODEC_HEIF),
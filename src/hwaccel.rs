@@ -1,3 +1,5 @@
+use std::sync::{Mutex, PoisonError};
+
 use directxtex_sys::DXGI_FORMAT;
 use once_cell::sync::OnceCell;
 use windows::Win32::Foundation::E_FAIL;
@@ -69,3 +71,12 @@ pub fn hwdevice() -> Result<ID3D11Device> {
     let device = HWDEVICE.get_or_try_init(try_create_device)?;
     Ok(device.clone())
 }
+
+// The device's immediate context isn't thread-safe, so only one compression uses it at a time
+static HWLOCK: Mutex<()> = Mutex::new(());
+
+pub fn with_hwdevice<T>(func: impl FnOnce(&ID3D11Device) -> Result<T>) -> Result<T> {
+    let device = hwdevice()?;
+    let _guard = HWLOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    func(&device)
+}
@@ -1,9 +1,9 @@
 #![allow(unsafe_code)]
 
-use std::ffi::OsStr;
-use std::path::Path;
+#[cfg(feature = "directxtex")] use std::path::Path;
 
-use directxtex_sys as sys;
+#[cfg(feature = "directxtex")] use directxtex_sys as sys;
+#[cfg(feature = "directxtex")]
 pub use sys::{
     Blob as RawBlob,
     Image as RawImage,
@@ -14,27 +14,60 @@ pub use sys::{
     DXGI_FORMAT,
     TEX_ALPHA_MODE,
     TEX_DIMENSION,
+    XMVECTORF32,
 };
 
-#[cfg_attr(not(feature = "windows"), path = "error_compat.rs")] pub(crate) mod error;
+#[cfg_attr(not(feature = "windows"), path = "error_compat.rs")]
+#[cfg_attr(not(feature = "directxtex"), allow(dead_code))]
+pub(crate) mod error;
 
 pub use error::{Error, Result};
 
-mod cwide;
-pub mod flags;
-#[cfg(feature = "hwaccel")] pub(crate) mod hwaccel;
-pub mod ops;
-pub mod scratch;
-mod types;
-pub mod util;
-pub use cwide::CWide;
-pub use scratch::ScratchImage;
-pub use types::{Blob, Image};
+#[cfg(feature = "directxtex")] pub mod atlas;
+#[cfg(feature = "rayon")] pub mod batch;
+#[cfg(feature = "directxtex")] mod cwide;
+#[cfg(feature = "directxtex")] pub mod file_set;
+#[cfg(feature = "image")] mod dynamic_image;
+#[cfg(feature = "directxtex")] pub mod flags;
+#[cfg(all(windows, feature = "hwaccel"))] pub(crate) mod hwaccel;
+mod metadata;
+#[cfg(feature = "directxtex")] pub mod limits;
+#[cfg(feature = "directxtex")] pub mod ops;
+#[cfg(feature = "directxtex")] pub mod options;
+#[cfg(feature = "directxtex")] pub mod orientation;
+#[cfg(feature = "directxtex")] pub mod pipeline;
+#[cfg(feature = "directxtex")] pub mod preset;
+#[cfg(feature = "directxtex")] pub mod scratch;
+#[cfg(all(feature = "directxtex", feature = "testing"))] pub mod testing;
+#[cfg(feature = "directxtex")] mod types;
+#[cfg(feature = "directxtex")] pub mod util;
+#[cfg(not(feature = "directxtex"))]
+pub mod util {
+    // The table from directxtex_sys, which isn't a dependency without DirectXTex
+    #[path = "../../sys/src/format.rs"]
+    mod format_table;
+    mod layout;
+    pub use format_table::*;
+    pub use layout::*;
+}
+#[cfg(feature = "directxtex")] pub use cwide::CWide;
+#[cfg(not(feature = "directxtex"))]
+pub use metadata::{TexMetadata, DXGI_FORMAT, TEX_ALPHA_MODE, TEX_DIMENSION};
+#[cfg(feature = "directxtex")] pub use limits::LoadLimits;
+#[cfg(feature = "directxtex")] pub use options::{LoadOptions, SaveOptions};
+#[cfg(feature = "directxtex")] pub use orientation::Orientation;
+#[cfg(feature = "directxtex")] pub use preset::TexturePreset;
+#[cfg(feature = "directxtex")] pub use scratch::ScratchImage;
+#[cfg(feature = "directxtex")] pub use types::{Blob, Image};
 mod formats;
-use flags::{DDS_FLAGS, TGA_FLAGS, WIC_FLAGS};
-#[cfg(windows)] pub use formats::wic;
-pub use formats::{dds, exr, hdr, tga};
-
+#[cfg(feature = "directxtex")] use flags::{DDS_FLAGS, TGA_FLAGS, WIC_FLAGS};
+#[cfg(feature = "jpeg")] pub use formats::jpeg;
+#[cfg(feature = "png")] pub use formats::png;
+#[cfg(all(windows, feature = "directxtex"))] pub use formats::wic;
+pub use formats::dds;
+#[cfg(feature = "directxtex")] pub use formats::{exr, hdr, ktx, ktx2, tga, FileType};
+
+#[cfg(feature = "directxtex")]
 pub fn compress_texture(
     format: DXGI_FORMAT,
     width: usize,
@@ -56,6 +89,7 @@ pub fn compress_texture(
     Ok(compressed.buffer().to_vec())
 }
 
+#[cfg(feature = "directxtex")]
 pub fn decompress_texture(
     format: DXGI_FORMAT,
     width: usize,
@@ -70,58 +104,127 @@ pub fn decompress_texture(
     Ok(decompressed.buffer().to_vec())
 }
 
+// KTX1 can only be read, so it isn't a FileType
+#[cfg(feature = "directxtex")]
+fn is_ktx1(file_name: &Path) -> bool {
+    file_name.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("ktx"))
+}
+
+// The file type comes from `FileType::from_path` here and in `load`, `load_with_limits`, `save`
+// and the options, so a file always goes through the same backend
+#[cfg(feature = "directxtex")]
 pub fn metadata(file_name: impl AsRef<Path>) -> Result<TexMetadata> {
     let file_name = file_name.as_ref();
+    if is_ktx1(file_name) {
+        return ktx::metadata(file_name).map(|metadata| metadata.to_metadata());
+    }
 
-    match file_name.extension().and_then(OsStr::to_str) {
-        Some(ext) if ext.eq_ignore_ascii_case("dds") => {
-            dds::metadata(file_name, DDS_FLAGS::default())
-        }
-        Some(ext) if ext.eq_ignore_ascii_case("tga") => {
-            tga::metadata(file_name, TGA_FLAGS::default())
-        }
-        Some(ext) if ext.eq_ignore_ascii_case("hdr") => hdr::metadata(file_name),
-        Some(ext) if ext.eq_ignore_ascii_case("exr") => exr::metadata(file_name),
+    match FileType::from_path(file_name).ok_or_else(error::invalid_arg)? {
+        FileType::Dds => dds::metadata(file_name, DDS_FLAGS::default()),
+        FileType::Tga => tga::metadata(file_name, TGA_FLAGS::default()),
+        FileType::Hdr => hdr::metadata(file_name),
+        FileType::Exr => exr::metadata(file_name),
+        FileType::Ktx2 => ktx2::metadata(file_name),
+        #[cfg(feature = "png")]
+        FileType::Png => png::metadata(file_name),
+        #[cfg(feature = "jpeg")]
+        FileType::Jpeg => jpeg::metadata(file_name),
         #[cfg(windows)]
-        Some(_) => wic::metadata(file_name, WIC_FLAGS::default()),
-        _ => Err(error::invalid_arg()),
+        FileType::Wic(_) => wic::metadata(file_name, WIC_FLAGS::default()),
     }
 }
 
+// Loads without limits, use `load_with_limits` for untrusted files
+#[cfg(feature = "directxtex")]
 pub fn load(file_name: impl AsRef<Path>) -> Result<ScratchImage> {
     let file_name = file_name.as_ref();
+    if is_ktx1(file_name) {
+        return ktx::load(file_name);
+    }
 
-    match file_name.extension().and_then(OsStr::to_str) {
-        Some(ext) if ext.eq_ignore_ascii_case("dds") => dds::load(file_name, DDS_FLAGS::default()),
-        Some(ext) if ext.eq_ignore_ascii_case("tga") => tga::load(file_name, TGA_FLAGS::default()),
-        Some(ext) if ext.eq_ignore_ascii_case("hdr") => hdr::load(file_name),
-        Some(ext) if ext.eq_ignore_ascii_case("exr") => exr::load(file_name),
+    match FileType::from_path(file_name).ok_or_else(error::invalid_arg)? {
+        FileType::Dds => dds::load(file_name, DDS_FLAGS::default()),
+        FileType::Tga => tga::load(file_name, TGA_FLAGS::default()),
+        FileType::Hdr => hdr::load(file_name),
+        FileType::Exr => exr::load(file_name),
+        FileType::Ktx2 => ktx2::load(file_name),
+        #[cfg(feature = "png")]
+        FileType::Png => png::load(file_name),
+        #[cfg(feature = "jpeg")]
+        FileType::Jpeg => jpeg::load(file_name),
         #[cfg(windows)]
-        Some(_) => wic::load(file_name, WIC_FLAGS::default()),
-        _ => Err(error::invalid_arg()),
+        FileType::Wic(_) => wic::load(file_name, WIC_FLAGS::default()),
     }
 }
 
-pub fn save(file_name: impl AsRef<Path>, image: &Image, metadata: &TexMetadata) -> Result<()> {
+// Like `load`, but the file is rejected with a `limits::is_limit_exceeded` error when it or its
+// header is over `limits`, before any pixels are decoded
+#[cfg(feature = "directxtex")]
+pub fn load_with_limits(file_name: impl AsRef<Path>, limits: &LoadLimits) -> Result<ScratchImage> {
+    load_with(file_name, &LoadOptions::new().limits(*limits))
+}
+
+// Like `load`, with the flags, file type, target format and limits in `options`
+#[cfg(feature = "directxtex")]
+pub fn load_with(file_name: impl AsRef<Path>, options: &LoadOptions) -> Result<ScratchImage> {
     let file_name = file_name.as_ref();
+    let limits = &options.limits;
 
-    match file_name.extension().and_then(OsStr::to_str) {
-        Some(ext) if ext.eq_ignore_ascii_case("dds") => {
-            dds::save(file_name, image, DDS_FLAGS::default())
-        }
-        Some(ext) if ext.eq_ignore_ascii_case("tga") => {
-            tga::save(file_name, image, metadata, TGA_FLAGS::default())
+    let file_type = match options.file_type {
+        Some(file_type) => file_type,
+        None if is_ktx1(file_name) => {
+            return options.finish(ktx::load_with_limits(file_name, limits)?);
         }
-        Some(ext) if ext.eq_ignore_ascii_case("hdr") => hdr::save(file_name, image),
-        Some(ext) if ext.eq_ignore_ascii_case("exr") => exr::save(file_name, image),
+        None => FileType::from_path(file_name).ok_or_else(error::invalid_arg)?,
+    };
+    let image = match file_type {
+        FileType::Dds => dds::load_with_limits(file_name, options.to_dds_flags(), limits)?,
+        FileType::Tga => tga::load_with_limits(file_name, options.to_tga_flags(), limits)?,
+        FileType::Hdr => hdr::load_with_limits(file_name, limits)?,
+        FileType::Exr => exr::load_with_limits(file_name, limits)?,
+        FileType::Ktx2 => ktx2::load_with_limits(file_name, limits)?,
+        #[cfg(feature = "png")]
+        FileType::Png => png::load_with_limits(file_name, limits)?,
+        #[cfg(feature = "jpeg")]
+        FileType::Jpeg => jpeg::load_with_limits(file_name, limits)?,
         #[cfg(windows)]
-        Some(ext) => {
-            if let Some(codec) = wic::wic_codec_by_ext(ext) {
-                wic::save(file_name, codec, image, WIC_FLAGS::default())
-            } else {
-                Err(error::invalid_arg())
-            }
-        }
-        _ => Err(error::invalid_arg()),
+        FileType::Wic(_) => wic::load_with_limits(file_name, options.to_wic_flags(), limits)?,
+    };
+    options.finish(image)
+}
+
+#[cfg(feature = "directxtex")]
+pub fn save(file_name: impl AsRef<Path>, image: &Image, metadata: &TexMetadata) -> Result<()> {
+    let file_name = file_name.as_ref();
+    let file_type = FileType::from_path(file_name).ok_or_else(error::invalid_arg)?;
+    save_as(file_name, file_type, image, metadata)
+}
+
+#[cfg(feature = "directxtex")]
+pub fn save_as(
+    file_name: impl AsRef<Path>,
+    file_type: FileType,
+    image: &Image,
+    metadata: &TexMetadata,
+) -> Result<()> {
+    let file_name = file_name.as_ref();
+
+    match file_type {
+        FileType::Dds => dds::save(file_name, image, DDS_FLAGS::default()),
+        FileType::Tga => tga::save(file_name, image, metadata, TGA_FLAGS::default()),
+        FileType::Hdr => hdr::save(file_name, image),
+        FileType::Exr => exr::save(file_name, image),
+        FileType::Ktx2 => ktx2::save(
+            file_name,
+            std::slice::from_ref(image),
+            &image.metadata(),
+            ktx2::Supercompression::default(),
+        ),
+        #[cfg(feature = "png")]
+        FileType::Png => png::save(file_name, image),
+        #[cfg(feature = "jpeg")]
+        FileType::Jpeg => jpeg::save(file_name, image),
+        #[cfg(windows)]
+        FileType::Wic(codec) => wic::save(file_name, codec, image, WIC_FLAGS::default()),
     }
 }
@@ -0,0 +1,143 @@
+#[cfg(feature = "serde")] use serde::{Deserialize, Serialize};
+
+use std::fmt;
+
+use directxtex_sys::{TexMetadata, CP_FLAGS, TEX_DIMENSION};
+
+use crate::util::compute_pitch;
+use crate::Error;
+
+pub use crate::error::is_limit_exceeded;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Limit {
+    Width,
+    Height,
+    Depth,
+    ArraySize,
+    MipLevels,
+    Bytes,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Width => "width",
+            Self::Height => "height",
+            Self::Depth => "depth",
+            Self::ArraySize => "array size",
+            Self::MipLevels => "mip count",
+            Self::Bytes => "size in bytes",
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LimitExceeded {
+    pub limit: Limit,
+    pub value: u64,
+    pub max:   u64,
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "texture {} of {} is over the limit of {}", self.limit, self.value, self.max)
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+// Loaders return this with a code of its own, see `is_limit_exceeded`, and the limit, value and
+// maximum in the message
+impl From<LimitExceeded> for Error {
+    fn from(exceeded: LimitExceeded) -> Self {
+        crate::error::with_message(&crate::error::limit_exceeded(), &exceeded.to_string())
+    }
+}
+
+// Checked against a file's metadata before any pixels are allocated. The defaults are Direct3D
+// 11's resource limits and 1 GiB.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct LoadLimits {
+    pub max_width:  usize,
+    pub max_height: usize,
+    pub max_depth:  usize,
+    pub max_array:  usize,
+    pub max_mips:   usize,
+    // Everything the loaded ScratchImage holds
+    pub max_bytes:  u64,
+}
+
+impl Default for LoadLimits {
+    fn default() -> Self {
+        Self {
+            max_width: 16384,
+            max_height: 16384,
+            max_depth: 2048,
+            max_array: 2048,
+            max_mips: 15,
+            max_bytes: 1 << 30,
+        }
+    }
+}
+
+impl LoadLimits {
+    pub const UNLIMITED: Self = Self {
+        max_width: usize::MAX,
+        max_height: usize::MAX,
+        max_depth: usize::MAX,
+        max_array: usize::MAX,
+        max_mips: usize::MAX,
+        max_bytes: u64::MAX,
+    };
+
+    pub fn check(&self, metadata: &TexMetadata) -> Result<(), LimitExceeded> {
+        let exceeded = |limit, value: usize, max: usize| {
+            if value > max {
+                Err(LimitExceeded {
+                    limit,
+                    value: value as u64,
+                    max: max as u64,
+                })
+            } else {
+                Ok(())
+            }
+        };
+        exceeded(Limit::Width, metadata.width, self.max_width)?;
+        exceeded(Limit::Height, metadata.height, self.max_height)?;
+        exceeded(Limit::Depth, metadata.depth, self.max_depth)?;
+        exceeded(Limit::ArraySize, metadata.arraySize, self.max_array)?;
+        exceeded(Limit::MipLevels, metadata.mipLevels, self.max_mips)?;
+
+        let bytes = image_bytes(metadata).unwrap_or(u64::MAX);
+        if bytes > self.max_bytes {
+            return Err(LimitExceeded {
+                limit: Limit::Bytes,
+                value: bytes,
+                max: self.max_bytes,
+            });
+        }
+        Ok(())
+    }
+}
+
+// Pixel memory of a ScratchImage initialized from `metadata`, None if it doesn't fit in a u64.
+// DirectXTex refuses more mips than the dimensions allow, so at most 64 are counted.
+pub fn image_bytes(metadata: &TexMetadata) -> Option<u64> {
+    let is_volume = metadata.dimension == TEX_DIMENSION::TEX_DIMENSION_TEXTURE3D;
+    let mut total = 0u64;
+    for mip in 0..metadata.mipLevels.min(64) {
+        let width = (metadata.width >> mip).max(1);
+        let height = (metadata.height >> mip).max(1);
+        let (_, slice_pitch) =
+            compute_pitch(metadata.format, width, height, CP_FLAGS::default()).ok()?;
+        let slices = if is_volume { (metadata.depth >> mip).max(1) } else { metadata.arraySize };
+        let level = u64::try_from(slice_pitch).ok()?.checked_mul(slices as u64)?;
+        total = total.checked_add(level)?;
+    }
+    Some(total)
+}
@@ -0,0 +1,46 @@
+#![cfg(not(feature = "directxtex"))]
+#![allow(non_camel_case_types, non_snake_case)]
+
+// Stand-ins with the same layout and names as the bindgen types, for builds that only use the pure
+// Rust parts and don't link DirectXTex
+
+#[cfg(feature = "windows")]
+pub use windows::Win32::Graphics::Dxgi::Common::DXGI_FORMAT;
+#[cfg(not(feature = "windows"))]
+pub type DXGI_FORMAT = u32;
+
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TEX_DIMENSION(pub u32);
+
+impl TEX_DIMENSION {
+    pub const TEX_DIMENSION_TEXTURE1D: Self = Self(2);
+    pub const TEX_DIMENSION_TEXTURE2D: Self = Self(3);
+    pub const TEX_DIMENSION_TEXTURE3D: Self = Self(4);
+}
+
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TEX_ALPHA_MODE(pub u32);
+
+impl TEX_ALPHA_MODE {
+    pub const TEX_ALPHA_MODE_UNKNOWN: Self = Self(0);
+    pub const TEX_ALPHA_MODE_STRAIGHT: Self = Self(1);
+    pub const TEX_ALPHA_MODE_PREMULTIPLIED: Self = Self(2);
+    pub const TEX_ALPHA_MODE_OPAQUE: Self = Self(3);
+    pub const TEX_ALPHA_MODE_CUSTOM: Self = Self(4);
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TexMetadata {
+    pub width:      usize,
+    pub height:     usize,
+    pub depth:      usize,
+    pub arraySize:  usize,
+    pub mipLevels:  usize,
+    pub miscFlags:  u32,
+    pub miscFlags2: u32,
+    pub format:     DXGI_FORMAT,
+    pub dimension:  TEX_DIMENSION,
+}
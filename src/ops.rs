@@ -1,9 +1,38 @@
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+
 use directxtex_sys as sys;
 
-use crate::error::hresult;
-use crate::flags::{TEX_COMPRESS_FLAGS, TEX_FILTER_FLAGS, TEX_FR_FLAGS, TEX_PMALPHA_FLAGS};
+use crate::error::{self, hresult};
+use crate::flags::{
+    CNMAP_FLAGS,
+    TEX_COMPRESS_FLAGS,
+    TEX_FILTER_FLAGS,
+    TEX_FR_FLAGS,
+    TEX_PMALPHA_FLAGS,
+};
 use crate::{Image, Result, ScratchImage};
 
+mod channels;
+mod color;
+mod cubemap;
+mod diff;
+mod histogram;
+mod ibl;
+pub(crate) mod plane;
+mod stats;
+mod tonemap;
+
+pub use channels::{pack_channels, swizzle, Channel};
+pub use color::{hlg_decode, hlg_encode, pq_decode, pq_encode, rotate_color, ColorRotation};
+pub use cubemap::{cube_to_equirect, equirect_to_cube};
+pub use diff::{diff_image, DiffMode};
+pub use histogram::{histogram, luminance_histogram, Histogram};
+pub use ibl::{irradiance_cube, prefilter_ggx, sh9};
+pub use plane::Sampling;
+pub use stats::{statistics, AlphaClass, ChannelStats, ImageStats, Stats};
+pub use tonemap::*;
+
 pub fn generate_mipmaps(
     images: &[Image],
     metadata: &sys::TexMetadata,
@@ -25,7 +54,7 @@ pub fn generate_mipmaps(
     .map(|_| ScratchImage(out))
 }
 
-#[cfg(feature = "windows")]
+#[cfg(all(windows, feature = "windows"))]
 pub fn compress_with_device(
     device: &windows::Win32::Graphics::Direct3D11::ID3D11Device,
     images: &[Image],
@@ -72,10 +101,11 @@ pub fn compress(
     to_format: sys::DXGI_FORMAT,
     flags: TEX_COMPRESS_FLAGS,
 ) -> Result<ScratchImage> {
-    #[cfg(feature = "hwaccel")]
+    #[cfg(all(windows, feature = "hwaccel"))]
     if crate::hwaccel::should_accel(to_format) {
-        let hwdevice = crate::hwaccel::hwdevice()?;
-        return compress_with_device(&hwdevice, images, metadata, to_format, flags);
+        return crate::hwaccel::with_hwdevice(|device| {
+            compress_with_device(device, images, metadata, to_format, flags)
+        });
     }
 
     let mut out = sys::ScratchImage::default();
@@ -197,30 +227,92 @@ pub fn flip_rotate(
     }
 }
 
+#[inline]
+pub fn compute_normal_map(
+    images: &[Image],
+    metadata: &sys::TexMetadata,
+    flags: CNMAP_FLAGS,
+    amplitude: f32,
+    to_format: sys::DXGI_FORMAT,
+) -> Result<ScratchImage> {
+    let mut out = sys::ScratchImage::default();
+
+    unsafe {
+        hresult(sys::ComputeNormalMap1(
+            Image::slice_as_ptr(images),
+            images.len(),
+            metadata,
+            flags,
+            amplitude,
+            to_format,
+            &mut out,
+        ))
+        .map(|_| ScratchImage(out))
+    }
+}
+
+// Pixels handed to evaluate and transform callbacks, as RGBA floats
+pub type Pixel = sys::XMVECTORF32;
+
+// Userdata behind the trampolines. A panicking callback can't unwind through DirectXTex, so the
+// panic is kept here, the remaining rows are skipped and it's returned as an E_ABORT error with
+// the panic message once DirectXTex returns.
+struct Callback<F> {
+    func:  F,
+    panic: Option<Box<dyn Any + Send>>,
+}
+
+impl<F> Callback<F> {
+    const fn new(func: F) -> Self { Self { func, panic: None } }
+
+    fn call(&mut self, call: impl FnOnce(&mut F)) {
+        if self.panic.is_none() {
+            if let Err(panic) = panic::catch_unwind(AssertUnwindSafe(|| call(&mut self.func))) {
+                self.panic = Some(panic);
+            }
+        }
+    }
+
+    fn finish<T>(self, result: Result<T>) -> Result<T> {
+        let Some(panic) = self.panic else {
+            return result;
+        };
+        let message = panic
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown panic");
+        Err(error::with_message(&error::aborted(), &format!("callback panicked: {message}")))
+    }
+}
+
 unsafe extern "C" fn evaluate_func_impl<EVALFN>(
-    pixels: *const sys::XMVECTORF32,
+    pixels: *const Pixel,
     width: usize,
     y: usize,
     userdata: *mut u8,
 ) where
-    EVALFN: FnMut(&[sys::XMVECTORF32], usize),
+    EVALFN: FnMut(&[Pixel], usize),
 {
-    let func: *mut EVALFN = userdata.cast();
+    let callback: *mut Callback<EVALFN> = userdata.cast();
     let pixels = std::slice::from_raw_parts(pixels, width);
-    (*func)(pixels, y);
+    (*callback).call(|func| func(pixels, y));
 }
 
+// Calls `evaluate_func` with every row of `images` and its y. A panic in `evaluate_func` stops the
+// evaluation and is returned as an error.
 #[inline]
 pub fn evaluate_images<EVALFN>(
     images: &[Image],
     metadata: &sys::TexMetadata,
-    mut evaluate_func: EVALFN,
+    evaluate_func: EVALFN,
 ) -> Result<()>
 where
-    EVALFN: FnMut(&[sys::XMVECTORF32], usize),
+    EVALFN: FnMut(&[Pixel], usize),
 {
-    let func: *mut EVALFN = &mut evaluate_func;
-    hresult(unsafe {
+    let mut callback = Callback::new(evaluate_func);
+    let func: *mut Callback<EVALFN> = &mut callback;
+    let result = hresult(unsafe {
         sys::EvaluateImage1(
             Image::slice_as_ptr(images),
             images.len(),
@@ -228,37 +320,54 @@ where
             Some(evaluate_func_impl::<EVALFN>),
             func.cast(),
         )
-    })
+    });
+    callback.finish(result)
+}
+
+#[inline]
+pub fn evaluate_image<EVALFN>(image: &Image, evaluate_func: EVALFN) -> Result<()>
+where
+    EVALFN: FnMut(&[Pixel], usize),
+{
+    let mut callback = Callback::new(evaluate_func);
+    let func: *mut Callback<EVALFN> = &mut callback;
+    let result = hresult(unsafe {
+        sys::EvaluateImage(image.as_ptr(), Some(evaluate_func_impl::<EVALFN>), func.cast())
+    });
+    callback.finish(result)
 }
 
 unsafe extern "C" fn transform_func_impl<TRANSFN>(
-    out_pixels: *mut sys::XMVECTORF32,
-    in_pixels: *const sys::XMVECTORF32,
+    out_pixels: *mut Pixel,
+    in_pixels: *const Pixel,
     width: usize,
     y: usize,
     userdata: *mut u8,
 ) where
-    TRANSFN: FnMut(&mut [sys::XMVECTORF32], &[sys::XMVECTORF32], usize),
+    TRANSFN: FnMut(&mut [Pixel], &[Pixel], usize),
 {
-    let func: *mut TRANSFN = userdata.cast();
+    let callback: *mut Callback<TRANSFN> = userdata.cast();
     let in_pixels = std::slice::from_raw_parts(in_pixels, width);
     let out_pixels = std::slice::from_raw_parts_mut(out_pixels, width);
-    (*func)(out_pixels, in_pixels, y);
+    (*callback).call(|func| func(out_pixels, in_pixels, y));
 }
 
+// Like `evaluate_images`, with `transform_func` writing each row of a new image in the same
+// format
 #[inline]
 pub fn transform_images<TRANSFN>(
     images: &[Image],
     metadata: &sys::TexMetadata,
-    mut transform_func: TRANSFN,
+    transform_func: TRANSFN,
 ) -> Result<ScratchImage>
 where
-    TRANSFN: FnMut(&mut [sys::XMVECTORF32], &[sys::XMVECTORF32], usize),
+    TRANSFN: FnMut(&mut [Pixel], &[Pixel], usize),
 {
     let mut out = sys::ScratchImage::default();
 
-    let func: *mut TRANSFN = &mut transform_func;
-    hresult(unsafe {
+    let mut callback = Callback::new(transform_func);
+    let func: *mut Callback<TRANSFN> = &mut callback;
+    let result = hresult(unsafe {
         sys::TransformImage1(
             Image::slice_as_ptr(images),
             images.len(),
@@ -267,14 +376,33 @@ where
             func.cast(),
             &mut out,
         )
-    })
-    .map(|_| ScratchImage(out))
+    });
+    callback.finish(result.map(|_| ScratchImage(out)))
+}
+
+#[inline]
+pub fn transform_image<TRANSFN>(image: &Image, transform_func: TRANSFN) -> Result<ScratchImage>
+where
+    TRANSFN: FnMut(&mut [Pixel], &[Pixel], usize),
+{
+    let mut out = sys::ScratchImage::default();
+
+    let mut callback = Callback::new(transform_func);
+    let func: *mut Callback<TRANSFN> = &mut callback;
+    let result = hresult(unsafe {
+        sys::TransformImage(
+            image.as_ptr(),
+            Some(transform_func_impl::<TRANSFN>),
+            func.cast(),
+            &mut out,
+        )
+    });
+    callback.finish(result.map(|_| ScratchImage(out)))
 }
 
 // TODO:
 // ConvertToSinglePlane
 // GenerateMipMaps3D
 // ScaleMipMapsAlphaForCoverage
-// ComputeNormalMap
 // CopyRectangle
 // ComputeMSE
@@ -0,0 +1,142 @@
+#[cfg(feature = "serde")] use serde::{Deserialize, Serialize};
+
+use directxtex_sys::{TexMetadata, XMVECTORF32, DXGI_FORMAT};
+
+use super::plane::{float_format, Plane};
+use crate::flags::TEX_FILTER_FLAGS;
+use crate::util::is_compressed;
+use crate::{error, ops, Image, Result, ScratchImage};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Channel {
+    R,
+    G,
+    B,
+    A,
+}
+
+impl Channel {
+    #[inline]
+    #[must_use]
+    pub const fn index(self) -> usize { self as usize }
+}
+
+#[derive(Clone, Copy)]
+enum Selector {
+    Channel(Channel),
+    Zero,
+    One,
+}
+
+impl Selector {
+    fn pick(self, [r, g, b, a]: [f32; 4]) -> f32 {
+        match self {
+            Self::Channel(Channel::R) => r,
+            Self::Channel(Channel::G) => g,
+            Self::Channel(Channel::B) => b,
+            Self::Channel(Channel::A) => a,
+            Self::Zero => 0.0,
+            Self::One => 1.0,
+        }
+    }
+}
+
+// texconv's -swizzle masks: one of rgba, xyzw, 0 or 1 per output channel, shorter masks repeat
+// their last character
+fn parse_mask(mask: &str) -> Result<[Selector; 4]> {
+    let mask = mask.as_bytes();
+    if mask.is_empty() || mask.len() > 4 {
+        return Err(error::invalid_arg());
+    }
+    let mut selectors = [Selector::Zero; 4];
+    for (index, selector) in selectors.iter_mut().enumerate() {
+        *selector = match mask[index.min(mask.len() - 1)].to_ascii_lowercase() {
+            b'r' | b'x' => Selector::Channel(Channel::R),
+            b'g' | b'y' => Selector::Channel(Channel::G),
+            b'b' | b'z' => Selector::Channel(Channel::B),
+            b'a' | b'w' => Selector::Channel(Channel::A),
+            b'0' => Selector::Zero,
+            b'1' => Selector::One,
+            _ => return Err(error::invalid_arg()),
+        };
+    }
+    Ok(selectors)
+}
+
+// Reorders channels in place, keeping the format. Block compressed images come back in their
+// decompressed format.
+pub fn swizzle(images: &[Image], metadata: &TexMetadata, mask: &str) -> Result<ScratchImage> {
+    let selectors = parse_mask(mask)?;
+    if is_compressed(metadata.format) {
+        let decompressed = ops::decompress(images, metadata)?;
+        return swizzle(decompressed.images(), decompressed.metadata(), mask);
+    }
+
+    ops::transform_images(images, metadata, |out_pixels, in_pixels, _| {
+        for (out, &XMVECTORF32(r, g, b, a)) in out_pixels.iter_mut().zip(in_pixels) {
+            let [r, g, b, a] = selectors.map(|selector| selector.pick([r, g, b, a]));
+            *out = XMVECTORF32(r, g, b, a);
+        }
+    })
+}
+
+fn to_float(image: &Image) -> Result<ScratchImage> {
+    let metadata = image.metadata();
+    let images = std::slice::from_ref(image);
+    if is_compressed(metadata.format) {
+        let decompressed = ops::decompress(images, &metadata)?;
+        decompressed.convert(float_format(), TEX_FILTER_FLAGS::default())
+    } else {
+        ops::convert(images, &metadata, float_format(), TEX_FILTER_FLAGS::default())
+    }
+}
+
+// Builds one image from a channel of each source, like occlusion, roughness and metalness into
+// an ORM map. Missing color channels are 0 and a missing alpha is 1. Sources need the same
+// dimensions but not the same format.
+pub fn pack_channels(
+    sources: [Option<(&Image, Channel)>; 4],
+    format: DXGI_FORMAT,
+) -> Result<ScratchImage> {
+    let (first, _) = sources.iter().flatten().next().ok_or_else(error::invalid_arg)?;
+    let (width, height) = (first.width(), first.height());
+    if sources
+        .iter()
+        .flatten()
+        .any(|(image, _)| image.width() != width || image.height() != height)
+    {
+        return Err(error::invalid_arg());
+    }
+
+    let floats = sources
+        .iter()
+        .map(|source| {
+            source
+                .map(|(image, channel)| to_float(image).map(|float| (float, channel)))
+                .transpose()
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let planes = floats
+        .iter()
+        .map(|source| {
+            source
+                .as_ref()
+                .map(|(image, channel)| (Plane::read(image.image0()), *channel))
+        })
+        .collect::<Vec<_>>();
+    let base = floats.iter().flatten().map(|(image, _)| image).next().ok_or_else(error::fail)?;
+
+    let packed = ops::transform_images(base.images(), base.metadata(), |out_pixels, _, y| {
+        for (x, out) in out_pixels.iter_mut().enumerate() {
+            let [r, g, b, a] = std::array::from_fn(|index| match &planes[index] {
+                Some((plane, channel)) => plane.pixels[y * width + x][channel.index()],
+                None if index == Channel::A.index() => 1.0,
+                None => 0.0,
+            });
+            *out = XMVECTORF32(r, g, b, a);
+        }
+    })?;
+    packed.into_converted(format, TEX_FILTER_FLAGS::default())
+}
@@ -0,0 +1,147 @@
+#[cfg(feature = "serde")] use serde::{Deserialize, Serialize};
+
+use directxtex_sys::{TexMetadata, XMVECTORF32};
+
+use crate::{error, ops, Image, Result, ScratchImage};
+
+// Primaries conversions between linear Rec.709, Rec.2020 and P3 with a D65 white point
+#[rustfmt::skip]
+const REC709_TO_REC2020: [[f32; 3]; 3] = [
+    [0.627_404_0, 0.329_282_0, 0.043_313_6],
+    [0.069_097_0, 0.919_540_0, 0.011_361_2],
+    [0.016_391_6, 0.088_013_2, 0.895_595_0],
+];
+#[rustfmt::skip]
+const REC2020_TO_REC709: [[f32; 3]; 3] = [
+    [ 1.660_491_0, -0.587_641_1, -0.072_849_9],
+    [-0.124_550_5,  1.132_899_9, -0.008_349_4],
+    [-0.018_150_8, -0.100_578_9,  1.118_729_7],
+];
+#[rustfmt::skip]
+const REC709_TO_P3: [[f32; 3]; 3] = [
+    [0.822_462_1, 0.177_538_0, 0.000_000_0],
+    [0.033_194_1, 0.966_805_8, 0.000_000_0],
+    [0.017_082_7, 0.072_397_4, 0.910_519_9],
+];
+#[rustfmt::skip]
+const P3_TO_REC709: [[f32; 3]; 3] = [
+    [ 1.224_940_1, -0.224_940_4, 0.000_000_0],
+    [-0.042_056_9,  1.042_057_1, 0.000_000_0],
+    [-0.019_637_6, -0.078_636_1, 1.098_273_5],
+];
+
+// ST.2084 reference peak and constants
+const PQ_MAX_NITS: f32 = 10_000.0;
+const PQ_M1: f32 = 2610.0 / 16384.0;
+const PQ_M2: f32 = 2523.0 / 4096.0 * 128.0;
+const PQ_C1: f32 = 3424.0 / 4096.0;
+const PQ_C2: f32 = 2413.0 / 4096.0 * 32.0;
+const PQ_C3: f32 = 2392.0 / 4096.0 * 32.0;
+
+// Like texconv's -rotatecolor. Linear values of 1.0 are `white_nits` when going to or from PQ,
+// which has to be positive.
+// Alpha is left alone and results are stored in the source format, so use float formats for
+// anything outside [0, 1].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ColorRotation {
+    Rec709ToRec2020,
+    Rec2020ToRec709,
+    Rec709ToP3,
+    P3ToRec709,
+    // ST.2084 on linear values
+    PqEncode { white_nits: f32 },
+    PqDecode { white_nits: f32 },
+    // BT.2100 HLG OETF on scene linear values in [0, 1]
+    HlgEncode,
+    HlgDecode,
+    // Linear scRGB Rec.709 to Rec.2020 PQ and back
+    Rec709ToHdr10 { white_nits: f32 },
+    Hdr10ToRec709 { white_nits: f32 },
+}
+
+fn transform(matrix: &[[f32; 3]; 3], rgb: [f32; 3]) -> [f32; 3] {
+    matrix.map(|row| row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2])
+}
+
+#[must_use]
+pub fn pq_encode(linear: f32) -> f32 {
+    let power = linear.clamp(0.0, 1.0).powf(PQ_M1);
+    ((PQ_C1 + PQ_C2 * power) / (1.0 + PQ_C3 * power)).powf(PQ_M2)
+}
+
+#[must_use]
+pub fn pq_decode(encoded: f32) -> f32 {
+    let power = encoded.clamp(0.0, 1.0).powf(1.0 / PQ_M2);
+    ((power - PQ_C1).max(0.0) / (PQ_C2 - PQ_C3 * power)).powf(1.0 / PQ_M1)
+}
+
+const HLG_A: f32 = 0.178_832_77;
+const HLG_B: f32 = 0.284_668_92;
+const HLG_C: f32 = 0.559_910_7;
+
+#[must_use]
+pub fn hlg_encode(linear: f32) -> f32 {
+    let linear = linear.clamp(0.0, 1.0);
+    if linear <= 1.0 / 12.0 {
+        (3.0 * linear).sqrt()
+    } else {
+        HLG_A * (12.0 * linear - HLG_B).ln() + HLG_C
+    }
+}
+
+#[must_use]
+pub fn hlg_decode(encoded: f32) -> f32 {
+    let encoded = encoded.clamp(0.0, 1.0);
+    if encoded <= 0.5 {
+        encoded * encoded / 3.0
+    } else {
+        (((encoded - HLG_C) / HLG_A).exp() + HLG_B) / 12.0
+    }
+}
+
+impl ColorRotation {
+    #[must_use]
+    pub fn apply(self, rgb: [f32; 3]) -> [f32; 3] {
+        match self {
+            Self::Rec709ToRec2020 => transform(&REC709_TO_REC2020, rgb),
+            Self::Rec2020ToRec709 => transform(&REC2020_TO_REC709, rgb),
+            Self::Rec709ToP3 => transform(&REC709_TO_P3, rgb),
+            Self::P3ToRec709 => transform(&P3_TO_REC709, rgb),
+            Self::PqEncode { white_nits } => rgb.map(|c| pq_encode(c * white_nits / PQ_MAX_NITS)),
+            Self::PqDecode { white_nits } => rgb.map(|c| pq_decode(c) * PQ_MAX_NITS / white_nits),
+            Self::HlgEncode => rgb.map(hlg_encode),
+            Self::HlgDecode => rgb.map(hlg_decode),
+            Self::Rec709ToHdr10 { white_nits } => {
+                Self::PqEncode { white_nits }.apply(transform(&REC709_TO_REC2020, rgb))
+            }
+            Self::Hdr10ToRec709 { white_nits } => {
+                transform(&REC2020_TO_REC709, Self::PqDecode { white_nits }.apply(rgb))
+            }
+        }
+    }
+}
+
+pub fn rotate_color(
+    images: &[Image],
+    metadata: &TexMetadata,
+    rotation: ColorRotation,
+) -> Result<ScratchImage> {
+    if let ColorRotation::PqEncode { white_nits }
+    | ColorRotation::PqDecode { white_nits }
+    | ColorRotation::Rec709ToHdr10 { white_nits }
+    | ColorRotation::Hdr10ToRec709 { white_nits } = rotation
+    {
+        if white_nits.is_nan() || white_nits <= 0.0 {
+            return Err(error::invalid_arg());
+        }
+    }
+
+    ops::transform_images(images, metadata, |out_pixels, in_pixels, _| {
+        for (out, &XMVECTORF32(r, g, b, a)) in out_pixels.iter_mut().zip(in_pixels) {
+            let [r, g, b] = rotation.apply([r, g, b]);
+            *out = XMVECTORF32(r, g, b, a);
+        }
+    })
+}
@@ -0,0 +1,130 @@
+use std::f32::consts::PI;
+
+use directxtex_sys::{TexMetadata, TEX_DIMENSION};
+
+use super::plane::{float_format, to_float, Plane, Sampling};
+use crate::flags::TEX_FILTER_FLAGS;
+use crate::util::TEX_MISC_TEXTURECUBE;
+use crate::{error, Result, ScratchImage};
+
+// Cubes are laid out the D3D way: +X, -X, +Y, -Y, +Z, -Z with Y up, and equirectangular images
+// put +Z in the middle column and +Y on the top row
+
+
+pub(crate) fn normalize([x, y, z]: [f32; 3]) -> [f32; 3] {
+    let length = (x * x + y * y + z * z).sqrt();
+    [x / length, y / length, z / length]
+}
+
+// Direction through normalized coordinates `s`, `t` of a cube face
+pub(crate) fn face_direction(face: usize, s: f32, t: f32) -> [f32; 3] {
+    let (a, b) = (2.0 * s - 1.0, 2.0 * t - 1.0);
+    normalize(match face {
+        0 => [1.0, -b, -a],
+        1 => [-1.0, -b, a],
+        2 => [a, 1.0, b],
+        3 => [a, -1.0, -b],
+        4 => [a, -b, 1.0],
+        _ => [-a, -b, -1.0],
+    })
+}
+
+// Cube face and normalized coordinates hit by a direction
+pub(crate) fn direction_face([x, y, z]: [f32; 3]) -> (usize, f32, f32) {
+    let (ax, ay, az) = (x.abs(), y.abs(), z.abs());
+    let (face, sc, tc, major) = if ax >= ay && ax >= az {
+        if x > 0.0 { (0, -z, -y, ax) } else { (1, z, -y, ax) }
+    } else if ay >= az {
+        if y > 0.0 { (2, x, z, ay) } else { (3, x, -z, ay) }
+    } else if z > 0.0 {
+        (4, x, -y, az)
+    } else {
+        (5, -x, -y, az)
+    };
+    (face, 0.5 * (sc / major + 1.0), 0.5 * (tc / major + 1.0))
+}
+
+pub(crate) fn is_cubemap(metadata: &TexMetadata) -> bool {
+    metadata.miscFlags & TEX_MISC_TEXTURECUBE != 0 && metadata.arraySize >= 6
+}
+
+pub(crate) fn cube_metadata(face_size: usize, mip_levels: usize) -> TexMetadata {
+    TexMetadata {
+        width: face_size,
+        height: face_size,
+        depth: 1,
+        arraySize: 6,
+        mipLevels: mip_levels,
+        miscFlags: TEX_MISC_TEXTURECUBE,
+        miscFlags2: 0,
+        format: float_format(),
+        dimension: TEX_DIMENSION::TEX_DIMENSION_TEXTURE2D,
+    }
+}
+
+// Projects the top mip of an equirectangular image onto a cube in the source's format
+pub fn equirect_to_cube(
+    source: &ScratchImage,
+    face_size: usize,
+    sampling: Sampling,
+) -> Result<ScratchImage> {
+    if face_size == 0 {
+        return Err(error::invalid_arg());
+    }
+    let (float, format) = to_float(source)?;
+    let equirect = Plane::read(float.image(0, 0, 0).ok_or_else(error::invalid_arg)?);
+
+    let mut cube = ScratchImage::initialize(&cube_metadata(face_size, 1))?;
+    let mut face = Plane::new(face_size, face_size);
+    for index in 0..6 {
+        for (i, pixel) in face.pixels.iter_mut().enumerate() {
+            let s = ((i % face_size) as f32 + 0.5) / face_size as f32;
+            let t = ((i / face_size) as f32 + 0.5) / face_size as f32;
+            let [x, y, z] = face_direction(index, s, t);
+            let u = x.atan2(z) / (2.0 * PI) + 0.5;
+            let v = 0.5 - y.clamp(-1.0, 1.0).asin() / PI;
+            *pixel = equirect.sample(u, v, sampling, true);
+        }
+        face.write(&mut cube, index)?;
+    }
+
+    cube.into_converted(format, TEX_FILTER_FLAGS::default())
+}
+
+// Unwraps the top mip of a cube into an equirectangular image in the cube's format. Samples don't
+// cross face edges.
+pub fn cube_to_equirect(
+    cube: &ScratchImage,
+    width: usize,
+    height: usize,
+    sampling: Sampling,
+) -> Result<ScratchImage> {
+    if !is_cubemap(cube.metadata()) || width == 0 || height == 0 {
+        return Err(error::invalid_arg());
+    }
+    let (float, format) = to_float(cube)?;
+    let faces = (0..6)
+        .map(|item| float.image(0, item, 0).map(Plane::read))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(error::invalid_arg)?;
+
+    let mut equirect = Plane::new(width, height);
+    for (i, pixel) in equirect.pixels.iter_mut().enumerate() {
+        let phi = (((i % width) as f32 + 0.5) / width as f32 - 0.5) * 2.0 * PI;
+        let theta = (0.5 - ((i / width) as f32 + 0.5) / height as f32) * PI;
+        let direction = [theta.cos() * phi.sin(), theta.sin(), theta.cos() * phi.cos()];
+        let (face, s, t) = direction_face(direction);
+        *pixel = faces[face].sample(s, t, sampling, false);
+    }
+
+    let metadata = TexMetadata {
+        width,
+        height,
+        arraySize: 1,
+        miscFlags: 0,
+        ..cube_metadata(1, 1)
+    };
+    let mut image = ScratchImage::initialize(&metadata)?;
+    equirect.write(&mut image, 0)?;
+    image.into_converted(format, TEX_FILTER_FLAGS::default())
+}
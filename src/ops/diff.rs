@@ -0,0 +1,71 @@
+#[cfg(feature = "serde")] use serde::{Deserialize, Serialize};
+
+use directxtex_sys::TexMetadata;
+
+use super::plane::{float_format, to_float, Plane};
+use crate::{error, Result, ScratchImage};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum DiffMode {
+    // |a - b| for each channel, alpha included
+    Absolute,
+    // The largest channel difference times `scale` as an opaque black, red, yellow, white heatmap
+    Scaled { scale: f32 },
+    // Opaque white where any channel differs by more than `threshold`, black elsewhere
+    Threshold { threshold: f32 },
+}
+
+impl DiffMode {
+    fn apply(self, a: [f32; 4], b: [f32; 4]) -> [f32; 4] {
+        let difference: [f32; 4] = std::array::from_fn(|c| (a[c] - b[c]).abs());
+        // NaNs count as the largest possible error
+        let error = difference
+            .iter()
+            .fold(0.0f32, |error, &d| if d.is_nan() { f32::INFINITY } else { error.max(d) });
+        match self {
+            Self::Absolute => difference,
+            Self::Scaled { scale } => heat(error * scale),
+            Self::Threshold { threshold } => {
+                let value = if error > threshold { 1.0 } else { 0.0 };
+                [value, value, value, 1.0]
+            }
+        }
+    }
+}
+
+fn heat(t: f32) -> [f32; 4] {
+    let t = t.clamp(0.0, 1.0) * 3.0;
+    [t.min(1.0), (t - 1.0).clamp(0.0, 1.0), (t - 2.0).clamp(0.0, 1.0), 1.0]
+}
+
+fn same_layout(a: &TexMetadata, b: &TexMetadata) -> bool {
+    (a.width, a.height, a.depth, a.arraySize, a.mipLevels, a.dimension)
+        == (b.width, b.height, b.depth, b.arraySize, b.mipLevels, b.dimension)
+}
+
+// Per pixel differences between every subresource of two images with the same dimensions, as
+// R32G32B32A32_FLOAT. Formats may differ, both sides are decompressed and converted to float
+// first, so sRGB formats are compared linearized. The result can go straight to `save_hdr`, or
+// be converted to R8G8B8A8_UNORM for `save_tga`.
+pub fn diff_image(a: &ScratchImage, b: &ScratchImage, mode: DiffMode) -> Result<ScratchImage> {
+    if !same_layout(a.metadata(), b.metadata()) {
+        return Err(error::invalid_arg());
+    }
+    let (a, _) = to_float(a)?;
+    let (b, _) = to_float(b)?;
+
+    let mut out = ScratchImage::initialize(&TexMetadata {
+        format: float_format(),
+        ..*a.metadata()
+    })?;
+    for (index, (a, b)) in a.images().iter().zip(b.images()).enumerate() {
+        let (a, mut plane) = (Plane::read(a), Plane::read(b));
+        for (out, a) in plane.pixels.iter_mut().zip(&a.pixels) {
+            *out = mode.apply(*a, *out);
+        }
+        plane.write(&mut out, index)?;
+    }
+    Ok(out)
+}
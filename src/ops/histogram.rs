@@ -0,0 +1,183 @@
+#[cfg(feature = "serde")] use serde::{Deserialize, Serialize};
+
+use std::ops::RangeInclusive;
+
+use directxtex_sys::{TexMetadata, TEX_DIMENSION};
+
+use super::tonemap::srgb_decode;
+use super::Channel;
+use crate::util::{image_index, is_compressed, is_srgb};
+use crate::{error, ops, Image, Result};
+
+// Rec.709 luminance weights
+const LUMINANCE: [f32; 3] = [0.2126, 0.7152, 0.0722];
+
+// Counts of values in `bins` equal steps over `start..=end`. Values outside the range are counted
+// in `below` and `above`, NaNs are skipped.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Histogram {
+    pub start:     f32,
+    pub end:       f32,
+    pub bins:      Vec<u64>,
+    pub below:     u64,
+    pub above:     u64,
+    // Whether the bins are over log2 of the values, as from `luminance_histogram`
+    pub log_scale: bool,
+}
+
+impl Histogram {
+    fn new(bins: usize, range: RangeInclusive<f32>, log_scale: bool) -> Result<Self> {
+        let (start, end) = range.into_inner();
+        if bins == 0 || !start.is_finite() || !end.is_finite() || start >= end {
+            return Err(error::invalid_arg());
+        }
+        Ok(Self {
+            start,
+            end,
+            bins: vec![0; bins],
+            below: 0,
+            above: 0,
+            log_scale,
+        })
+    }
+
+    fn add(&mut self, value: f32) {
+        let value = if self.log_scale { value.log2() } else { value };
+        if value.is_nan() {
+            return;
+        }
+        if value < self.start {
+            self.below += 1;
+        } else if value > self.end {
+            self.above += 1;
+        } else {
+            let position = (value - self.start) / (self.end - self.start) * self.bins.len() as f32;
+            let bin = (position as usize).min(self.bins.len() - 1);
+            self.bins[bin] += 1;
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn bin_width(&self) -> f32 { (self.end - self.start) / self.bins.len() as f32 }
+
+    // Start and end of a bin, in log2 units for log scale histograms
+    #[inline]
+    #[must_use]
+    pub fn bin_range(&self, bin: usize) -> RangeInclusive<f32> {
+        let start = self.start + bin as f32 * self.bin_width();
+        start..=start + self.bin_width()
+    }
+
+    // Everything counted, including values outside the range
+    #[inline]
+    #[must_use]
+    pub fn total(&self) -> u64 { self.below + self.bins.iter().sum::<u64>() + self.above }
+
+    // Value below which `percent` of the counted values fall, interpolated within its bin and
+    // clamped to the range. For log scale histograms this is a value, not its log2.
+    #[must_use]
+    pub fn percentile(&self, percent: f32) -> Option<f32> {
+        let total = self.total();
+        if total == 0 {
+            return None;
+        }
+        let target = f64::from(percent.clamp(0.0, 100.0)) / 100.0 * total as f64;
+
+        let mut position = self.end;
+        let mut cumulative = self.below as f64;
+        if target <= cumulative {
+            position = self.start;
+        } else {
+            for (bin, &count) in self.bins.iter().enumerate() {
+                let next = cumulative + count as f64;
+                if count > 0 && target <= next {
+                    let fraction = ((target - cumulative) / count as f64) as f32;
+                    position = self.start + (bin as f32 + fraction) * self.bin_width();
+                    break;
+                }
+                cumulative = next;
+            }
+        }
+        Some(if self.log_scale { position.exp2() } else { position })
+    }
+}
+
+// Images of one mip, or all of them
+fn select_mip<'a, 'b>(
+    images: &'a [Image<'b>],
+    metadata: &TexMetadata,
+    mip: Option<usize>,
+) -> Result<Vec<&'a Image<'b>>> {
+    let Some(mip) = mip else {
+        return Ok(images.iter().collect());
+    };
+    if mip >= metadata.mipLevels {
+        return Err(error::invalid_arg());
+    }
+
+    let (items, slices) = if metadata.dimension == TEX_DIMENSION::TEX_DIMENSION_TEXTURE3D {
+        (1, (metadata.depth >> mip).max(1))
+    } else {
+        (metadata.arraySize, 1)
+    };
+    (0..items)
+        .flat_map(|item| (0..slices).map(move |slice| image_index(metadata, mip, item, slice)))
+        .map(|index| images.get(index).ok_or_else(error::invalid_arg))
+        .collect()
+}
+
+fn accumulate(
+    images: &[Image],
+    metadata: &TexMetadata,
+    mip: Option<usize>,
+    histogram: &mut Histogram,
+    mut value: impl FnMut([f32; 4]) -> f32,
+) -> Result<()> {
+    if is_compressed(metadata.format) {
+        let decompressed = ops::decompress(images, metadata)?;
+        return accumulate(decompressed.images(), decompressed.metadata(), mip, histogram, value);
+    }
+    for image in select_mip(images, metadata, mip)? {
+        ops::evaluate_image(image, |pixels, _| {
+            for pixel in pixels {
+                histogram.add(value(pixel.to_array()));
+            }
+        })?;
+    }
+    Ok(())
+}
+
+// Histogram of one channel as stored, over every mip when `mip` is None
+pub fn histogram(
+    images: &[Image],
+    metadata: &TexMetadata,
+    channel: Channel,
+    bins: usize,
+    range: RangeInclusive<f32>,
+    mip: Option<usize>,
+) -> Result<Histogram> {
+    let mut histogram = Histogram::new(bins, range, false)?;
+    accumulate(images, metadata, mip, &mut histogram, |pixel| pixel[channel.index()])?;
+    Ok(histogram)
+}
+
+// Histogram of Rec.709 luminance, with sRGB formats linearized first. With `log_scale` the range
+// is in log2 luminance, so -8.0..=8.0 covers 1/256 to 256, and black counts as below the range.
+pub fn luminance_histogram(
+    images: &[Image],
+    metadata: &TexMetadata,
+    bins: usize,
+    range: RangeInclusive<f32>,
+    log_scale: bool,
+    mip: Option<usize>,
+) -> Result<Histogram> {
+    let mut histogram = Histogram::new(bins, range, log_scale)?;
+    let srgb = is_srgb(metadata.format);
+    accumulate(images, metadata, mip, &mut histogram, |[r, g, b, _]| {
+        let rgb = if srgb { [r, g, b].map(srgb_decode) } else { [r, g, b] };
+        rgb.iter().zip(LUMINANCE).map(|(channel, weight)| channel * weight).sum()
+    })?;
+    Ok(histogram)
+}
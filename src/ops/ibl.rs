@@ -0,0 +1,252 @@
+use std::f32::consts::PI;
+
+use directxtex_sys::DXGI_FORMAT;
+
+use super::cubemap::{cube_metadata, direction_face, face_direction, is_cubemap, normalize};
+use super::plane::{to_float, Plane, Sampling};
+use crate::flags::TEX_FILTER_FLAGS;
+use crate::util::{is_compressed, is_srgb};
+use crate::{error, Result, ScratchImage};
+
+// Image based lighting from the top mip of the first cube in `cube`. Block compressed and sRGB
+// cubes are read through `convert`, everything else as stored. Results are returned in the
+// source's format.
+
+type Faces = [Plane; 6];
+
+fn read_faces(cube: &ScratchImage) -> Result<(Faces, DXGI_FORMAT)> {
+    if !is_cubemap(cube.metadata()) {
+        return Err(error::invalid_arg());
+    }
+    let converted;
+    let (source, format) = if is_compressed(cube.format()) || is_srgb(cube.format()) {
+        let (float, format) = to_float(cube)?;
+        converted = float;
+        (&converted, format)
+    } else {
+        (cube, cube.format())
+    };
+
+    let mut faces = Vec::with_capacity(6);
+    for item in 0..6 {
+        faces.push(Plane::evaluate(source.image(0, item, 0).ok_or_else(error::invalid_arg)?)?);
+    }
+    let faces = faces.try_into().map_err(|_| error::fail())?;
+    Ok((faces, format))
+}
+
+fn write_faces(levels: &[Faces], format: DXGI_FORMAT) -> Result<ScratchImage> {
+    let mut cube = ScratchImage::initialize(&cube_metadata(levels[0][0].width, levels.len()))?;
+    for (mip, faces) in levels.iter().enumerate() {
+        for (item, face) in faces.iter().enumerate() {
+            face.write(&mut cube, item * levels.len() + mip)?;
+        }
+    }
+    cube.into_converted(format, TEX_FILTER_FLAGS::default())
+}
+
+// Fills every texel of a new set of faces from its direction
+fn render_faces(size: usize, mut func: impl FnMut([f32; 3]) -> [f32; 4]) -> Faces {
+    std::array::from_fn(|face| {
+        let mut plane = Plane::new(size, size);
+        for (i, pixel) in plane.pixels.iter_mut().enumerate() {
+            let s = ((i % size) as f32 + 0.5) / size as f32;
+            let t = ((i / size) as f32 + 0.5) / size as f32;
+            *pixel = func(face_direction(face, s, t));
+        }
+        plane
+    })
+}
+
+// Box filtered half size faces
+fn downsample(faces: &Faces) -> Faces {
+    std::array::from_fn(|face| {
+        let source = &faces[face];
+        let (width, height) = ((source.width / 2).max(1), (source.height / 2).max(1));
+        let mut plane = Plane::new(width, height);
+        for (i, pixel) in plane.pixels.iter_mut().enumerate() {
+            let (x, y) = (i % width, i / width);
+            let mut sum = [0.0; 4];
+            for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let sx = (x * 2 + dx).min(source.width - 1);
+                let sy = (y * 2 + dy).min(source.height - 1);
+                let texel = source.pixels[sy * source.width + sx];
+                for (sum, texel) in sum.iter_mut().zip(texel) {
+                    *sum += texel * 0.25;
+                }
+            }
+            *pixel = sum;
+        }
+        plane
+    })
+}
+
+fn sample_level(faces: &Faces, direction: [f32; 3]) -> [f32; 4] {
+    let (face, s, t) = direction_face(direction);
+    faces[face].sample(s, t, Sampling::Bilinear, false)
+}
+
+// Trilinear lookup into a box filtered mip chain
+fn sample_lod(chain: &[Faces], direction: [f32; 3], lod: f32) -> [f32; 4] {
+    let lod = lod.clamp(0.0, (chain.len() - 1) as f32);
+    let level = lod.floor() as usize;
+    let fraction = lod - level as f32;
+    let lower = sample_level(&chain[level], direction);
+    if fraction == 0.0 || level + 1 == chain.len() {
+        return lower;
+    }
+    let upper = sample_level(&chain[level + 1], direction);
+    std::array::from_fn(|c| lower[c] + (upper[c] - lower[c]) * fraction)
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 { a[0] * b[0] + a[1] * b[1] + a[2] * b[2] }
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+fn hammersley(index: u32, count: u32) -> (f32, f32) {
+    (index as f32 / count as f32, index.reverse_bits() as f32 / 4_294_967_296.0)
+}
+
+// Split sum prefiltering with N = V = R, sampling the source mip that matches each sample's
+// solid angle to avoid fireflies
+fn prefilter_texel(
+    chain: &[Faces],
+    normal: [f32; 3],
+    roughness: f32,
+    sample_count: u32,
+) -> [f32; 4] {
+    let alpha2 = roughness.powi(4);
+    let up = if normal[2].abs() < 0.999 { [0.0, 0.0, 1.0] } else { [1.0, 0.0, 0.0] };
+    let tangent_x = normalize(cross(up, normal));
+    let tangent_y = cross(normal, tangent_x);
+    let texel_solid_angle = 4.0 * PI / (6.0 * (chain[0][0].width * chain[0][0].width) as f32);
+
+    let mut color = [0.0; 4];
+    let mut weight = 0.0;
+    for index in 0..sample_count {
+        let (u, v) = hammersley(index, sample_count);
+        let phi = 2.0 * PI * u;
+        let cos_theta = ((1.0 - v) / (1.0 + (alpha2 - 1.0) * v)).sqrt();
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let (x, y) = (sin_theta * phi.cos(), sin_theta * phi.sin());
+        let half: [f32; 3] =
+            std::array::from_fn(|c| tangent_x[c] * x + tangent_y[c] * y + normal[c] * cos_theta);
+        let n_dot_h = dot(normal, half);
+        let light: [f32; 3] = std::array::from_fn(|c| 2.0 * n_dot_h * half[c] - normal[c]);
+        let n_dot_l = dot(normal, light);
+        if n_dot_l <= 0.0 {
+            continue;
+        }
+
+        let d = alpha2 / (PI * (n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0).powi(2));
+        let pdf = d / 4.0;
+        let sample_solid_angle = 1.0 / (sample_count as f32 * pdf + f32::EPSILON);
+        let lod = 0.5 * (sample_solid_angle / texel_solid_angle).log2() + 1.0;
+
+        let sample = sample_lod(chain, light, lod);
+        for (color, sample) in color.iter_mut().zip(sample) {
+            *color += sample * n_dot_l;
+        }
+        weight += n_dot_l;
+    }
+    color.map(|color| color / weight.max(f32::EPSILON))
+}
+
+// GGX prefiltered specular, one roughness per mip going linearly from 0 at the top to 1 at the
+// last mip. The cube's mip count is kept, cubes without mips get a full chain.
+pub fn prefilter_ggx(cube: &ScratchImage, sample_count: usize) -> Result<ScratchImage> {
+    let sample_count = u32::try_from(sample_count).map_err(|_| error::invalid_arg())?;
+    if sample_count == 0 {
+        return Err(error::invalid_arg());
+    }
+    let (faces, format) = read_faces(cube)?;
+    let size = faces[0].width;
+
+    let mut chain = vec![faces];
+    while chain.last().map_or(1, |faces| faces[0].width) > 1 {
+        let next = downsample(chain.last().unwrap());
+        chain.push(next);
+    }
+
+    let mip_levels = if cube.mip_levels() > 1 { cube.mip_levels() } else { chain.len() };
+    let mut levels = Vec::with_capacity(mip_levels);
+    for mip in 0..mip_levels {
+        let roughness = if mip_levels > 1 { mip as f32 / (mip_levels - 1) as f32 } else { 0.0 };
+        let mip_size = (size >> mip).max(1);
+        levels.push(render_faces(mip_size, |normal| {
+            if roughness == 0.0 {
+                sample_level(&chain[0], normal)
+            } else {
+                prefilter_texel(&chain, normal, roughness, sample_count)
+            }
+        }));
+    }
+    write_faces(&levels, format)
+}
+
+fn sh_basis([x, y, z]: [f32; 3]) -> [f32; 9] {
+    [
+        0.282_095,
+        0.488_603 * y,
+        0.488_603 * z,
+        0.488_603 * x,
+        1.092_548 * x * y,
+        1.092_548 * y * z,
+        0.315_392 * (3.0 * z * z - 1.0),
+        1.092_548 * x * z,
+        0.546_274 * (x * x - y * y),
+    ]
+}
+
+// Radiance projected onto the first nine real spherical harmonics, ordered (l, m) = (0, 0),
+// (1, -1), (1, 0), (1, 1), (2, -2) .. (2, 2) and evaluated on the cube's own x, y, z axes
+pub fn sh9(cube: &ScratchImage) -> Result<[[f32; 3]; 9]> {
+    let (faces, _) = read_faces(cube)?;
+    Ok(project_sh9(&faces))
+}
+
+fn project_sh9(faces: &Faces) -> [[f32; 3]; 9] {
+    let mut coefficients = [[0.0f64; 3]; 9];
+    for (index, face) in faces.iter().enumerate() {
+        let size = face.width;
+        for (i, pixel) in face.pixels.iter().enumerate() {
+            let s = ((i % size) as f32 + 0.5) / size as f32;
+            let t = ((i / size) as f32 + 0.5) / size as f32;
+            let (a, b) = (2.0 * s - 1.0, 2.0 * t - 1.0);
+            // Solid angle of the texel
+            let weight = 4.0 / ((size * size) as f32 * (1.0 + a * a + b * b).powf(1.5));
+            let basis = sh_basis(face_direction(index, s, t));
+            for (coefficient, basis) in coefficients.iter_mut().zip(basis) {
+                for (coefficient, channel) in coefficient.iter_mut().zip(&pixel[..3]) {
+                    *coefficient += f64::from(channel * basis * weight);
+                }
+            }
+        }
+    }
+    coefficients.map(|coefficient| coefficient.map(|channel| channel as f32))
+}
+
+// Diffuse irradiance divided by pi, so that a constant environment maps to itself. Computed from
+// the SH9 projection.
+pub fn irradiance_cube(cube: &ScratchImage, size: usize) -> Result<ScratchImage> {
+    if size == 0 {
+        return Err(error::invalid_arg());
+    }
+    let (faces, format) = read_faces(cube)?;
+    let coefficients = project_sh9(&faces);
+
+    // Cosine lobe convolution per band, over pi
+    const BANDS: [f32; 9] = [1.0, 2.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0, 0.25, 0.25, 0.25, 0.25, 0.25];
+    let levels = [render_faces(size, |normal| {
+        let mut irradiance = [0.0, 0.0, 0.0, 1.0];
+        for ((coefficient, basis), band) in coefficients.iter().zip(sh_basis(normal)).zip(BANDS) {
+            for (irradiance, coefficient) in irradiance.iter_mut().zip(coefficient) {
+                *irradiance += coefficient * basis * band;
+            }
+        }
+        irradiance.map(|channel| channel.max(0.0))
+    })];
+    write_faces(&levels, format)
+}
@@ -0,0 +1,142 @@
+#[cfg(feature = "serde")] use serde::{Deserialize, Serialize};
+
+use directxtex_sys::DXGI_FORMAT;
+
+use crate::flags::TEX_FILTER_FLAGS;
+use crate::util::{self, is_compressed};
+use crate::{error, ops, Image, Result, ScratchImage};
+
+// Images as RGBA floats for the operations DirectXTex doesn't have, shared by the cubemap, IBL,
+// channel, diff and atlas code
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Sampling {
+    #[default]
+    Bilinear,
+    // Catmull-Rom
+    Bicubic,
+}
+
+// A single image as RGBA floats
+pub(crate) struct Plane {
+    pub width:  usize,
+    pub height: usize,
+    pub pixels: Vec<[f32; 4]>,
+}
+
+impl Plane {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![[0.0; 4]; width * height],
+        }
+    }
+
+    // `image` has to be R32G32B32A32_FLOAT
+    pub fn read(image: &Image) -> Self {
+        let mut plane = Self::new(image.width(), image.height());
+        let row_size = image.width() * 16;
+        for (y, row) in image.buffer().chunks(image.row_pitch()).take(image.height()).enumerate() {
+            let pixels = &mut plane.pixels[y * image.width()..][..image.width()];
+            for (pixel, bytes) in pixels.iter_mut().zip(row[..row_size].chunks_exact(16)) {
+                for (channel, bytes) in pixel.iter_mut().zip(bytes.chunks_exact(4)) {
+                    *channel = f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                }
+            }
+        }
+        plane
+    }
+
+    // Reads any uncompressed format through EvaluateImage, values are taken as they are stored
+    pub fn evaluate(image: &Image) -> Result<Self> {
+        let mut plane = Self::new(image.width(), image.height());
+        let width = image.width();
+        ops::evaluate_image(image, |row, y| {
+            let pixels = &mut plane.pixels[y * width..][..width];
+            for (pixel, value) in pixels.iter_mut().zip(row) {
+                *pixel = value.to_array();
+            }
+        })?;
+        Ok(plane)
+    }
+
+    // `image` has to be R32G32B32A32_FLOAT with the plane's dimensions
+    pub fn write(&self, image: &mut ScratchImage, index: usize) -> Result<()> {
+        let row_pitch = image.images().get(index).ok_or_else(error::invalid_arg)?.row_pitch();
+        let buffer = image.image_buffer_mut(index).ok_or_else(error::invalid_arg)?;
+        for (row, pixels) in buffer.chunks_mut(row_pitch).zip(self.pixels.chunks(self.width)) {
+            for (bytes, pixel) in row.chunks_exact_mut(16).zip(pixels) {
+                for (bytes, channel) in bytes.chunks_exact_mut(4).zip(pixel) {
+                    bytes.copy_from_slice(&channel.to_ne_bytes());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn texel(&self, x: isize, y: isize, wrap: bool) -> [f32; 4] {
+        let x = if wrap {
+            x.rem_euclid(self.width as isize)
+        } else {
+            x.clamp(0, self.width as isize - 1)
+        };
+        let y = y.clamp(0, self.height as isize - 1);
+        self.pixels[y as usize * self.width + x as usize]
+    }
+
+    // Samples at normalized coordinates, wrapping horizontally when `wrap` is set and clamping
+    // otherwise
+    pub fn sample(&self, s: f32, t: f32, sampling: Sampling, wrap: bool) -> [f32; 4] {
+        let x = s * self.width as f32 - 0.5;
+        let y = t * self.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as isize, y0 as isize);
+
+        let (offset, weights_x, weights_y) = match sampling {
+            Sampling::Bilinear => (0, [1.0 - fx, fx, 0.0, 0.0], [1.0 - fy, fy, 0.0, 0.0]),
+            Sampling::Bicubic => (1, catmull_rom(fx), catmull_rom(fy)),
+        };
+        let taps = if sampling == Sampling::Bilinear { 2 } else { 4 };
+
+        let mut result = [0.0; 4];
+        for (j, weight_y) in weights_y.iter().enumerate().take(taps) {
+            for (i, weight_x) in weights_x.iter().enumerate().take(taps) {
+                let texel = self.texel(x0 + i as isize - offset, y0 + j as isize - offset, wrap);
+                for (result, texel) in result.iter_mut().zip(texel) {
+                    *result += texel * weight_x * weight_y;
+                }
+            }
+        }
+        result
+    }
+}
+
+fn catmull_rom(t: f32) -> [f32; 4] {
+    let (t2, t3) = (t * t, t * t * t);
+    [
+        0.5 * (-t3 + 2.0 * t2 - t),
+        0.5 * (3.0 * t3 - 5.0 * t2 + 2.0),
+        0.5 * (-3.0 * t3 + 4.0 * t2 + t),
+        0.5 * (t3 - t2),
+    ]
+}
+
+pub(crate) fn float_format() -> DXGI_FORMAT { util::DXGI_FORMAT_R32G32B32A32_FLOAT }
+
+// The image as R32G32B32A32_FLOAT and the format to go back to, compressed images are returned
+// to their decompressed format
+pub(crate) fn to_float(image: &ScratchImage) -> Result<(ScratchImage, DXGI_FORMAT)> {
+    let decompressed;
+    let image = if is_compressed(image.format()) {
+        decompressed = image.decompress()?;
+        &decompressed
+    } else {
+        image
+    };
+    let float = image.convert(float_format(), TEX_FILTER_FLAGS::default())?;
+    Ok((float, image.format()))
+}
@@ -0,0 +1,261 @@
+#[cfg(feature = "serde")] use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeSet;
+
+use directxtex_sys::TexMetadata;
+
+use crate::util::is_compressed;
+use crate::{ops, Image, Result};
+
+// Distinct alpha values tracked before giving up on counting them
+const MAX_UNIQUE_ALPHA: usize = 256;
+
+// Finite values go into min, max, mean and variance, NaNs and infinities are only counted
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChannelStats {
+    pub min:            f32,
+    pub max:            f32,
+    pub mean:           f64,
+    pub variance:       f64,
+    pub nan_count:      u64,
+    pub infinite_count: u64,
+    pub denormal_count: u64,
+}
+
+impl ChannelStats {
+    #[inline]
+    #[must_use]
+    pub fn std_dev(&self) -> f64 { self.variance.sqrt() }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum AlphaClass {
+    // Every alpha is 1
+    Opaque,
+    // Every alpha is 0
+    Transparent,
+    // Only 0 and 1
+    Binary,
+    Translucent,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ImageStats {
+    pub pixel_count:         u64,
+    // R, G, B, A as read by DirectXTex, formats without alpha read as 1
+    pub channels:            [ChannelStats; 4],
+    pub alpha:               AlphaClass,
+    // None when there are more than 256 distinct alpha values
+    pub unique_alpha_values: Option<usize>,
+}
+
+impl ImageStats {
+    #[inline]
+    #[must_use]
+    pub fn has_special_values(&self) -> bool {
+        self.channels
+            .iter()
+            .any(|channel| channel.nan_count > 0 || channel.infinite_count > 0)
+    }
+}
+
+// `overall` covers every image, `subresources` has one entry per image in the same order
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Stats {
+    pub overall:      ImageStats,
+    pub subresources: Vec<ImageStats>,
+}
+
+// Welford's running mean and sum of squared deviations, summing squares loses the variance to
+// cancellation once the values are large next to their spread
+#[derive(Clone, Copy)]
+struct ChannelAccumulator {
+    min:            f32,
+    max:            f32,
+    mean:           f64,
+    m2:             f64,
+    finite_count:   u64,
+    nan_count:      u64,
+    infinite_count: u64,
+    denormal_count: u64,
+}
+
+impl Default for ChannelAccumulator {
+    fn default() -> Self {
+        Self {
+            min: f32::INFINITY,
+            max: f32::NEG_INFINITY,
+            mean: 0.0,
+            m2: 0.0,
+            finite_count: 0,
+            nan_count: 0,
+            infinite_count: 0,
+            denormal_count: 0,
+        }
+    }
+}
+
+impl ChannelAccumulator {
+    fn add(&mut self, value: f32) {
+        if value.is_nan() {
+            self.nan_count += 1;
+        } else if value.is_infinite() {
+            self.infinite_count += 1;
+        } else {
+            if value.is_subnormal() {
+                self.denormal_count += 1;
+            }
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+            self.finite_count += 1;
+            let value = f64::from(value);
+            let delta = value - self.mean;
+            self.mean += delta / self.finite_count as f64;
+            self.m2 += delta * (value - self.mean);
+        }
+    }
+
+    // Chan et al.'s pairwise combination of the two means and M2s
+    fn merge(&mut self, other: &Self) {
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        let count = self.finite_count + other.finite_count;
+        if count > 0 {
+            let delta = other.mean - self.mean;
+            let weight = other.finite_count as f64 / count as f64;
+            self.mean += delta * weight;
+            self.m2 += other.m2 + delta * delta * self.finite_count as f64 * weight;
+        }
+        self.finite_count = count;
+        self.nan_count += other.nan_count;
+        self.infinite_count += other.infinite_count;
+        self.denormal_count += other.denormal_count;
+    }
+
+    fn finish(&self) -> ChannelStats {
+        let (min, max, mean, variance) = if self.finite_count == 0 {
+            (0.0, 0.0, 0.0, 0.0)
+        } else {
+            let variance = self.m2 / self.finite_count as f64;
+            (self.min, self.max, self.mean, variance.max(0.0))
+        };
+        ChannelStats {
+            min,
+            max,
+            mean,
+            variance,
+            nan_count: self.nan_count,
+            infinite_count: self.infinite_count,
+            denormal_count: self.denormal_count,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+struct Accumulator {
+    pixel_count:  u64,
+    channels:     [ChannelAccumulator; 4],
+    all_opaque:   bool,
+    all_clear:    bool,
+    all_binary:   bool,
+    // Alpha bit patterns, None once there are too many
+    unique_alpha: Option<BTreeSet<u32>>,
+}
+
+impl Accumulator {
+    fn new() -> Self {
+        Self {
+            all_opaque: true,
+            all_clear: true,
+            all_binary: true,
+            unique_alpha: Some(BTreeSet::new()),
+            ..Self::default()
+        }
+    }
+
+    fn add(&mut self, pixel: [f32; 4]) {
+        self.pixel_count += 1;
+        for (channel, value) in self.channels.iter_mut().zip(pixel) {
+            channel.add(value);
+        }
+
+        let alpha = pixel[3];
+        self.all_opaque &= alpha >= 1.0;
+        self.all_clear &= alpha <= 0.0;
+        self.all_binary &= alpha <= 0.0 || alpha >= 1.0;
+        self.add_unique(std::iter::once(alpha.to_bits()));
+    }
+
+    fn add_unique(&mut self, values: impl IntoIterator<Item = u32>) {
+        if let Some(unique) = &mut self.unique_alpha {
+            unique.extend(values);
+            if unique.len() > MAX_UNIQUE_ALPHA {
+                self.unique_alpha = None;
+            }
+        }
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.pixel_count += other.pixel_count;
+        for (channel, other) in self.channels.iter_mut().zip(&other.channels) {
+            channel.merge(other);
+        }
+        self.all_opaque &= other.all_opaque;
+        self.all_clear &= other.all_clear;
+        self.all_binary &= other.all_binary;
+        match &other.unique_alpha {
+            Some(values) => self.add_unique(values.iter().copied()),
+            None => self.unique_alpha = None,
+        }
+    }
+
+    fn finish(&self) -> ImageStats {
+        let alpha = if self.all_opaque {
+            AlphaClass::Opaque
+        } else if self.all_clear {
+            AlphaClass::Transparent
+        } else if self.all_binary {
+            AlphaClass::Binary
+        } else {
+            AlphaClass::Translucent
+        };
+        ImageStats {
+            pixel_count: self.pixel_count,
+            channels: self.channels.map(|channel| channel.finish()),
+            alpha,
+            unique_alpha_values: self.unique_alpha.as_ref().map(BTreeSet::len),
+        }
+    }
+}
+
+// Per channel statistics of every image in `images`, each of which is read through
+// `evaluate_image`. Block compressed images are decompressed first.
+pub fn statistics(images: &[Image], metadata: &TexMetadata) -> Result<Stats> {
+    if is_compressed(metadata.format) {
+        let decompressed = ops::decompress(images, metadata)?;
+        return statistics(decompressed.images(), decompressed.metadata());
+    }
+
+    let mut overall = Accumulator::new();
+    let mut subresources = Vec::with_capacity(images.len());
+    for image in images {
+        let mut accumulator = Accumulator::new();
+        ops::evaluate_image(image, |pixels, _| {
+            for pixel in pixels {
+                accumulator.add(pixel.to_array());
+            }
+        })?;
+        overall.merge(&accumulator);
+        subresources.push(accumulator.finish());
+    }
+
+    Ok(Stats {
+        overall: overall.finish(),
+        subresources,
+    })
+}
@@ -0,0 +1,109 @@
+#[cfg(feature = "serde")] use serde::{Deserialize, Serialize};
+
+use directxtex_sys::{TexMetadata, XMVECTORF32};
+
+use crate::flags::TEX_FILTER_FLAGS;
+use crate::util::{self, is_compressed};
+use crate::{error, ops, Image, Result, ScratchImage};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Operator {
+    // c / (1 + c) per channel
+    Reinhard,
+    // Narkowicz's fit of the ACES filmic curve
+    Aces,
+    // 1 - e^(-exposure * c), encoded with the given gamma instead of the sRGB curve. The gamma has
+    // to be positive.
+    Exposure { exposure: f32, gamma: f32 },
+    // John Hable's Uncharted 2 curve, with his exposure bias of 2 and white point of 11.2
+    Hable,
+}
+
+fn hable_curve(x: f32) -> f32 {
+    const A: f32 = 0.15;
+    const B: f32 = 0.50;
+    const C: f32 = 0.10;
+    const D: f32 = 0.20;
+    const E: f32 = 0.02;
+    const F: f32 = 0.30;
+    ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F
+}
+
+#[must_use]
+pub fn srgb_encode(linear: f32) -> f32 {
+    if linear <= 0.003_130_8 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[must_use]
+pub fn srgb_decode(encoded: f32) -> f32 {
+    if encoded <= 0.040_45 {
+        encoded / 12.92
+    } else {
+        ((encoded + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+impl Operator {
+    // Maps a linear HDR value to a display encoded value in [0, 1]
+    #[must_use]
+    pub fn map(self, value: f32) -> f32 {
+        // Also turns NaN into black
+        let value = value.max(0.0);
+        let mapped = match self {
+            Self::Reinhard => value / (1.0 + value),
+            Self::Aces => {
+                (value * (2.51 * value + 0.03)) / (value * (2.43 * value + 0.59) + 0.14)
+            }
+            Self::Exposure { exposure, gamma } => {
+                let mapped = 1.0 - (-exposure * value).exp();
+                return mapped.clamp(0.0, 1.0).powf(1.0 / gamma);
+            }
+            Self::Hable => hable_curve(value * 2.0) / hable_curve(11.2),
+        };
+        srgb_encode(mapped.clamp(0.0, 1.0))
+    }
+}
+
+// Tone maps float or HDR images into R8G8B8A8_UNORM_SRGB, alpha is clamped
+pub fn tonemap(
+    images: &[Image],
+    metadata: &TexMetadata,
+    operator: Operator,
+) -> Result<ScratchImage> {
+    if let Operator::Exposure { gamma, .. } = operator {
+        if gamma.is_nan() || gamma <= 0.0 {
+            return Err(error::invalid_arg());
+        }
+    }
+
+    // TransformImage can't read block compressed data, like BC6H skies
+    if is_compressed(metadata.format) {
+        let decompressed = ops::decompress(images, metadata)?;
+        return tonemap(decompressed.images(), decompressed.metadata(), operator);
+    }
+
+    let mapped = ops::transform_images(images, metadata, |out_pixels, in_pixels, _| {
+        for (out, &XMVECTORF32(r, g, b, a)) in out_pixels.iter_mut().zip(in_pixels) {
+            *out = XMVECTORF32(
+                operator.map(r),
+                operator.map(g),
+                operator.map(b),
+                a.clamp(0.0, 1.0),
+            );
+        }
+    })?;
+
+    // The values are already encoded, so they are stored as they are
+    ops::convert(
+        mapped.images(),
+        mapped.metadata(),
+        util::DXGI_FORMAT_R8G8B8A8_UNORM_SRGB,
+        TEX_FILTER_FLAGS::TEX_FILTER_SRGB_IN,
+    )
+}
@@ -0,0 +1,412 @@
+use std::path::Path;
+
+#[cfg(windows)] use crate::flags::WIC_FLAGS;
+use crate::flags::{DDS_FLAGS, TEX_COMPRESS_FLAGS, TEX_FILTER_FLAGS, TGA_FLAGS};
+use crate::ktx2::Supercompression;
+use crate::util::is_compressed;
+use crate::{FileType, LoadLimits, Result, ScratchImage, DXGI_FORMAT};
+
+// How TGA and WIC files choose between sRGB and linear formats when loading, and what they
+// record when saving. DDS, KTX2 and the float formats always go by the DXGI format.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SrgbHandling {
+    // Whatever the file says
+    #[default]
+    FromFile,
+    // Files that don't say are sRGB
+    DefaultSrgb,
+    // Anything the file says is ignored
+    Ignore,
+    ForceSrgb,
+    ForceLinear,
+}
+
+impl SrgbHandling {
+    #[must_use]
+    pub const fn tga_flags(self) -> TGA_FLAGS {
+        match self {
+            Self::FromFile => TGA_FLAGS::TGA_FLAGS_NONE,
+            Self::DefaultSrgb => TGA_FLAGS::TGA_FLAGS_DEFAULT_SRGB,
+            Self::Ignore => TGA_FLAGS::TGA_FLAGS_IGNORE_SRGB,
+            Self::ForceSrgb => TGA_FLAGS::TGA_FLAGS_FORCE_SRGB,
+            Self::ForceLinear => TGA_FLAGS::TGA_FLAGS_FORCE_LINEAR,
+        }
+    }
+
+    #[cfg(windows)]
+    #[must_use]
+    pub const fn wic_flags(self) -> WIC_FLAGS {
+        match self {
+            Self::FromFile => WIC_FLAGS::WIC_FLAGS_NONE,
+            Self::DefaultSrgb => WIC_FLAGS::WIC_FLAGS_DEFAULT_SRGB,
+            Self::Ignore => WIC_FLAGS::WIC_FLAGS_IGNORE_SRGB,
+            Self::ForceSrgb => WIC_FLAGS::WIC_FLAGS_FORCE_SRGB,
+            Self::ForceLinear => WIC_FLAGS::WIC_FLAGS_FORCE_LINEAR,
+        }
+    }
+}
+
+// The target format shared by both option sets. Block compressed images are decompressed first,
+// compressed targets go through `compress`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Target {
+    format:         DXGI_FORMAT,
+    filter:         TEX_FILTER_FLAGS,
+    compress_flags: TEX_COMPRESS_FLAGS,
+}
+
+impl Target {
+    fn apply(&self, image: &ScratchImage) -> Result<ScratchImage> {
+        if !image.is_compressed() {
+            return self.convert_or_compress(image);
+        }
+        let decompressed = image.decompress()?;
+        if decompressed.format() == self.format {
+            Ok(decompressed)
+        } else {
+            self.convert_or_compress(&decompressed)
+        }
+    }
+
+    fn convert_or_compress(&self, image: &ScratchImage) -> Result<ScratchImage> {
+        if is_compressed(self.format) {
+            image.compress(self.format, self.compress_flags)
+        } else {
+            image.convert(self.format, self.filter)
+        }
+    }
+}
+
+// Settings for `directxtex::load_with`. Each setting only applies to the formats that know about
+// it, the rest ignore it. Files over `LoadLimits::default()` are rejected unless `limits` says
+// otherwise.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LoadOptions {
+    pub(crate) file_type: Option<FileType>,
+    pub(crate) limits:    LoadLimits,
+    target:               Option<Target>,
+    srgb:                 SrgbHandling,
+    dds_flags:            DDS_FLAGS,
+    tga_flags:            TGA_FLAGS,
+    #[cfg(windows)]
+    wic_flags:            WIC_FLAGS,
+}
+
+impl LoadOptions {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self { Self::default() }
+
+    // Reads the file as `file_type` whatever its extension
+    #[must_use]
+    pub fn file_type(mut self, file_type: FileType) -> Self {
+        self.file_type = Some(file_type);
+        self
+    }
+
+    #[must_use]
+    pub fn limits(mut self, limits: LoadLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    // Converts or compresses into `format` after loading
+    #[must_use]
+    pub fn format(self, format: DXGI_FORMAT) -> Self {
+        self.format_with(format, TEX_FILTER_FLAGS::default(), TEX_COMPRESS_FLAGS::default())
+    }
+
+    #[must_use]
+    pub fn format_with(
+        mut self,
+        format: DXGI_FORMAT,
+        filter: TEX_FILTER_FLAGS,
+        compress_flags: TEX_COMPRESS_FLAGS,
+    ) -> Self {
+        self.target = Some(Target {
+            format,
+            filter,
+            compress_flags,
+        });
+        self
+    }
+
+    // TGA and WIC
+    #[must_use]
+    pub fn srgb(mut self, srgb: SrgbHandling) -> Self {
+        self.srgb = srgb;
+        self
+    }
+
+    // DDS and WIC: no BGR formats, they are swizzled to RGB
+    #[must_use]
+    pub fn force_rgb(mut self) -> Self {
+        self.dds_flags |= DDS_FLAGS::DDS_FLAGS_FORCE_RGB;
+        #[cfg(windows)]
+        {
+            self.wic_flags |= WIC_FLAGS::WIC_FLAGS_FORCE_RGB;
+        }
+        self
+    }
+
+    // DDS and WIC: 16 bits per pixel formats are expanded to 32
+    #[must_use]
+    pub fn no_16bpp(mut self) -> Self {
+        self.dds_flags |= DDS_FLAGS::DDS_FLAGS_NO_16BPP;
+        #[cfg(windows)]
+        {
+            self.wic_flags |= WIC_FLAGS::WIC_FLAGS_NO_16BPP;
+        }
+        self
+    }
+
+    // DDS: legacy luminance formats are expanded to RGBA instead of mapped to R or RG
+    #[must_use]
+    pub fn expand_luminance(mut self) -> Self {
+        self.dds_flags |= DDS_FLAGS::DDS_FLAGS_EXPAND_LUMINANCE;
+        self
+    }
+
+    // DDS: only the top mip is loaded
+    #[must_use]
+    pub fn ignore_mips(mut self) -> Self {
+        self.dds_flags |= DDS_FLAGS::DDS_FLAGS_IGNORE_MIPS;
+        self
+    }
+
+    // DDS: accepts some malformed headers other tools write
+    #[must_use]
+    pub fn permissive(mut self) -> Self {
+        self.dds_flags |= DDS_FLAGS::DDS_FLAGS_PERMISSIVE;
+        self
+    }
+
+    // TGA: 24 and 32 bits per pixel files are returned as BGRX and BGRA
+    #[must_use]
+    pub fn bgr(mut self) -> Self {
+        self.tga_flags |= TGA_FLAGS::TGA_FLAGS_BGR;
+        self
+    }
+
+    // TGA: keeps an alpha channel that is zero everywhere instead of treating it as opaque
+    #[must_use]
+    pub fn allow_all_zero_alpha(mut self) -> Self {
+        self.tga_flags |= TGA_FLAGS::TGA_FLAGS_ALLOW_ALL_ZERO_ALPHA;
+        self
+    }
+
+    // WIC: loads every frame of a multi-frame TIFF or GIF as an array
+    #[cfg(windows)]
+    #[must_use]
+    pub fn all_frames(mut self) -> Self {
+        self.wic_flags |= WIC_FLAGS::WIC_FLAGS_ALL_FRAMES;
+        self
+    }
+
+    // Raw flags for anything not covered above, combined with what the other methods set
+    #[must_use]
+    pub fn dds_flags(mut self, flags: DDS_FLAGS) -> Self {
+        self.dds_flags |= flags;
+        self
+    }
+
+    #[must_use]
+    pub fn tga_flags(mut self, flags: TGA_FLAGS) -> Self {
+        self.tga_flags |= flags;
+        self
+    }
+
+    #[cfg(windows)]
+    #[must_use]
+    pub fn wic_flags(mut self, flags: WIC_FLAGS) -> Self {
+        self.wic_flags |= flags;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn to_dds_flags(&self) -> DDS_FLAGS { self.dds_flags }
+
+    #[inline]
+    #[must_use]
+    pub fn to_tga_flags(&self) -> TGA_FLAGS { self.tga_flags | self.srgb.tga_flags() }
+
+    #[cfg(windows)]
+    #[inline]
+    #[must_use]
+    pub fn to_wic_flags(&self) -> WIC_FLAGS { self.wic_flags | self.srgb.wic_flags() }
+
+    pub(crate) fn finish(&self, image: ScratchImage) -> Result<ScratchImage> {
+        match self.target {
+            Some(target) if target.format != image.format() => target.apply(&image),
+            _ => Ok(image),
+        }
+    }
+}
+
+// Settings for `ScratchImage::save_with`. DDS and KTX2 hold the whole image, the other formats
+// save a single image, the top mip of `item`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SaveOptions {
+    file_type:        Option<FileType>,
+    target:           Option<Target>,
+    item:             usize,
+    srgb:             SrgbHandling,
+    supercompression: Supercompression,
+    dds_flags:        DDS_FLAGS,
+    tga_flags:        TGA_FLAGS,
+    #[cfg(windows)]
+    wic_flags:        WIC_FLAGS,
+}
+
+impl SaveOptions {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self { Self::default() }
+
+    // Writes the file as `file_type` whatever its extension
+    #[must_use]
+    pub fn file_type(mut self, file_type: FileType) -> Self {
+        self.file_type = Some(file_type);
+        self
+    }
+
+    // Saves through WIC with the given container, like `file_type(FileType::Wic(container))`
+    #[cfg(windows)]
+    #[must_use]
+    pub fn wic_container(self, container: crate::WICCodecs) -> Self {
+        self.file_type(FileType::Wic(container))
+    }
+
+    // Converts or compresses into `format` before saving, the image itself is left alone
+    #[must_use]
+    pub fn format(self, format: DXGI_FORMAT) -> Self {
+        self.format_with(format, TEX_FILTER_FLAGS::default(), TEX_COMPRESS_FLAGS::default())
+    }
+
+    #[must_use]
+    pub fn format_with(
+        mut self,
+        format: DXGI_FORMAT,
+        filter: TEX_FILTER_FLAGS,
+        compress_flags: TEX_COMPRESS_FLAGS,
+    ) -> Self {
+        self.target = Some(Target {
+            format,
+            filter,
+            compress_flags,
+        });
+        self
+    }
+
+    // The array item saved to single image formats
+    #[must_use]
+    pub fn item(mut self, item: usize) -> Self {
+        self.item = item;
+        self
+    }
+
+    // TGA and WIC
+    #[must_use]
+    pub fn srgb(mut self, srgb: SrgbHandling) -> Self {
+        self.srgb = srgb;
+        self
+    }
+
+    #[must_use]
+    pub fn supercompression(mut self, supercompression: Supercompression) -> Self {
+        self.supercompression = supercompression;
+        self
+    }
+
+    // DDS: always writes the DX10 header extension
+    #[must_use]
+    pub fn force_dx10_header(mut self) -> Self {
+        self.dds_flags |= DDS_FLAGS::DDS_FLAGS_FORCE_DX10_EXT;
+        self
+    }
+
+    // DDS: writes a legacy header wherever the format allows it, for older readers
+    #[must_use]
+    pub fn force_dx9_legacy(mut self) -> Self {
+        self.dds_flags |= DDS_FLAGS::DDS_FLAGS_FORCE_DX9_LEGACY;
+        self
+    }
+
+    // WIC: dithers when the container's pixel format has fewer bits
+    #[cfg(windows)]
+    #[must_use]
+    pub fn dither(mut self) -> Self {
+        self.wic_flags |= WIC_FLAGS::WIC_FLAGS_DITHER;
+        self
+    }
+
+    // Raw flags for anything not covered above, combined with what the other methods set
+    #[must_use]
+    pub fn dds_flags(mut self, flags: DDS_FLAGS) -> Self {
+        self.dds_flags |= flags;
+        self
+    }
+
+    #[must_use]
+    pub fn tga_flags(mut self, flags: TGA_FLAGS) -> Self {
+        self.tga_flags |= flags;
+        self
+    }
+
+    #[cfg(windows)]
+    #[must_use]
+    pub fn wic_flags(mut self, flags: WIC_FLAGS) -> Self {
+        self.wic_flags |= flags;
+        self
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn to_dds_flags(&self) -> DDS_FLAGS { self.dds_flags }
+
+    #[inline]
+    #[must_use]
+    pub fn to_tga_flags(&self) -> TGA_FLAGS { self.tga_flags | self.srgb.tga_flags() }
+
+    #[cfg(windows)]
+    #[inline]
+    #[must_use]
+    pub fn to_wic_flags(&self) -> WIC_FLAGS { self.wic_flags | self.srgb.wic_flags() }
+
+    pub(crate) fn save(&self, image: &ScratchImage, file_name: &Path) -> Result<()> {
+        let file_type = match self.file_type {
+            Some(file_type) => file_type,
+            None => FileType::from_path(file_name).ok_or_else(crate::error::invalid_arg)?,
+        };
+        let converted;
+        let image = match self.target {
+            Some(target) if target.format != image.format() => {
+                converted = target.apply(image)?;
+                &converted
+            }
+            _ => image,
+        };
+
+        match file_type {
+            FileType::Dds => image.save_dds(file_name, self.dds_flags),
+            FileType::Ktx2 => image.save_ktx2(file_name, self.supercompression),
+            FileType::Tga => image.save_tga(self.item, file_name, self.to_tga_flags()),
+            FileType::Hdr => image.save_hdr(self.item, file_name),
+            FileType::Exr => image.save_exr(self.item, file_name),
+            #[cfg(feature = "png")]
+            FileType::Png => crate::png::save(file_name, self.top_image(image)?),
+            #[cfg(feature = "jpeg")]
+            FileType::Jpeg => crate::jpeg::save(file_name, self.top_image(image)?),
+            #[cfg(windows)]
+            FileType::Wic(container) => {
+                image.save_wic(self.item, file_name, container, self.to_wic_flags())
+            }
+        }
+    }
+
+    #[cfg(any(feature = "png", feature = "jpeg"))]
+    fn top_image<'image>(&self, image: &'image ScratchImage) -> Result<&'image crate::Image> {
+        image.image(0, self.item, 0).ok_or_else(crate::error::invalid_arg)
+    }
+}
@@ -0,0 +1,213 @@
+use crate::flags::TEX_FR_FLAGS;
+use crate::util::{bits_per_pixel, is_compressed, is_packed, is_planar};
+use crate::{error, Result, ScratchImage};
+
+// How the stored pixels relate to the upright image, using the EXIF orientation tag values
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(u16)]
+pub enum Orientation {
+    #[default]
+    Normal         = 1,
+    FlipHorizontal = 2,
+    Rotate180      = 3,
+    FlipVertical   = 4,
+    Transpose      = 5,
+    Rotate90       = 6,
+    Transverse     = 7,
+    Rotate270      = 8,
+}
+
+const TGA_DESCRIPTOR_RIGHT_TO_LEFT: u8 = 0x10;
+const TGA_DESCRIPTOR_TOP_TO_BOTTOM: u8 = 0x20;
+
+const EXIF_ORIENTATION_TAG: u16 = 0x0112;
+
+impl Orientation {
+    #[must_use]
+    pub const fn from_exif(tag: u16) -> Option<Self> {
+        match tag {
+            1 => Some(Self::Normal),
+            2 => Some(Self::FlipHorizontal),
+            3 => Some(Self::Rotate180),
+            4 => Some(Self::FlipVertical),
+            5 => Some(Self::Transpose),
+            6 => Some(Self::Rotate90),
+            7 => Some(Self::Transverse),
+            8 => Some(Self::Rotate270),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn exif(self) -> u16 { self as u16 }
+
+    // `tga::load` already honours the origin bits, this is for pixel data read by other means
+    #[must_use]
+    pub const fn from_tga_descriptor(descriptor: u8) -> Self {
+        let right_to_left = descriptor & TGA_DESCRIPTOR_RIGHT_TO_LEFT != 0;
+        let top_to_bottom = descriptor & TGA_DESCRIPTOR_TOP_TO_BOTTOM != 0;
+        match (right_to_left, top_to_bottom) {
+            (false, true) => Self::Normal,
+            (true, true) => Self::FlipHorizontal,
+            (false, false) => Self::FlipVertical,
+            (true, false) => Self::Rotate180,
+        }
+    }
+
+    #[must_use]
+    pub fn from_tga_header(buffer: &[u8]) -> Option<Self> {
+        const TGA_HEADER_SIZE: usize = 18;
+        if buffer.len() < TGA_HEADER_SIZE {
+            return None;
+        }
+        Some(Self::from_tga_descriptor(buffer[17]))
+    }
+
+    // Finds the orientation tag in the APP1 Exif segment of a JPEG, WIC ignores it when decoding
+    #[must_use]
+    pub fn from_jpeg(buffer: &[u8]) -> Option<Self> {
+        if buffer.get(..2)? != [0xff, 0xd8] {
+            return None;
+        }
+
+        let mut offset = 2;
+        while offset + 4 <= buffer.len() {
+            if buffer[offset] != 0xff {
+                return None;
+            }
+            let marker = buffer[offset + 1];
+            // Start of scan, no more metadata after this
+            if marker == 0xda {
+                return None;
+            }
+            let length = usize::from(u16::from_be_bytes([buffer[offset + 2], buffer[offset + 3]]));
+            let segment = buffer.get(offset + 4..offset + 2 + length)?;
+            if marker == 0xe1 && segment.starts_with(b"Exif\0\0") {
+                return Self::from_tiff(&segment[6..]);
+            }
+            offset += 2 + length;
+        }
+
+        None
+    }
+
+    // Reads the orientation tag from IFD0 of a TIFF structure, like the body of an Exif segment
+    #[must_use]
+    pub fn from_tiff(tiff: &[u8]) -> Option<Self> {
+        let little_endian = match tiff.get(..2)? {
+            b"II" => true,
+            b"MM" => false,
+            _ => return None,
+        };
+        let read_u16 = |at: usize| -> Option<u16> {
+            let bytes = [*tiff.get(at)?, *tiff.get(at + 1)?];
+            Some(if little_endian { u16::from_le_bytes(bytes) } else { u16::from_be_bytes(bytes) })
+        };
+        let read_u32 = |at: usize| -> Option<u32> {
+            let bytes = [*tiff.get(at)?, *tiff.get(at + 1)?, *tiff.get(at + 2)?, *tiff.get(at + 3)?];
+            Some(if little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+        };
+
+        let ifd = usize::try_from(read_u32(4)?).ok()?;
+        let entries = usize::from(read_u16(ifd)?);
+        (0..entries)
+            .map(|entry| ifd + 2 + entry * 12)
+            .find(|&entry| read_u16(entry) == Some(EXIF_ORIENTATION_TAG))
+            .and_then(|entry| read_u16(entry + 8))
+            .and_then(Self::from_exif)
+    }
+
+    // FlipRotate flags that bring an image with this orientation upright, rotation is applied
+    // before flipping
+    #[must_use]
+    pub const fn fix_flags(self) -> TEX_FR_FLAGS {
+        match self {
+            Self::Normal => TEX_FR_FLAGS::TEX_FR_ROTATE0,
+            Self::FlipHorizontal => TEX_FR_FLAGS::TEX_FR_FLIP_HORIZONTAL,
+            Self::Rotate180 => TEX_FR_FLAGS::TEX_FR_ROTATE180,
+            Self::FlipVertical => TEX_FR_FLAGS::TEX_FR_FLIP_VERTICAL,
+            Self::Transpose => TEX_FR_FLAGS(
+                TEX_FR_FLAGS::TEX_FR_ROTATE90.0 | TEX_FR_FLAGS::TEX_FR_FLIP_HORIZONTAL.0,
+            ),
+            Self::Rotate90 => TEX_FR_FLAGS::TEX_FR_ROTATE90,
+            Self::Transverse => TEX_FR_FLAGS(
+                TEX_FR_FLAGS::TEX_FR_ROTATE270.0 | TEX_FR_FLAGS::TEX_FR_FLIP_HORIZONTAL.0,
+            ),
+            Self::Rotate270 => TEX_FR_FLAGS::TEX_FR_ROTATE270,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub const fn swaps_dimensions(self) -> bool {
+        matches!(
+            self,
+            Self::Transpose | Self::Rotate90 | Self::Transverse | Self::Rotate270
+        )
+    }
+
+    // Maps a pixel of the upright image back to the stored image of `width` x `height`
+    #[inline]
+    #[must_use]
+    pub const fn source_pixel(
+        self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> (usize, usize) {
+        match self {
+            Self::Normal => (x, y),
+            Self::FlipHorizontal => (width - 1 - x, y),
+            Self::Rotate180 => (width - 1 - x, height - 1 - y),
+            Self::FlipVertical => (x, height - 1 - y),
+            Self::Transpose => (y, x),
+            Self::Rotate90 => (y, height - 1 - x),
+            Self::Transverse => (width - 1 - y, height - 1 - x),
+            Self::Rotate270 => (width - 1 - y, x),
+        }
+    }
+}
+
+// Pure Rust equivalent of FlipRotate with `fix_flags`, which also works where DirectXTex is
+// built without WIC. Handles every uncompressed format with whole bytes per pixel.
+pub fn normalize(image: &ScratchImage, orientation: Orientation) -> Result<ScratchImage> {
+    let format = image.format();
+    let bits = bits_per_pixel(format);
+    if is_compressed(format) || is_packed(format) || is_planar(format) || bits == 0 || bits % 8 != 0
+    {
+        return Err(error::invalid_arg());
+    }
+    let pixel_size = bits / 8;
+
+    let mut metadata = *image.metadata();
+    if orientation.swaps_dimensions() {
+        std::mem::swap(&mut metadata.width, &mut metadata.height);
+    }
+    let mut out = ScratchImage::initialize(&metadata)?;
+
+    for (index, src) in image.images().iter().enumerate() {
+        let (width, height, src_pitch) = (src.width(), src.height(), src.row_pitch());
+        let src_pixels = src.buffer();
+
+        let dst_pitch = out.images()[index].row_pitch();
+        let (dst_width, dst_height) = if orientation.swaps_dimensions() {
+            (height, width)
+        } else {
+            (width, height)
+        };
+        let dst_pixels = out.image_buffer_mut(index).ok_or_else(error::fail)?;
+
+        for y in 0..dst_height {
+            let dst_row = &mut dst_pixels[y * dst_pitch..][..dst_width * pixel_size];
+            for (x, dst) in dst_row.chunks_exact_mut(pixel_size).enumerate() {
+                let (src_x, src_y) = orientation.source_pixel(x, y, width, height);
+                let src = &src_pixels[src_y * src_pitch + src_x * pixel_size..][..pixel_size];
+                dst.copy_from_slice(src);
+            }
+        }
+    }
+
+    Ok(out)
+}
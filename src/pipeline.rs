@@ -0,0 +1,274 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use directxtex_sys::DXGI_FORMAT;
+
+use crate::flags::{
+    CNMAP_FLAGS,
+    DDS_FLAGS,
+    TEX_COMPRESS_FLAGS,
+    TEX_FILTER_FLAGS,
+    TEX_FR_FLAGS,
+    TEX_PMALPHA_FLAGS,
+};
+use crate::ktx2::Supercompression;
+use crate::{error, ops, FileType, Result, ScratchImage};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Step {
+    Decompress,
+    Resize {
+        width:  usize,
+        height: usize,
+        filter: TEX_FILTER_FLAGS,
+    },
+    FlipRotate {
+        flags: TEX_FR_FLAGS,
+    },
+    PremultiplyAlpha {
+        flags: TEX_PMALPHA_FLAGS,
+    },
+    GenerateMips {
+        levels: usize,
+        filter: TEX_FILTER_FLAGS,
+    },
+    NormalMap {
+        flags:     CNMAP_FLAGS,
+        amplitude: f32,
+        format:    DXGI_FORMAT,
+    },
+    Convert {
+        format: DXGI_FORMAT,
+        filter: TEX_FILTER_FLAGS,
+    },
+    Compress {
+        format: DXGI_FORMAT,
+        flags:  TEX_COMPRESS_FLAGS,
+    },
+    // DDS and KTX2 keep every mip and item, other file types only get the first image
+    Save {
+        path:      PathBuf,
+        file_type: FileType,
+    },
+}
+
+impl Step {
+    #[must_use]
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Decompress => "Decompress",
+            Self::Resize { .. } => "Resize",
+            Self::FlipRotate { .. } => "FlipRotate",
+            Self::PremultiplyAlpha { .. } => "PremultiplyAlpha",
+            Self::GenerateMips { .. } => "GenerateMips",
+            Self::NormalMap { .. } => "NormalMap",
+            Self::Convert { .. } => "Convert",
+            Self::Compress { .. } => "Compress",
+            Self::Save { .. } => "Save",
+        }
+    }
+
+    #[must_use]
+    const fn changes_dimensions(&self) -> bool {
+        matches!(self, Self::Resize { .. } | Self::FlipRotate { .. })
+    }
+
+    #[must_use]
+    const fn drops_mips(&self) -> bool {
+        match self {
+            Self::Save { file_type, .. } => !matches!(file_type, FileType::Dds | FileType::Ktx2),
+            _ => false,
+        }
+    }
+
+    fn run(&self, image: ScratchImage) -> Result<ScratchImage> {
+        match self {
+            Self::Decompress => image.maybe_decompress(),
+            Self::Resize {
+                width,
+                height,
+                filter,
+            } => image.resize(*width, *height, *filter),
+            Self::FlipRotate { flags } => {
+                ops::flip_rotate(image.images(), image.metadata(), *flags)
+            }
+            Self::PremultiplyAlpha { flags } => image.premultiply_alpha(*flags),
+            Self::GenerateMips { levels, filter } => image.generate_mipmaps(*levels, *filter),
+            Self::NormalMap {
+                flags,
+                amplitude,
+                format,
+            } => image.compute_normal_map(*flags, *amplitude, *format),
+            Self::Convert { format, filter } => image.into_converted(*format, *filter),
+            Self::Compress { format, flags } => image.compress(*format, *flags),
+            Self::Save { path, file_type } => {
+                match file_type {
+                    FileType::Dds => image.save_dds(path.as_path(), DDS_FLAGS::default())?,
+                    FileType::Ktx2 => image.save_ktx2(path, Supercompression::default())?,
+                    _ => crate::save_as(path, *file_type, image.image0(), image.metadata())?,
+                }
+                Ok(image)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct StepTiming {
+    pub index:   usize,
+    pub name:    &'static str,
+    pub elapsed: Duration,
+}
+
+#[derive(Debug)]
+pub struct PipelineOutput {
+    pub image:   ScratchImage,
+    pub timings: Vec<StepTiming>,
+}
+
+impl PipelineOutput {
+    #[must_use]
+    pub fn total(&self) -> Duration { self.timings.iter().map(|timing| timing.elapsed).sum() }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Pipeline {
+    steps: Vec<Step>,
+}
+
+impl Pipeline {
+    #[inline]
+    #[must_use]
+    pub fn builder() -> PipelineBuilder { PipelineBuilder::default() }
+
+    #[inline]
+    #[must_use]
+    pub fn steps(&self) -> &[Step] { &self.steps }
+
+    // Ordering rules:
+    // - Only Save can follow Compress and there can only be one Compress
+    // - Decompress has to come before anything that touches pixels
+    // - Resize and FlipRotate can't follow GenerateMips, the mip chain would be thrown away
+    // - Saves after GenerateMips have to be DDS or KTX2, other file types only hold the top mip
+    // Errors name the offending step and its index.
+    pub fn validate(steps: &[Step]) -> Result<()> {
+        let mut compressed = false;
+        let mut processed = false;
+        let mut mipmapped = false;
+
+        for (index, step) in steps.iter().enumerate() {
+            let invalid = |reason: &str| {
+                let message = format!("step {index} ({}) {reason}", step.name());
+                Err(error::with_message(&error::invalid_arg(), &message))
+            };
+            match step {
+                step if mipmapped && step.drops_mips() => {
+                    return invalid("would only save the top mip made by GenerateMips")
+                }
+                Step::Save { .. } => continue,
+                _ if compressed => return invalid("can't follow Compress"),
+                Step::Decompress if processed => {
+                    return invalid("has to come before the steps that process pixels")
+                }
+                step if mipmapped && step.changes_dimensions() => {
+                    return invalid("would throw away the mips made by GenerateMips")
+                }
+                Step::GenerateMips { .. } => mipmapped = true,
+                Step::Compress { .. } => compressed = true,
+                _ => (),
+            }
+
+            if !matches!(step, Step::Decompress) {
+                processed = true;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn run(&self, mut image: ScratchImage) -> Result<PipelineOutput> {
+        let mut timings = Vec::with_capacity(self.steps.len());
+
+        for (index, step) in self.steps.iter().enumerate() {
+            let start = Instant::now();
+            image = step.run(image)?;
+            timings.push(StepTiming {
+                index,
+                name: step.name(),
+                elapsed: start.elapsed(),
+            });
+        }
+
+        Ok(PipelineOutput { image, timings })
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PipelineBuilder {
+    steps: Vec<Step>,
+}
+
+impl PipelineBuilder {
+    #[must_use]
+    pub fn step(mut self, step: Step) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    #[must_use]
+    pub fn decompress(self) -> Self { self.step(Step::Decompress) }
+
+    #[must_use]
+    pub fn resize(self, width: usize, height: usize, filter: TEX_FILTER_FLAGS) -> Self {
+        self.step(Step::Resize {
+            width,
+            height,
+            filter,
+        })
+    }
+
+    #[must_use]
+    pub fn flip_rotate(self, flags: TEX_FR_FLAGS) -> Self { self.step(Step::FlipRotate { flags }) }
+
+    #[must_use]
+    pub fn premultiply_alpha(self, flags: TEX_PMALPHA_FLAGS) -> Self {
+        self.step(Step::PremultiplyAlpha { flags })
+    }
+
+    #[must_use]
+    pub fn generate_mips(self, levels: usize, filter: TEX_FILTER_FLAGS) -> Self {
+        self.step(Step::GenerateMips { levels, filter })
+    }
+
+    #[must_use]
+    pub fn normal_map(self, flags: CNMAP_FLAGS, amplitude: f32, format: DXGI_FORMAT) -> Self {
+        self.step(Step::NormalMap {
+            flags,
+            amplitude,
+            format,
+        })
+    }
+
+    #[must_use]
+    pub fn convert(self, format: DXGI_FORMAT, filter: TEX_FILTER_FLAGS) -> Self {
+        self.step(Step::Convert { format, filter })
+    }
+
+    #[must_use]
+    pub fn compress(self, format: DXGI_FORMAT, flags: TEX_COMPRESS_FLAGS) -> Self {
+        self.step(Step::Compress { format, flags })
+    }
+
+    #[must_use]
+    pub fn save(self, path: impl Into<PathBuf>, file_type: FileType) -> Self {
+        self.step(Step::Save {
+            path: path.into(),
+            file_type,
+        })
+    }
+
+    pub fn build(self) -> Result<Pipeline> {
+        Pipeline::validate(&self.steps)?;
+        Ok(Pipeline { steps: self.steps })
+    }
+}
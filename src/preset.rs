@@ -0,0 +1,270 @@
+#[cfg(feature = "serde")] use serde::{Deserialize, Serialize};
+
+use crate::flags::{CNMAP_FLAGS, TEX_FILTER_FLAGS, TEX_PMALPHA_FLAGS};
+use crate::util::{
+    has_alpha,
+    make_linear,
+    make_srgb,
+    DXGI_FORMAT_BC5_UNORM,
+    DXGI_FORMAT_BC6H_UF16,
+    DXGI_FORMAT_BC7_UNORM,
+    DXGI_FORMAT_R8G8B8A8_UNORM,
+};
+use crate::{Result, ScratchImage, DXGI_FORMAT};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum SrgbMode {
+    // Use the target format as written
+    #[default]
+    Auto,
+    // Color data, filtered in linear space and stored in the _SRGB variant of the target format
+    Srgb,
+    // Non-color data, stored in the linear variant of the target format
+    Linear,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum MipPolicy {
+    None,
+    #[default]
+    Full,
+    Levels(usize),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Filter {
+    #[default]
+    Default,
+    Point,
+    Linear,
+    Cubic,
+    Box,
+    Triangle,
+}
+
+impl Filter {
+    #[must_use]
+    pub const fn flags(self) -> TEX_FILTER_FLAGS {
+        match self {
+            Self::Default => TEX_FILTER_FLAGS::TEX_FILTER_DEFAULT,
+            Self::Point => TEX_FILTER_FLAGS::TEX_FILTER_POINT,
+            Self::Linear => TEX_FILTER_FLAGS::TEX_FILTER_LINEAR,
+            Self::Cubic => TEX_FILTER_FLAGS::TEX_FILTER_CUBIC,
+            Self::Box => TEX_FILTER_FLAGS::TEX_FILTER_BOX,
+            Self::Triangle => TEX_FILTER_FLAGS::TEX_FILTER_TRIANGLE,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum HeightChannel {
+    #[default]
+    Red,
+    Green,
+    Blue,
+    Alpha,
+    Luminance,
+}
+
+// Generates a tangent space normal map from a height map before mips are built
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct NormalMapSettings {
+    pub amplitude: f32,
+    pub channel:   HeightChannel,
+    pub invert:    bool,
+    pub mirror:    bool,
+}
+
+impl Default for NormalMapSettings {
+    fn default() -> Self {
+        Self {
+            amplitude: 2.0,
+            channel:   HeightChannel::default(),
+            invert:    false,
+            mirror:    false,
+        }
+    }
+}
+
+impl NormalMapSettings {
+    #[must_use]
+    pub fn flags(&self) -> CNMAP_FLAGS {
+        let mut flags = match self.channel {
+            HeightChannel::Red => CNMAP_FLAGS::CNMAP_CHANNEL_RED,
+            HeightChannel::Green => CNMAP_FLAGS::CNMAP_CHANNEL_GREEN,
+            HeightChannel::Blue => CNMAP_FLAGS::CNMAP_CHANNEL_BLUE,
+            HeightChannel::Alpha => CNMAP_FLAGS::CNMAP_CHANNEL_ALPHA,
+            HeightChannel::Luminance => CNMAP_FLAGS::CNMAP_CHANNEL_LUMINANCE,
+        };
+        if self.invert {
+            flags |= CNMAP_FLAGS::CNMAP_INVERT_SIGN;
+        }
+        if self.mirror {
+            flags |= CNMAP_FLAGS::CNMAP_MIRROR;
+        }
+        flags
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TexturePreset {
+    #[cfg_attr(feature = "serde", serde(with = "directxtex_sys::serialize::format_name"))]
+    pub format:      DXGI_FORMAT,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub srgb:        SrgbMode,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mips:        MipPolicy,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub filter:      Filter,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub premultiply: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub max_size:    Option<usize>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub normal_map:  Option<NormalMapSettings>,
+}
+
+impl TexturePreset {
+    #[must_use]
+    pub const fn new(format: DXGI_FORMAT) -> Self {
+        Self {
+            format,
+            srgb: SrgbMode::Auto,
+            mips: MipPolicy::Full,
+            filter: Filter::Default,
+            premultiply: false,
+            max_size: None,
+            normal_map: None,
+        }
+    }
+
+    #[must_use]
+    pub const fn albedo() -> Self {
+        Self {
+            srgb: SrgbMode::Srgb,
+            ..Self::new(DXGI_FORMAT_BC7_UNORM)
+        }
+    }
+
+    #[must_use]
+    pub const fn normal() -> Self {
+        Self {
+            srgb: SrgbMode::Linear,
+            ..Self::new(DXGI_FORMAT_BC5_UNORM)
+        }
+    }
+
+    #[must_use]
+    pub const fn orm() -> Self {
+        Self {
+            srgb: SrgbMode::Linear,
+            ..Self::new(DXGI_FORMAT_BC7_UNORM)
+        }
+    }
+
+    #[must_use]
+    pub const fn hdr_sky() -> Self {
+        Self {
+            srgb: SrgbMode::Linear,
+            filter: Filter::Cubic,
+            ..Self::new(DXGI_FORMAT_BC6H_UF16)
+        }
+    }
+
+    #[must_use]
+    pub const fn ui() -> Self {
+        Self {
+            srgb: SrgbMode::Srgb,
+            mips: MipPolicy::None,
+            filter: Filter::Linear,
+            premultiply: true,
+            max_size: Some(4096),
+            ..Self::new(DXGI_FORMAT_BC7_UNORM)
+        }
+    }
+
+    #[must_use]
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            name if name.eq_ignore_ascii_case("albedo") => Some(Self::albedo()),
+            name if name.eq_ignore_ascii_case("normal") => Some(Self::normal()),
+            name if name.eq_ignore_ascii_case("orm") => Some(Self::orm()),
+            name if name.eq_ignore_ascii_case("hdr_sky") => Some(Self::hdr_sky()),
+            name if name.eq_ignore_ascii_case("ui") => Some(Self::ui()),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn target_format(&self) -> DXGI_FORMAT { self.tag(self.format) }
+
+    // `format` with the _SRGB tag `srgb` asks for, unchanged for Auto
+    fn tag(&self, format: DXGI_FORMAT) -> DXGI_FORMAT {
+        match self.srgb {
+            SrgbMode::Auto => format,
+            SrgbMode::Srgb => make_srgb(format),
+            SrgbMode::Linear => make_linear(format),
+        }
+    }
+
+    #[must_use]
+    pub fn filter_flags(&self) -> TEX_FILTER_FLAGS {
+        if self.srgb == SrgbMode::Srgb {
+            self.filter.flags() | TEX_FILTER_FLAGS::TEX_FILTER_SRGB
+        } else {
+            self.filter.flags()
+        }
+    }
+
+    pub fn apply(&self, image: ScratchImage) -> Result<ScratchImage> {
+        let mut image = image.maybe_decompress()?;
+
+        // DirectXTex decodes sources tagged _SRGB before filtering and encodes them on the way
+        // out, so the source is retagged to match the mode before anything touches it
+        let source = self.tag(image.format());
+        if source != image.format() {
+            image.override_format(source)?;
+        }
+
+        if let Some(max_size) = self.max_size {
+            let (width, height) = (image.width(), image.height());
+            if width > max_size || height > max_size {
+                let scale = max_size as f64 / width.max(height) as f64;
+                let width = ((width as f64 * scale) as usize).max(1);
+                let height = ((height as f64 * scale) as usize).max(1);
+                image = image.resize(width, height, self.filter_flags())?;
+            }
+        }
+
+        if let Some(normal_map) = &self.normal_map {
+            image = image.compute_normal_map(
+                normal_map.flags(),
+                normal_map.amplitude,
+                DXGI_FORMAT_R8G8B8A8_UNORM,
+            )?;
+        }
+
+        if self.premultiply && has_alpha(image.format()) {
+            image = image.premultiply_alpha(TEX_PMALPHA_FLAGS::default())?;
+        }
+
+        image = match self.mips {
+            MipPolicy::None => image,
+            MipPolicy::Full => image.generate_mipmaps(0, self.filter_flags())?,
+            MipPolicy::Levels(levels) => image.generate_mipmaps(levels, self.filter_flags())?,
+        };
+
+        image.convert_or_compress(self.target_format())
+    }
+}
@@ -1,21 +1,26 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use directxtex_sys::{
     self as sys,
     TexMetadata,
+    CNMAP_FLAGS,
     CP_FLAGS,
     DDS_FLAGS,
     DXGI_FORMAT,
     TEX_COMPRESS_FLAGS,
     TEX_FILTER_FLAGS,
+    TEX_FR_FLAGS,
     TEX_PMALPHA_FLAGS,
     TGA_FLAGS,
-    WIC_FLAGS,
 };
+#[cfg(windows)] use directxtex_sys::WIC_FLAGS;
 
 use crate::error::hresult;
+use crate::formats::ktx2::Supercompression;
 use crate::util::is_compressed;
-use crate::{error, formats, ops, Blob, CWide, Image, Result};
+use crate::options::{LoadOptions, SaveOptions};
+use crate::orientation::{self, Orientation};
+use crate::{error, file_set, formats, ops, Blob, CWide, FileType, Image, Result};
 
 #[derive(Debug)]
 pub struct ScratchImage(pub(crate) sys::ScratchImage);
@@ -24,6 +29,12 @@ impl Drop for ScratchImage {
     fn drop(&mut self) { unsafe { self.0.Release() }; }
 }
 
+// Safety: A ScratchImage exclusively owns its pixel memory and image table and every `&self`
+// method only reads from them. The one piece of shared state is the hwaccel D3D11 device, which
+// `compress` locks around every use.
+unsafe impl Send for ScratchImage {}
+unsafe impl Sync for ScratchImage {}
+
 impl ScratchImage {
     #[inline]
     #[must_use]
@@ -50,6 +61,23 @@ impl ScratchImage {
         crate::save(file_name, image, self.metadata())
     }
 
+    #[inline]
+    pub fn load_with(file_name: impl AsRef<Path>, options: &LoadOptions) -> Result<Self> {
+        crate::load_with(file_name, options)
+    }
+
+    pub fn save_with(&self, file_name: impl AsRef<Path>, options: &SaveOptions) -> Result<()> {
+        options.save(self, file_name.as_ref())
+    }
+
+    // Every mip, item and slice to its own file, see `file_set` for the pattern placeholders
+    pub fn save_all(&self, pattern: impl AsRef<Path>, file_type: FileType) -> Result<Vec<PathBuf>> {
+        file_set::save_all(self, pattern, file_type)
+    }
+
+    #[inline]
+    pub fn load_all(pattern: impl AsRef<Path>) -> Result<Self> { file_set::load_all(pattern) }
+
     #[inline]
     pub fn new(
         format: DXGI_FORMAT,
@@ -66,6 +94,16 @@ impl ScratchImage {
         }
     }
 
+    // Allocates a zero filled image with the given layout
+    #[inline]
+    pub fn initialize(metadata: &TexMetadata) -> Result<Self> {
+        let mut raw = sys::ScratchImage::default();
+        hresult(unsafe { raw.Initialize(metadata, CP_FLAGS::default()) })?;
+        // Safety: Initialize allocated m_size bytes at m_memory, which aren't initialized yet
+        unsafe { raw.m_memory.write_bytes(0, raw.m_size) };
+        Ok(Self(raw))
+    }
+
     #[inline]
     pub fn new_1d(
         format: DXGI_FORMAT,
@@ -173,6 +211,15 @@ impl ScratchImage {
         unsafe { Image::from_ptr(self.0.GetImage(mip, item, slice)) }
     }
 
+    #[inline]
+    #[must_use]
+    pub fn image_buffer_mut(&mut self, index: usize) -> Option<&mut [u8]> {
+        let image = self.images().get(index)?;
+        let (pixels, size) = (image.pixels_ptr(), image.slice_pitch());
+        // Safety: Every image points into the memory owned by this ScratchImage
+        Some(unsafe { std::slice::from_raw_parts_mut(pixels, size) })
+    }
+
     #[inline]
     #[must_use]
     pub fn image0(&self) -> &Image<'_> {
@@ -180,7 +227,7 @@ impl ScratchImage {
         unsafe { Image::from_ptr(self.0.GetImage(0, 0, 0)).unwrap_unchecked() }
     }
 
-    #[cfg(feature = "windows")]
+    #[cfg(all(windows, feature = "windows"))]
     pub fn compress_with_device(
         &self,
         device: &windows::Win32::Graphics::Direct3D11::ID3D11Device,
@@ -190,6 +237,7 @@ impl ScratchImage {
         ops::compress_with_device(device, self.images(), self.metadata(), to_format, flags)
     }
 
+    #[cfg(windows)]
     pub unsafe fn compress_with_device_ptr(
         &self,
         device: *mut sys::ID3D11Device,
@@ -268,11 +316,73 @@ impl ScratchImage {
         }
     }
 
+    #[inline]
+    pub fn flip_rotate(&self, flags: TEX_FR_FLAGS) -> Result<Self> {
+        ops::flip_rotate(self.images(), self.metadata(), flags)
+    }
+
+    #[inline]
+    pub fn flip_horizontal(&self) -> Result<Self> {
+        self.flip_rotate(TEX_FR_FLAGS::TEX_FR_FLIP_HORIZONTAL)
+    }
+
+    #[inline]
+    pub fn flip_vertical(&self) -> Result<Self> {
+        self.flip_rotate(TEX_FR_FLAGS::TEX_FR_FLIP_VERTICAL)
+    }
+
+    #[inline]
+    pub fn rotate90(&self) -> Result<Self> { self.flip_rotate(TEX_FR_FLAGS::TEX_FR_ROTATE90) }
+
+    #[inline]
+    pub fn rotate180(&self) -> Result<Self> { self.flip_rotate(TEX_FR_FLAGS::TEX_FR_ROTATE180) }
+
+    #[inline]
+    pub fn rotate270(&self) -> Result<Self> { self.flip_rotate(TEX_FR_FLAGS::TEX_FR_ROTATE270) }
+
+    #[inline]
+    pub fn normalize_orientation(&self, orientation: Orientation) -> Result<Self> {
+        orientation::normalize(self, orientation)
+    }
+
     #[inline]
     pub fn premultiply_alpha(&self, flags: TEX_PMALPHA_FLAGS) -> Result<Self> {
         ops::premultiply_alpha(self.images(), self.metadata(), flags)
     }
 
+    #[inline]
+    pub fn compute_normal_map(
+        &self,
+        flags: CNMAP_FLAGS,
+        amplitude: f32,
+        to_format: DXGI_FORMAT,
+    ) -> Result<Self> {
+        ops::compute_normal_map(self.images(), self.metadata(), flags, amplitude, to_format)
+    }
+
+    #[inline]
+    pub fn tonemap(&self, operator: ops::Operator) -> Result<Self> {
+        ops::tonemap(self.images(), self.metadata(), operator)
+    }
+
+    #[inline]
+    pub fn swizzle(&self, mask: &str) -> Result<Self> {
+        ops::swizzle(self.images(), self.metadata(), mask)
+    }
+
+    #[inline]
+    pub fn rotate_color(&self, rotation: ops::ColorRotation) -> Result<Self> {
+        ops::rotate_color(self.images(), self.metadata(), rotation)
+    }
+
+    #[inline]
+    pub fn diff(&self, other: &Self, mode: ops::DiffMode) -> Result<Self> {
+        ops::diff_image(self, other, mode)
+    }
+
+    #[inline]
+    pub fn statistics(&self) -> Result<ops::Stats> { ops::statistics(self.images(), self.metadata()) }
+
     #[inline]
     #[must_use]
     pub const fn images(&self) -> &[Image] {
@@ -316,6 +426,18 @@ impl ScratchImage {
         formats::dds::save_slice_to_memory(self.images(), self.metadata(), dds_flags)
     }
 
+    pub fn save_ktx2(
+        &self,
+        file_name: impl AsRef<Path>,
+        supercompression: Supercompression,
+    ) -> Result<()> {
+        formats::ktx2::save(file_name, self.images(), self.metadata(), supercompression)
+    }
+
+    pub fn save_ktx2_to_memory(&self, supercompression: Supercompression) -> Result<Blob> {
+        formats::ktx2::save_to_memory(self.images(), self.metadata(), supercompression)
+    }
+
     pub fn save_tga<'file_name, CWIDE>(
         &self,
         item: usize,
@@ -358,6 +480,7 @@ impl ScratchImage {
         formats::exr::save(file_name, image)
     }
 
+    #[cfg(windows)]
     pub fn save_wic<'file_name, CWIDE>(
         &self,
         item: usize,
@@ -373,6 +496,7 @@ impl ScratchImage {
         formats::wic::save(file_name, container, image, wic_flags)
     }
 
+    #[cfg(windows)]
     #[inline]
     pub fn save_wic_to_memory(
         &self,
@@ -0,0 +1,165 @@
+// Pixel comparisons and golden files for tests. Images are compared through `ops::diff_image`, so
+// formats may differ and block compressed images are decompressed first.
+
+use std::path::{Path, PathBuf};
+
+use crate::flags::DDS_FLAGS;
+use crate::ops::{self, DiffMode};
+use crate::{dds, Result, ScratchImage};
+
+// Set to anything but "0" to overwrite golden files with the actual results
+pub const BLESS_VAR: &str = "DIRECTXTEX_BLESS";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Texel {
+    pub subresource: usize,
+    pub x:           usize,
+    pub y:           usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Comparison {
+    // Mean squared error over every channel of every subresource, infinite with NaN differences
+    pub mse:     f64,
+    // Largest channel difference and where it is
+    pub max_abs: f32,
+    pub worst:   Texel,
+}
+
+impl Comparison {
+    #[inline]
+    #[must_use]
+    pub fn is_close(&self, max_mse: f64, max_abs: f32) -> bool {
+        self.mse <= max_mse && self.max_abs <= max_abs
+    }
+}
+
+// Errors when the images don't have the same dimensions, mips and array size
+pub fn compare(a: &ScratchImage, b: &ScratchImage) -> Result<Comparison> {
+    let diff = ops::diff_image(a, b, DiffMode::Absolute)?;
+
+    let (mut sum, mut count) = (0.0f64, 0usize);
+    let mut max_abs = 0.0f32;
+    let mut worst = Texel::default();
+    for (subresource, image) in diff.images().iter().enumerate() {
+        let row_size = image.width() * 16;
+        for (y, row) in image.buffer().chunks(image.row_pitch()).take(image.height()).enumerate() {
+            for (x, pixel) in row[..row_size].chunks_exact(16).enumerate() {
+                for bytes in pixel.chunks_exact(4) {
+                    let difference = f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                    let difference = if difference.is_nan() { f32::INFINITY } else { difference };
+                    sum += f64::from(difference) * f64::from(difference);
+                    count += 1;
+                    if difference > max_abs {
+                        max_abs = difference;
+                        worst = Texel { subresource, x, y };
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(Comparison {
+        mse: if count == 0 { 0.0 } else { sum / count as f64 },
+        max_abs,
+        worst,
+    })
+}
+
+#[track_caller]
+pub fn assert_images_close(
+    actual: &ScratchImage,
+    expected: &ScratchImage,
+    max_mse: f64,
+    max_abs: f32,
+) {
+    let comparison = match compare(actual, expected) {
+        Ok(comparison) => comparison,
+        Err(error) => panic!("images can't be compared: {error}"),
+    };
+    assert!(
+        comparison.is_close(max_mse, max_abs),
+        "images differ: mse {} (max {max_mse}), largest difference {} at {:?} (max {max_abs})",
+        comparison.mse,
+        comparison.max_abs,
+        comparison.worst,
+    );
+}
+
+// assert_images_close!(actual, expected, max_mse, max_abs) on two ScratchImages
+#[macro_export]
+macro_rules! assert_images_close {
+    ($actual:expr, $expected:expr, $max_mse:expr, $max_abs:expr $(,)?) => {
+        $crate::testing::assert_images_close(&$actual, &$expected, $max_mse, $max_abs)
+    };
+}
+
+fn blessing() -> bool { std::env::var_os(BLESS_VAR).is_some_and(|value| value != "0") }
+
+fn sibling(golden: &Path, suffix: &str) -> PathBuf {
+    let mut name = golden.file_stem().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    golden.with_file_name(name)
+}
+
+// Checks `actual` against the DDS at `golden`. While BLESS_VAR is set, missing and mismatching
+// golden files are recorded from `actual`. Otherwise a missing golden file panics, and so does a
+// mismatch after writing `<name>.actual.dds` and a `<name>.diff.hdr` heatmap of the first
+// subresource next to the golden file.
+#[track_caller]
+pub fn assert_golden(
+    actual: &ScratchImage,
+    golden: impl AsRef<Path>,
+    max_mse: f64,
+    max_abs: f32,
+) {
+    let golden = golden.as_ref();
+    let record = || {
+        if let Some(parent) = golden.parent() {
+            std::fs::create_dir_all(parent).expect("Failed to create the golden file directory");
+        }
+        actual.save_dds(golden, DDS_FLAGS::default()).expect("Failed to save the golden file");
+    };
+    if !golden.exists() {
+        if blessing() {
+            record();
+            return;
+        }
+        panic!("{} is missing, set {BLESS_VAR}=1 to record it", golden.display());
+    }
+
+    let expected = dds::load(golden, DDS_FLAGS::default()).expect("Failed to load the golden file");
+    let comparison = compare(actual, &expected);
+    if comparison.as_ref().is_ok_and(|comparison| comparison.is_close(max_mse, max_abs)) {
+        return;
+    }
+    if blessing() {
+        record();
+        return;
+    }
+
+    let actual_path = sibling(golden, ".actual.dds");
+    actual.save_dds(&*actual_path, DDS_FLAGS::default()).expect("Failed to save the actual image");
+    let diff_path = sibling(golden, ".diff.hdr");
+    if let Ok(diff) = ops::diff_image(actual, &expected, DiffMode::Scaled { scale: 4.0 }) {
+        // The heatmap is only there to help, failing to write it shouldn't hide the mismatch
+        let _ = diff.save_hdr(0, &*diff_path);
+    }
+    match comparison {
+        Ok(comparison) => panic!(
+            "{} doesn't match: mse {} (max {max_mse}), largest difference {} at {:?} (max \
+             {max_abs}), see {} and {}, or set {BLESS_VAR}=1 to accept it",
+            golden.display(),
+            comparison.mse,
+            comparison.max_abs,
+            comparison.worst,
+            actual_path.display(),
+            diff_path.display(),
+        ),
+        Err(error) => panic!(
+            "{} can't be compared ({error}), see {} or set {BLESS_VAR}=1 to accept it",
+            golden.display(),
+            actual_path.display(),
+        ),
+    }
+}
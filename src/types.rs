@@ -1,31 +1,176 @@
+use std::alloc::{handle_alloc_error, Layout};
+use std::io;
 use std::marker::PhantomData;
+use std::ops::Deref;
 
 use directxtex_sys as sys;
 
+use crate::error::hresult;
+use crate::Result;
+
+// `len` bytes of the blob are in use, the rest of its allocation is spare capacity for writes
 #[derive(Debug)]
-pub struct Blob(pub(crate) sys::Blob);
+pub struct Blob {
+    blob: sys::Blob,
+    len:  usize,
+}
+
 impl Drop for Blob {
-    fn drop(&mut self) { unsafe { self.0.Release() }; }
+    fn drop(&mut self) { unsafe { self.blob.Release() }; }
 }
 
+// Safety: A Blob exclusively owns its heap allocation and has no thread affinity
+unsafe impl Send for Blob {}
+unsafe impl Sync for Blob {}
+
 impl Blob {
+    // Zero filled, Blob::Initialize leaves the memory uninitialized
+    pub fn new(size: usize) -> Result<Self> {
+        let mut blob = Self::owned(sys::Blob::default());
+        blob.resize(size)?;
+        Ok(blob)
+    }
+
     #[inline]
     #[must_use]
-    pub const unsafe fn from_sys(blob: sys::Blob) -> Self { Self(blob) }
+    pub const unsafe fn from_sys(blob: sys::Blob) -> Self { Self::owned(blob) }
+
+    // A blob filled in by DirectXTex
+    #[inline]
+    pub(crate) const fn owned(blob: sys::Blob) -> Self {
+        Self {
+            len: blob.m_size,
+            blob,
+        }
+    }
 
     #[inline]
     #[must_use]
     pub const fn buffer(&self) -> &[u8] {
-        unsafe { std::slice::from_raw_parts(self.0.m_buffer.cast::<u8>(), self.0.m_size) }
+        if self.len == 0 {
+            return &[];
+        }
+        unsafe { std::slice::from_raw_parts(self.blob.m_buffer.cast::<u8>(), self.len) }
     }
 
     #[inline]
     #[must_use]
-    pub const fn len(&self) -> usize { self.0.m_size }
+    pub const fn len(&self) -> usize { self.len }
+
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool { self.len == 0 }
 
     #[inline]
     #[must_use]
-    pub const fn is_empty(&self) -> bool { self.0.m_size == 0 }
+    pub fn buffer_mut(&mut self) -> &mut [u8] {
+        if self.len == 0 {
+            return &mut [];
+        }
+        unsafe { std::slice::from_raw_parts_mut(self.blob.m_buffer.cast::<u8>(), self.len) }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn into_vec(self) -> Vec<u8> { self.buffer().to_vec() }
+
+    fn capacity(&self) -> usize {
+        if self.blob.m_buffer.is_null() { 0 } else { self.blob.m_size }
+    }
+
+    // Reallocates with Blob::Resize, which keeps the contents
+    fn reserve(&mut self, capacity: usize) -> Result<()> {
+        if capacity <= self.capacity() {
+            Ok(())
+        } else if self.blob.m_buffer.is_null() {
+            hresult(unsafe { self.blob.Initialize(capacity) })
+        } else {
+            hresult(unsafe { self.blob.Resize(capacity) })
+        }
+    }
+
+    // Grows or shrinks the blob, keeping the contents that fit. New bytes are zeroed.
+    pub fn resize(&mut self, size: usize) -> Result<()> {
+        self.reserve(size)?;
+        if size > self.len {
+            unsafe {
+                self.blob.m_buffer.cast::<u8>().add(self.len).write_bytes(0, size - self.len);
+            }
+        }
+        self.len = size;
+        Ok(())
+    }
+
+    // Shrinks the blob without reallocating
+    #[inline]
+    pub fn trim(&mut self, size: usize) -> Result<()> {
+        if size > self.len {
+            return Err(crate::error::invalid_arg());
+        }
+        hresult(unsafe { self.blob.Trim(size) })?;
+        self.len = size;
+        Ok(())
+    }
+}
+
+impl Deref for Blob {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target { self.buffer() }
+}
+
+impl AsRef<[u8]> for Blob {
+    #[inline]
+    fn as_ref(&self) -> &[u8] { self.buffer() }
+}
+
+impl From<Blob> for Vec<u8> {
+    #[inline]
+    fn from(blob: Blob) -> Self { blob.into_vec() }
+}
+
+// Running out of memory aborts, like cloning a Vec
+impl Clone for Blob {
+    fn clone(&self) -> Self {
+        let mut blob = Self::owned(sys::Blob::default());
+        if blob.reserve(self.len).is_err() {
+            handle_alloc_error(Layout::array::<u8>(self.len).unwrap_or(Layout::new::<u8>()));
+        }
+        blob.len = self.len;
+        blob.buffer_mut().copy_from_slice(self.buffer());
+        blob
+    }
+}
+
+// Appends to the end of the blob, doubling its allocation when it runs out
+impl io::Write for Blob {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let offset = self.len;
+        let end = offset
+            .checked_add(buf.len())
+            .ok_or_else(|| io::Error::from(io::ErrorKind::OutOfMemory))?;
+        if end > self.capacity() {
+            self.reserve(end.max(self.capacity().saturating_mul(2)).max(64))
+                .map_err(|_| io::Error::from(io::ErrorKind::OutOfMemory))?;
+        }
+        self.len = end;
+        self.buffer_mut()[offset..].copy_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+#[cfg(feature = "bytes")]
+impl From<Blob> for bytes::Bytes {
+    #[inline]
+    fn from(blob: Blob) -> Self { Self::from_owner(blob) }
 }
 
 #[repr(C)]
@@ -34,6 +179,10 @@ pub struct Image<'scratch> {
     marker: PhantomData<&'scratch crate::ScratchImage>,
 }
 
+// Safety: An Image is a read-only view into pixels owned by a ScratchImage (or caller buffer)
+unsafe impl Send for Image<'_> {}
+unsafe impl Sync for Image<'_> {}
+
 impl<'scratch> Image<'scratch> {
     #[inline]
     #[must_use]
@@ -72,6 +221,10 @@ impl<'scratch> Image<'scratch> {
         unsafe { std::slice::from_raw_parts(self.inner.pixels.cast::<u8>(), self.inner.slicePitch) }
     }
 
+    #[inline]
+    #[must_use]
+    pub(crate) const fn pixels_ptr(&self) -> *mut u8 { self.inner.pixels }
+
     #[inline]
     #[must_use]
     pub const fn width(&self) -> usize { self.inner.width }
@@ -95,6 +248,21 @@ impl<'scratch> Image<'scratch> {
     #[inline]
     #[must_use]
     pub const fn format(&self) -> sys::DXGI_FORMAT { self.inner.format }
+
+    // Metadata describing this image as a standalone 2D texture
+    #[must_use]
+    pub fn metadata(&self) -> sys::TexMetadata {
+        sys::TexMetadata {
+            width: self.width(),
+            height: self.height(),
+            depth: 1,
+            arraySize: 1,
+            mipLevels: 1,
+            format: self.format(),
+            dimension: sys::TEX_DIMENSION::TEX_DIMENSION_TEXTURE2D,
+            ..sys::TexMetadata::default()
+        }
+    }
 }
 
 #[allow(dead_code)]
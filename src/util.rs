@@ -6,6 +6,10 @@ use directxtex_sys::{self as sys, CP_FLAGS, DXGI_FORMAT};
 use crate::error::hresult;
 use crate::Result;
 
+mod layout;
+pub use layout::*;
+pub use sys::format::*;
+
 #[inline]
 #[must_use]
 pub fn is_compressed(format: DXGI_FORMAT) -> bool { unsafe { sys::IsCompressed(format) } }
@@ -0,0 +1,15 @@
+use crate::{TexMetadata, TEX_DIMENSION};
+
+// TEX_MISC_TEXTURECUBE in TexMetadata::miscFlags
+pub const TEX_MISC_TEXTURECUBE: u32 = 0x4;
+
+// Position of a subresource in `ScratchImage::images`, like ScratchImage::GetImage: items then
+// mips for 1D and 2D textures, mips then depth slices for volumes
+#[must_use]
+pub fn image_index(metadata: &TexMetadata, mip: usize, item: usize, slice: usize) -> usize {
+    if metadata.dimension == TEX_DIMENSION::TEX_DIMENSION_TEXTURE3D {
+        (0..mip).map(|level| (metadata.depth >> level).max(1)).sum::<usize>() + slice
+    } else {
+        item * metadata.mipLevels + mip
+    }
+}
@@ -5,6 +5,20 @@ use std::path::Path;
 
 use vcpkg::Library;
 
+// Optional DirectXTex auxiliary headers, enabled by the crate feature of the same name
+static AUX_HEADERS: &[(&str, &str, &str)] = &[
+    ("CARGO_FEATURE_PNG", "DIRECTXTEX_WRAPPER_PNG", "DirectXTexPNG.h"),
+    ("CARGO_FEATURE_JPEG", "DIRECTXTEX_WRAPPER_JPEG", "DirectXTexJPEG.h"),
+];
+
+fn aux_defines() -> Vec<&'static str> {
+    AUX_HEADERS
+        .iter()
+        .filter(|(feature, _, _)| env::var_os(feature).is_some())
+        .map(|(_, define, _)| *define)
+        .collect()
+}
+
 fn generate_bindings(library: &Library, bindings_file: &Path) {
     println!("cargo:print={:?}", library.include_paths);
     let bindings = bindgen::builder()
@@ -54,6 +68,7 @@ fn generate_bindings(library: &Library, bindings_file: &Path) {
         .generate_cstr(true)
         // .allowlist_type("DXGI_FORMAT")
         .clang_args(["-x", "c++"])
+        .clang_args(aux_defines().into_iter().map(|define| format!("-D{define}")))
         .clang_args(
             library
                 .include_paths
@@ -100,6 +115,15 @@ fn main() {
         );
     }
 
+    for (feature, _, header) in AUX_HEADERS {
+        if env::var_os(feature).is_some() && !check_for_header(&library, header) {
+            println!(
+                "cargo:warning={header} not found in include paths, ensure DirectXTex is \
+                 installed with the matching feature"
+            );
+        }
+    }
+
     #[cfg(windows)]
     println!("cargo:rustc-link-lib=ole32");
 
@@ -116,6 +140,10 @@ fn main() {
         }
     }
 
+    for define in aux_defines() {
+        cc.define(define, None);
+    }
+
     cc.cpp(true)
         .includes(library.include_paths)
         .file("wrapper/DirectXTexWrapper.cpp")
@@ -0,0 +1,200 @@
+// DXGI_FORMAT names and sizes, shared by the serde impls here and the directxtex crate. Builds of
+// directxtex without DirectXTex include this file by path, so it only needs `crate::DXGI_FORMAT`.
+
+use crate::DXGI_FORMAT;
+
+#[cfg(feature = "windows")]
+#[inline]
+#[must_use]
+pub const fn format_from_raw(raw: u32) -> DXGI_FORMAT { DXGI_FORMAT(raw) }
+
+#[cfg(not(feature = "windows"))]
+#[inline]
+#[must_use]
+pub const fn format_from_raw(raw: u32) -> DXGI_FORMAT { raw }
+
+#[cfg(feature = "windows")]
+#[inline]
+#[must_use]
+pub const fn format_to_raw(format: DXGI_FORMAT) -> u32 { format.0 }
+
+#[cfg(not(feature = "windows"))]
+#[inline]
+#[must_use]
+pub const fn format_to_raw(format: DXGI_FORMAT) -> u32 { format }
+
+// The DXGI_FORMAT_ constants, and a table of them with their names and the bits per pixel, which
+// match BitsPerPixel. Without the prefix the names are the ones texconv uses.
+macro_rules! formats {
+    ($(($value:literal, $name:ident, $bits:literal),)*) => {
+        $(pub const $name: DXGI_FORMAT = format_from_raw($value);)*
+
+        static FORMATS: &[(u32, &str, usize)] = &[$(($value, stringify!($name), $bits),)*];
+    };
+}
+
+const PREFIX: &str = "DXGI_FORMAT_";
+
+formats! {
+    (0, DXGI_FORMAT_UNKNOWN, 0),
+    (1, DXGI_FORMAT_R32G32B32A32_TYPELESS, 128),
+    (2, DXGI_FORMAT_R32G32B32A32_FLOAT, 128),
+    (3, DXGI_FORMAT_R32G32B32A32_UINT, 128),
+    (4, DXGI_FORMAT_R32G32B32A32_SINT, 128),
+    (5, DXGI_FORMAT_R32G32B32_TYPELESS, 96),
+    (6, DXGI_FORMAT_R32G32B32_FLOAT, 96),
+    (7, DXGI_FORMAT_R32G32B32_UINT, 96),
+    (8, DXGI_FORMAT_R32G32B32_SINT, 96),
+    (9, DXGI_FORMAT_R16G16B16A16_TYPELESS, 64),
+    (10, DXGI_FORMAT_R16G16B16A16_FLOAT, 64),
+    (11, DXGI_FORMAT_R16G16B16A16_UNORM, 64),
+    (12, DXGI_FORMAT_R16G16B16A16_UINT, 64),
+    (13, DXGI_FORMAT_R16G16B16A16_SNORM, 64),
+    (14, DXGI_FORMAT_R16G16B16A16_SINT, 64),
+    (15, DXGI_FORMAT_R32G32_TYPELESS, 64),
+    (16, DXGI_FORMAT_R32G32_FLOAT, 64),
+    (17, DXGI_FORMAT_R32G32_UINT, 64),
+    (18, DXGI_FORMAT_R32G32_SINT, 64),
+    (19, DXGI_FORMAT_R32G8X24_TYPELESS, 64),
+    (20, DXGI_FORMAT_D32_FLOAT_S8X24_UINT, 64),
+    (21, DXGI_FORMAT_R32_FLOAT_X8X24_TYPELESS, 64),
+    (22, DXGI_FORMAT_X32_TYPELESS_G8X24_UINT, 64),
+    (23, DXGI_FORMAT_R10G10B10A2_TYPELESS, 32),
+    (24, DXGI_FORMAT_R10G10B10A2_UNORM, 32),
+    (25, DXGI_FORMAT_R10G10B10A2_UINT, 32),
+    (26, DXGI_FORMAT_R11G11B10_FLOAT, 32),
+    (27, DXGI_FORMAT_R8G8B8A8_TYPELESS, 32),
+    (28, DXGI_FORMAT_R8G8B8A8_UNORM, 32),
+    (29, DXGI_FORMAT_R8G8B8A8_UNORM_SRGB, 32),
+    (30, DXGI_FORMAT_R8G8B8A8_UINT, 32),
+    (31, DXGI_FORMAT_R8G8B8A8_SNORM, 32),
+    (32, DXGI_FORMAT_R8G8B8A8_SINT, 32),
+    (33, DXGI_FORMAT_R16G16_TYPELESS, 32),
+    (34, DXGI_FORMAT_R16G16_FLOAT, 32),
+    (35, DXGI_FORMAT_R16G16_UNORM, 32),
+    (36, DXGI_FORMAT_R16G16_UINT, 32),
+    (37, DXGI_FORMAT_R16G16_SNORM, 32),
+    (38, DXGI_FORMAT_R16G16_SINT, 32),
+    (39, DXGI_FORMAT_R32_TYPELESS, 32),
+    (40, DXGI_FORMAT_D32_FLOAT, 32),
+    (41, DXGI_FORMAT_R32_FLOAT, 32),
+    (42, DXGI_FORMAT_R32_UINT, 32),
+    (43, DXGI_FORMAT_R32_SINT, 32),
+    (44, DXGI_FORMAT_R24G8_TYPELESS, 32),
+    (45, DXGI_FORMAT_D24_UNORM_S8_UINT, 32),
+    (46, DXGI_FORMAT_R24_UNORM_X8_TYPELESS, 32),
+    (47, DXGI_FORMAT_X24_TYPELESS_G8_UINT, 32),
+    (48, DXGI_FORMAT_R8G8_TYPELESS, 16),
+    (49, DXGI_FORMAT_R8G8_UNORM, 16),
+    (50, DXGI_FORMAT_R8G8_UINT, 16),
+    (51, DXGI_FORMAT_R8G8_SNORM, 16),
+    (52, DXGI_FORMAT_R8G8_SINT, 16),
+    (53, DXGI_FORMAT_R16_TYPELESS, 16),
+    (54, DXGI_FORMAT_R16_FLOAT, 16),
+    (55, DXGI_FORMAT_D16_UNORM, 16),
+    (56, DXGI_FORMAT_R16_UNORM, 16),
+    (57, DXGI_FORMAT_R16_UINT, 16),
+    (58, DXGI_FORMAT_R16_SNORM, 16),
+    (59, DXGI_FORMAT_R16_SINT, 16),
+    (60, DXGI_FORMAT_R8_TYPELESS, 8),
+    (61, DXGI_FORMAT_R8_UNORM, 8),
+    (62, DXGI_FORMAT_R8_UINT, 8),
+    (63, DXGI_FORMAT_R8_SNORM, 8),
+    (64, DXGI_FORMAT_R8_SINT, 8),
+    (65, DXGI_FORMAT_A8_UNORM, 8),
+    (66, DXGI_FORMAT_R1_UNORM, 1),
+    (67, DXGI_FORMAT_R9G9B9E5_SHAREDEXP, 32),
+    (68, DXGI_FORMAT_R8G8_B8G8_UNORM, 32),
+    (69, DXGI_FORMAT_G8R8_G8B8_UNORM, 32),
+    (70, DXGI_FORMAT_BC1_TYPELESS, 4),
+    (71, DXGI_FORMAT_BC1_UNORM, 4),
+    (72, DXGI_FORMAT_BC1_UNORM_SRGB, 4),
+    (73, DXGI_FORMAT_BC2_TYPELESS, 8),
+    (74, DXGI_FORMAT_BC2_UNORM, 8),
+    (75, DXGI_FORMAT_BC2_UNORM_SRGB, 8),
+    (76, DXGI_FORMAT_BC3_TYPELESS, 8),
+    (77, DXGI_FORMAT_BC3_UNORM, 8),
+    (78, DXGI_FORMAT_BC3_UNORM_SRGB, 8),
+    (79, DXGI_FORMAT_BC4_TYPELESS, 4),
+    (80, DXGI_FORMAT_BC4_UNORM, 4),
+    (81, DXGI_FORMAT_BC4_SNORM, 4),
+    (82, DXGI_FORMAT_BC5_TYPELESS, 8),
+    (83, DXGI_FORMAT_BC5_UNORM, 8),
+    (84, DXGI_FORMAT_BC5_SNORM, 8),
+    (85, DXGI_FORMAT_B5G6R5_UNORM, 16),
+    (86, DXGI_FORMAT_B5G5R5A1_UNORM, 16),
+    (87, DXGI_FORMAT_B8G8R8A8_UNORM, 32),
+    (88, DXGI_FORMAT_B8G8R8X8_UNORM, 32),
+    (89, DXGI_FORMAT_R10G10B10_XR_BIAS_A2_UNORM, 32),
+    (90, DXGI_FORMAT_B8G8R8A8_TYPELESS, 32),
+    (91, DXGI_FORMAT_B8G8R8A8_UNORM_SRGB, 32),
+    (92, DXGI_FORMAT_B8G8R8X8_TYPELESS, 32),
+    (93, DXGI_FORMAT_B8G8R8X8_UNORM_SRGB, 32),
+    (94, DXGI_FORMAT_BC6H_TYPELESS, 8),
+    (95, DXGI_FORMAT_BC6H_UF16, 8),
+    (96, DXGI_FORMAT_BC6H_SF16, 8),
+    (97, DXGI_FORMAT_BC7_TYPELESS, 8),
+    (98, DXGI_FORMAT_BC7_UNORM, 8),
+    (99, DXGI_FORMAT_BC7_UNORM_SRGB, 8),
+    (100, DXGI_FORMAT_AYUV, 32),
+    (101, DXGI_FORMAT_Y410, 32),
+    (102, DXGI_FORMAT_Y416, 64),
+    (103, DXGI_FORMAT_NV12, 12),
+    (104, DXGI_FORMAT_P010, 24),
+    (105, DXGI_FORMAT_P016, 24),
+    (106, DXGI_FORMAT_420_OPAQUE, 12),
+    (107, DXGI_FORMAT_YUY2, 32),
+    (108, DXGI_FORMAT_Y210, 64),
+    (109, DXGI_FORMAT_Y216, 64),
+    (110, DXGI_FORMAT_NV11, 12),
+    (111, DXGI_FORMAT_AI44, 8),
+    (112, DXGI_FORMAT_IA44, 8),
+    (113, DXGI_FORMAT_P8, 8),
+    (114, DXGI_FORMAT_A8P8, 16),
+    (115, DXGI_FORMAT_B4G4R4A4_UNORM, 16),
+    (130, DXGI_FORMAT_P208, 16),
+    (131, DXGI_FORMAT_V208, 16),
+    (132, DXGI_FORMAT_V408, 24),
+    (189, DXGI_FORMAT_SAMPLER_FEEDBACK_MIN_MIP_OPAQUE, 0),
+    (190, DXGI_FORMAT_SAMPLER_FEEDBACK_MIP_REGION_USED_OPAQUE, 0),
+    (191, DXGI_FORMAT_A4B4G4R4_UNORM, 16),
+}
+
+#[must_use]
+pub fn format_name(format: DXGI_FORMAT) -> Option<&'static str> {
+    let raw = format_to_raw(format);
+    FORMATS
+        .iter()
+        .find(|(value, ..)| *value == raw)
+        .map(|(_, name, _)| &name[PREFIX.len()..])
+}
+
+// Case insensitive, with or without the DXGI_FORMAT_ prefix
+#[must_use]
+pub fn format_from_name(name: &str) -> Option<DXGI_FORMAT> {
+    let name = name.trim();
+    let name = name
+        .get(..PREFIX.len())
+        .filter(|prefix| prefix.eq_ignore_ascii_case(PREFIX))
+        .map_or(name, |_| &name[PREFIX.len()..]);
+
+    FORMATS
+        .iter()
+        .find(|(_, known, _)| known[PREFIX.len()..].eq_ignore_ascii_case(name))
+        .map(|(value, ..)| format_from_raw(*value))
+}
+
+#[inline]
+pub fn format_names() -> impl Iterator<Item = &'static str> {
+    FORMATS.iter().map(|(_, name, _)| &name[PREFIX.len()..])
+}
+
+// Same as `bits_per_pixel` but answered from the table, so it works without DirectXTex
+#[must_use]
+pub fn format_bits_per_pixel(format: DXGI_FORMAT) -> usize {
+    let raw = format_to_raw(format);
+    FORMATS
+        .iter()
+        .find(|(value, ..)| *value == raw)
+        .map_or(0, |(.., bits)| *bits)
+}
@@ -15,7 +15,9 @@
 #![deny(clippy::std_instead_of_core)]
 #![deny(clippy::std_instead_of_alloc)]
 
+pub mod format;
 mod impls;
+#[cfg(feature = "serde")] pub mod serialize;
 mod types;
 
 pub use types::{
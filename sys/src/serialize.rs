@@ -0,0 +1,153 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    TexMetadata,
+    CMSE_FLAGS,
+    CNMAP_FLAGS,
+    CP_FLAGS,
+    CREATETEX_FLAGS,
+    DDS_FLAGS,
+    DXGI_FORMAT,
+    TEX_ALPHA_MODE,
+    TEX_COMPRESS_FLAGS,
+    TEX_DIMENSION,
+    TEX_FILTER_FLAGS,
+    TEX_FR_FLAGS,
+    TEX_PMALPHA_FLAGS,
+    TGA_FLAGS,
+    WIC_FLAGS,
+};
+
+// Flags and newtype enums are stored as their raw bits so unknown or combined values round-trip
+macro_rules! serde_bits {
+    ($($ty:ident),+ $(,)?) => {
+        $(
+            impl Serialize for $ty {
+                #[inline]
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    self.0.serialize(serializer)
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $ty {
+                #[inline]
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    Deserialize::deserialize(deserializer).map(Self)
+                }
+            }
+        )+
+    };
+}
+
+serde_bits!(
+    CMSE_FLAGS,
+    CNMAP_FLAGS,
+    CP_FLAGS,
+    CREATETEX_FLAGS,
+    DDS_FLAGS,
+    TEX_ALPHA_MODE,
+    TEX_COMPRESS_FLAGS,
+    TEX_DIMENSION,
+    TEX_FILTER_FLAGS,
+    TEX_FR_FLAGS,
+    TEX_PMALPHA_FLAGS,
+    TGA_FLAGS,
+    WIC_FLAGS,
+);
+
+// `#[serde(with = "directxtex_sys::serialize::format_name")]` stores a DXGI_FORMAT by its name
+// like `BC7_UNORM_SRGB`, see `format::format_from_name` for what is accepted
+pub mod format_name {
+    use core::fmt;
+
+    use serde::{de, ser, Deserializer, Serializer};
+
+    use crate::format::{format_from_name, format_name, format_to_raw};
+    use crate::DXGI_FORMAT;
+
+    pub fn serialize<S: Serializer>(format: &DXGI_FORMAT, serializer: S) -> Result<S::Ok, S::Error> {
+        let name = format_name(*format).ok_or_else(|| {
+            ser::Error::custom(format_args!("unknown DXGI format {}", format_to_raw(*format)))
+        })?;
+        serializer.serialize_str(name)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DXGI_FORMAT, D::Error> {
+        deserializer.deserialize_str(Visitor)
+    }
+
+    struct Visitor;
+
+    impl de::Visitor<'_> for Visitor {
+        type Value = DXGI_FORMAT;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a DXGI format name")
+        }
+
+        fn visit_str<E: de::Error>(self, name: &str) -> Result<DXGI_FORMAT, E> {
+            format_from_name(name).ok_or_else(|| {
+                E::custom(format_args!(
+                    "unknown DXGI format `{name}`, expected a name like `BC7_UNORM_SRGB`, \
+                     `BC5_UNORM` or `R8G8B8A8_UNORM` (the DXGI_FORMAT_ prefix is optional)"
+                ))
+            })
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "TexMetadata")]
+struct TexMetadataRepr {
+    width:       usize,
+    height:      usize,
+    #[serde(default = "one")]
+    depth:       usize,
+    #[serde(default = "one")]
+    array_size:  usize,
+    #[serde(default = "one")]
+    mip_levels:  usize,
+    #[serde(default)]
+    misc_flags:  u32,
+    #[serde(default)]
+    misc_flags2: u32,
+    #[serde(with = "format_name")]
+    format:      DXGI_FORMAT,
+    dimension:   TEX_DIMENSION,
+}
+
+const fn one() -> usize { 1 }
+
+impl Serialize for TexMetadata {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        TexMetadataRepr {
+            width:       self.width,
+            height:      self.height,
+            depth:       self.depth,
+            array_size:  self.arraySize,
+            mip_levels:  self.mipLevels,
+            misc_flags:  self.miscFlags,
+            misc_flags2: self.miscFlags2,
+            format:      self.format,
+            dimension:   self.dimension,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TexMetadata {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = TexMetadataRepr::deserialize(deserializer)?;
+        Ok(Self {
+            width:      repr.width,
+            height:     repr.height,
+            depth:      repr.depth,
+            arraySize:  repr.array_size,
+            mipLevels:  repr.mip_levels,
+            miscFlags:  repr.misc_flags,
+            miscFlags2: repr.misc_flags2,
+            format:     repr.format,
+            dimension:  repr.dimension,
+        })
+    }
+}
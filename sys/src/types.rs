@@ -36,16 +36,158 @@ pub struct IWICMetadataQueryReader([u8; 0]);
 #[repr(C)]
 pub struct IPropertyBag2([u8; 0]);
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
 #[repr(C)]
-pub struct XMVECTORF32(f32, f32, f32, f32);
+pub struct XMVECTORF32(pub f32, pub f32, pub f32, pub f32);
+
+impl XMVECTORF32 {
+    pub const ZERO: Self = Self(0.0, 0.0, 0.0, 0.0);
+    pub const ONE: Self = Self(1.0, 1.0, 1.0, 1.0);
+
+    #[inline]
+    #[must_use]
+    pub const fn new(x: f32, y: f32, z: f32, w: f32) -> Self { Self(x, y, z, w) }
+
+    #[inline]
+    #[must_use]
+    pub const fn splat(value: f32) -> Self { Self(value, value, value, value) }
+
+    #[inline]
+    #[must_use]
+    pub const fn x(self) -> f32 { self.0 }
+
+    #[inline]
+    #[must_use]
+    pub const fn y(self) -> f32 { self.1 }
+
+    #[inline]
+    #[must_use]
+    pub const fn z(self) -> f32 { self.2 }
+
+    #[inline]
+    #[must_use]
+    pub const fn w(self) -> f32 { self.3 }
+
+    #[inline]
+    pub fn set_x(&mut self, x: f32) { self.0 = x; }
+
+    #[inline]
+    pub fn set_y(&mut self, y: f32) { self.1 = y; }
+
+    #[inline]
+    pub fn set_z(&mut self, z: f32) { self.2 = z; }
+
+    #[inline]
+    pub fn set_w(&mut self, w: f32) { self.3 = w; }
+
+    #[inline]
+    #[must_use]
+    pub const fn to_array(self) -> [f32; 4] { [self.0, self.1, self.2, self.3] }
+
+    #[inline]
+    #[must_use]
+    pub fn map(self, mut func: impl FnMut(f32) -> f32) -> Self {
+        Self(func(self.0), func(self.1), func(self.2), func(self.3))
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn zip(self, other: Self, mut func: impl FnMut(f32, f32) -> f32) -> Self {
+        Self(
+            func(self.0, other.0),
+            func(self.1, other.1),
+            func(self.2, other.2),
+            func(self.3, other.3),
+        )
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn min(self, other: Self) -> Self { self.zip(other, f32::min) }
+
+    #[inline]
+    #[must_use]
+    pub fn max(self, other: Self) -> Self { self.zip(other, f32::max) }
+
+    #[inline]
+    #[must_use]
+    pub fn clamp(self, min: Self, max: Self) -> Self { self.max(min).min(max) }
+
+    #[inline]
+    #[must_use]
+    pub fn saturate(self) -> Self { self.clamp(Self::ZERO, Self::ONE) }
+
+    #[inline]
+    #[must_use]
+    pub fn lerp(self, other: Self, t: f32) -> Self { self + (other - self) * t }
+
+    #[inline]
+    #[must_use]
+    pub fn dot3(self, other: Self) -> f32 { self.0 * other.0 + self.1 * other.1 + self.2 * other.2 }
+
+    #[inline]
+    #[must_use]
+    pub fn dot4(self, other: Self) -> f32 { self.dot3(other) + self.3 * other.3 }
+}
+
+impl From<[f32; 4]> for XMVECTORF32 {
+    fn from([x, y, z, w]: [f32; 4]) -> Self { Self(x, y, z, w) }
+}
+
+impl From<XMVECTORF32> for [f32; 4] {
+    fn from(vector: XMVECTORF32) -> Self { vector.to_array() }
+}
+
+macro_rules! vector_ops {
+    ($($op:ident $func:ident $assign_op:ident $assign_func:ident),*) => {$(
+        impl core::ops::$op for XMVECTORF32 {
+            type Output = Self;
+
+            #[inline]
+            fn $func(self, other: Self) -> Self { self.zip(other, core::ops::$op::$func) }
+        }
+
+        impl core::ops::$op<f32> for XMVECTORF32 {
+            type Output = Self;
+
+            #[inline]
+            fn $func(self, other: f32) -> Self {
+                self.map(|value| core::ops::$op::$func(value, other))
+            }
+        }
+
+        impl core::ops::$assign_op for XMVECTORF32 {
+            #[inline]
+            fn $assign_func(&mut self, other: Self) { *self = core::ops::$op::$func(*self, other); }
+        }
+
+        impl core::ops::$assign_op<f32> for XMVECTORF32 {
+            #[inline]
+            fn $assign_func(&mut self, other: f32) { *self = core::ops::$op::$func(*self, other); }
+        }
+    )*};
+}
+
+vector_ops!(
+    Add add AddAssign add_assign,
+    Sub sub SubAssign sub_assign,
+    Mul mul MulAssign mul_assign,
+    Div div DivAssign div_assign
+);
+
+impl core::ops::Neg for XMVECTORF32 {
+    type Output = Self;
+
+    #[inline]
+    fn neg(self) -> Self { self.map(|value| -value) }
+}
 
 #[derive(Copy, Clone, Debug)]
 #[repr(C)]
-pub struct XMVECTORI32(i32, i32, i32, i32);
+pub struct XMVECTORI32(pub i32, pub i32, pub i32, pub i32);
 
 #[derive(Copy, Clone, Debug)]
 #[repr(C)]
-pub struct XMVECTORU32(u32, u32, u32, u32);
+pub struct XMVECTORU32(pub u32, pub u32, pub u32, pub u32);
 
 pub type XMVECTOR = XMVECTORF32;
@@ -35,6 +35,13 @@ impl DropBlob {
         assert_ne!(len, 0, "{ctx} produced an empty blob");
         eprintln!("{ctx} produced a {len} byte blob");
     }
+
+    pub fn as_slice(&self) -> &[u8] {
+        if self.0.m_buffer.is_null() {
+            return &[];
+        }
+        unsafe { std::slice::from_raw_parts(self.0.m_buffer.cast::<u8>(), self.0.m_size) }
+    }
 }
 
 impl std::ops::Deref for DropBlob {
@@ -61,6 +68,28 @@ impl DropScratch {
         assert_ne!(len, 0, "{ctx} produced an empty ScratchImage");
         eprintln!("{ctx} produced a {len} byte ScratchImage");
     }
+
+    // For lossless round trips: the layout and every byte of the pixels have to match. Alpha
+    // modes in miscFlags2 aren't compared, not every format stores them.
+    pub fn assert_same_pixels(&self, expected: &ScratchImage, ctx: &str) {
+        let layout = |image: &ScratchImage| {
+            let metadata = &image.m_metadata;
+            (
+                metadata.width,
+                metadata.height,
+                metadata.depth,
+                metadata.arraySize,
+                metadata.mipLevels,
+                metadata.format,
+                image.m_nimages,
+            )
+        };
+        assert_eq!(layout(&self.0), layout(expected), "{ctx} changed the image layout");
+        let pixels = |image: &ScratchImage| unsafe {
+            std::slice::from_raw_parts(image.m_memory, image.m_size)
+        };
+        assert!(pixels(&self.0) == pixels(expected), "{ctx} changed the pixels");
+    }
 }
 
 impl std::ops::Deref for DropScratch {
@@ -104,6 +104,22 @@ fn test_dds() {
     );
     blob.assert_has_data("SaveToDDSMemory1");
 
+    let mut reloaded = DropScratch::default();
+    assert_eq!(
+        unsafe {
+            LoadFromDDSMemory(
+                blob.as_slice().as_ptr(),
+                blob.as_slice().len(),
+                DDS_FLAGS::DDS_FLAGS_ALLOW_LARGE_FILES,
+                std::ptr::null_mut(),
+                &mut *reloaded,
+            )
+        },
+        S_OK
+    );
+    reloaded.assert_same_pixels(&image_file, "SaveToDDSMemory1");
+    image_mem.assert_same_pixels(&image_file, "LoadFromDDSMemory");
+
     let image = unsafe { image_mem.GetImage(0, 0, 0) };
     assert!(!image.is_null());
 
@@ -77,6 +77,22 @@ fn test_tga() {
     );
     blob.assert_has_data("SaveToTGAMemory");
 
+    let mut reloaded = DropScratch::default();
+    assert_eq!(
+        unsafe {
+            LoadFromTGAMemory(
+                blob.as_slice().as_ptr(),
+                blob.as_slice().len(),
+                TGA_FLAGS::default(),
+                std::ptr::null_mut(),
+                &mut *reloaded,
+            )
+        },
+        S_OK
+    );
+    reloaded.assert_same_pixels(&image_file, "SaveToTGAMemory");
+    image_mem.assert_same_pixels(&image_file, "LoadFromTGAMemory");
+
     let image = unsafe { image_mem.GetImage(0, 0, 0) };
     assert!(!image.is_null());
 
@@ -128,6 +128,25 @@ fn test_wic() {
     );
     blob.assert_has_data("SaveToWICMemory");
 
+    // PNG is lossless
+    let mut reloaded = DropScratch::default();
+    assert_eq!(
+        unsafe {
+            LoadFromWICMemory(
+                blob.as_slice().as_ptr(),
+                blob.as_slice().len(),
+                WIC_FLAGS::default(),
+                std::ptr::null_mut(),
+                &mut *reloaded,
+                None,
+                std::ptr::null_mut(),
+            )
+        },
+        S_OK
+    );
+    reloaded.assert_same_pixels(&image_file, "SaveToWICMemory");
+    image_mem.assert_same_pixels(&image_file, "LoadFromWICMemory");
+
     let mut blob = DropBlob::default();
     assert_eq!(
         unsafe {
@@ -1,6 +1,14 @@
 #include <DirectXTex.h>
 #include <DirectXTexEXR.h>
 
+#ifdef DIRECTXTEX_WRAPPER_PNG
+#include <DirectXTexPNG.h>
+#endif
+
+#ifdef DIRECTXTEX_WRAPPER_JPEG
+#include <DirectXTexJPEG.h>
+#endif
+
 using namespace DirectX;
 
 typedef void __cdecl GetMQR(IWICMetadataQueryReader *mqr, void *callback_fn);
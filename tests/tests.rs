@@ -2,8 +2,9 @@ use std::env;
 use std::path::PathBuf;
 
 use directxtex::flags::{DDS_FLAGS, TEX_FILTER_FLAGS, TGA_FLAGS};
+use directxtex::testing::assert_golden;
 #[cfg(feature = "windows")] use directxtex::util::initialize_com;
-use directxtex::{dds, tga, ScratchImage};
+use directxtex::{assert_images_close, dds, tga, ScratchImage};
 #[cfg(feature = "windows")] use directxtex::{flags::WIC_FLAGS, wic, wic::WIC_CODEC_JPEG};
 
 #[cfg(feature = "windows")]
@@ -40,6 +41,26 @@ fn out_file(file_name: &str) -> PathBuf {
     PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(file_name)
 }
 
+// RGBA32F fixtures, most of the ops tests build and read back raw float pixels
+fn float_image(pixels: &[[f32; 4]], width: usize, height: usize) -> ScratchImage {
+    let data: Vec<u8> = pixels.iter().flatten().flat_map(|c| c.to_ne_bytes()).collect();
+    ScratchImage::new_2d(RGBAF32, width, height, 1, 1, &data).expect("Failed to create image")
+}
+
+fn float_texel(bytes: &[u8]) -> [f32; 4] {
+    std::array::from_fn(|c| f32::from_ne_bytes(bytes[c * 4..c * 4 + 4].try_into().unwrap()))
+}
+
+fn float_pixels(image: &ScratchImage) -> Vec<[f32; 4]> {
+    image.buffer().chunks_exact(16).map(float_texel).collect()
+}
+
+fn float_pixel(image: &ScratchImage, mip: usize, item: usize, x: usize, y: usize) -> [f32; 4] {
+    let image = image.image(mip, item, 0).unwrap();
+    let offset = y * image.row_pitch() + x * 16;
+    float_texel(&image.buffer()[offset..offset + 16])
+}
+
 #[test]
 fn test_tga() {
     #[cfg(feature = "windows")]
@@ -48,20 +69,28 @@ fn test_tga() {
     let tga = tga::load(in_file("test.tga"), TGA_FLAGS::default()).expect("Failed to load TGA");
     tga.save_tga(0, out_file("test.tga"), TGA_FLAGS::default())
         .expect("Failed to save TGA");
+
+    let reloaded = tga::load(out_file("test.tga"), TGA_FLAGS::default()).expect("Failed to reload");
+    assert_images_close!(reloaded, tga, 0.0, 0.0);
 }
 
 #[test]
 fn test_simple() {
-    let compressed = dds::load(in_file("test_complex.dds"), DDS_FLAGS::default())
+    let source = dds::load(in_file("test_complex.dds"), DDS_FLAGS::default())
         .unwrap()
         .maybe_decompress()
-        .unwrap()
+        .unwrap();
+    let compressed = source
         .compress(COMPRESSED, directxtex::flags::TEX_COMPRESS_FLAGS::default())
         .unwrap();
     assert!(compressed.is_compressed());
     compressed
         .save_dds(out_file("test.dds"), DDS_FLAGS::default())
         .expect("Failed to save as DDS");
+
+    let reloaded = dds::load(out_file("test.dds"), DDS_FLAGS::default()).expect("Failed to reload");
+    assert_images_close!(reloaded, compressed, 0.0, 0.0);
+    assert_images_close!(compressed, source, 5e-3, 1.0);
 }
 
 #[cfg(feature = "windows")]
@@ -79,6 +108,10 @@ fn test_wic() {
         WIC_FLAGS::default(),
     )
     .expect("Failed to save as JPEG via WIC");
+
+    let jpeg = wic::load(out_file("test.jpg"), WIC_FLAGS::default()).expect("Failed to reload");
+    // JPEG has no alpha
+    assert_images_close!(jpeg, png.swizzle("rgb1").unwrap(), 1e-3, 0.5);
 }
 
 #[test]
@@ -86,21 +119,25 @@ fn test_hdr() {
     #[cfg(feature = "windows")]
     initialize_com().expect("Failed to initialize COM");
 
-    ScratchImage::load(in_file("test.hdr"))
+    let rgba8 = ScratchImage::load(in_file("test.hdr"))
         .expect("Failed to load HDR")
         .convert(RGBA8, TEX_FILTER_FLAGS::default())
-        .expect("Failed to convert from RGBAF32")
-        .save(out_file("test.tga"), 0)
-        .unwrap();
+        .expect("Failed to convert from RGBAF32");
+    rgba8.save(out_file("test_hdr.tga"), 0).unwrap();
+    let reloaded = ScratchImage::load(out_file("test_hdr.tga")).expect("Failed to reload TGA");
+    assert_images_close!(reloaded, rgba8, 0.0, 0.0);
 
-    ScratchImage::load(in_file("test.dds"))
+    let float = ScratchImage::load(in_file("test.dds"))
         .expect("Failed to load DDS")
         .maybe_decompress()
         .unwrap()
         .convert(RGBAF32, TEX_FILTER_FLAGS::default())
-        .expect("Failed to convert to RGBAF32")
-        .save(out_file("test.hdr"), 0)
-        .unwrap();
+        .expect("Failed to convert to RGBAF32");
+    float.save(out_file("test.hdr"), 0).unwrap();
+    // RGBE keeps 8 bits of mantissa and no alpha
+    let reloaded = ScratchImage::load(out_file("test.hdr")).expect("Failed to reload HDR");
+    let opaque = float.swizzle("rgb1").unwrap();
+    assert_images_close!(reloaded, opaque, 1e-4, 1.0 / 128.0);
 }
 
 #[test]
@@ -135,6 +172,12 @@ fn test_step() {
     image
         .save_dds(out_file("test_complex.dds"), DDS_FLAGS::default())
         .expect("Failed to save as DDS");
+
+    let reloaded =
+        dds::load(out_file("test_complex.dds"), DDS_FLAGS::default()).expect("Failed to reload");
+    assert_images_close!(reloaded, image, 0.0, 0.0);
+    let source = dds::load(in_file("test_complex.dds"), DDS_FLAGS::default()).unwrap();
+    assert_images_close!(image, source, 5e-3, 1.0);
 }
 
 #[test]
@@ -169,8 +212,20 @@ fn test_into_format() {
         .expect("Failed to save as JPEG");
 
     image
-        .save_tga(0, out_file("test.tga"), TGA_FLAGS::default())
+        .save_tga(0, out_file("test_into_format.tga"), TGA_FLAGS::default())
         .expect("Failed to save as TGA");
+    let reloaded = tga::load(out_file("test_into_format.tga"), TGA_FLAGS::default())
+        .expect("Failed to reload TGA");
+    let top = ScratchImage::new_2d(
+        image.format(),
+        image.width(),
+        image.height(),
+        1,
+        1,
+        image.image0().buffer(),
+    )
+    .unwrap();
+    assert_images_close!(reloaded, top, 0.0, 0.0);
 }
 
 #[test]
@@ -205,6 +260,10 @@ fn test_buffers() {
 
     assert_eq!(out1.buffer(), out2.buffer());
     assert_eq!(out1.buffer(), out3.buffer());
+
+    let blank = ScratchImage::initialize(image.metadata()).expect("Failed to initialize image");
+    assert_eq!(blank.buffer().len(), image.buffer().len());
+    assert!(blank.buffer().iter().all(|&byte| byte == 0));
 }
 
 #[test]
@@ -224,3 +283,1377 @@ fn test_evaluate() {
 
     assert_eq!(count, image.height());
 }
+
+#[test]
+fn test_blob() {
+    use std::io::Write;
+
+    let mut blob = directxtex::Blob::new(4).expect("Failed to create blob");
+    assert_eq!(blob.len(), 4);
+
+    blob.write_all(&[1, 2, 3, 4]).expect("Failed to write to blob");
+    assert_eq!(&blob[4..], &[1, 2, 3, 4]);
+
+    let clone = blob.clone();
+    assert_eq!(clone.as_ref(), blob.buffer());
+
+    blob.trim(4).expect("Failed to trim blob");
+    assert_eq!(blob.len(), 4);
+    assert_eq!(clone.into_vec().len(), 8);
+    assert!(blob.trim(5).is_err());
+
+    // New blobs and grown blobs are zeroed
+    assert_eq!(blob.buffer(), &[0; 4]);
+    blob.resize(6).expect("Failed to resize blob");
+    assert_eq!(blob.buffer(), &[0; 6]);
+
+    let mut written = directxtex::Blob::new(0).expect("Failed to create blob");
+    assert!(written.buffer().is_empty() && written.clone().is_empty());
+    for i in 0..1000u32 {
+        written.write_all(&i.to_le_bytes()).expect("Failed to write to blob");
+    }
+    assert_eq!(written.len(), 4000);
+    assert_eq!(&written[3996..], &999u32.to_le_bytes());
+}
+
+#[test]
+fn test_threads() {
+    #[cfg(feature = "windows")]
+    initialize_com().expect("Failed to initialize COM");
+
+    let image = dds::load(in_file("test.dds"), DDS_FLAGS::default()).expect("Failed to load DDS");
+    let blob = image
+        .save_dds_to_memory(DDS_FLAGS::default())
+        .expect("Failed to save DDS");
+
+    let shared = std::sync::Arc::new(image.clone());
+    let reader = {
+        let shared = std::sync::Arc::clone(&shared);
+        std::thread::spawn(move || shared.buffer().len())
+    };
+
+    // Both are dropped on the spawned thread
+    let len = std::thread::spawn(move || {
+        let decompressed = image.maybe_decompress().expect("Failed to decompress");
+        assert!(!decompressed.buffer().is_empty());
+        blob.len()
+    })
+    .join()
+    .unwrap();
+
+    assert_ne!(len, 0);
+    assert_eq!(reader.join().unwrap(), shared.buffer_size());
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_batch() {
+    let paths = [in_file("test.dds"), in_file("test_complex.dds"), in_file("test.tga")];
+
+    let results =
+        directxtex::batch::process(&paths, |image| image.into_format(RGBA8));
+    assert_eq!(results.len(), paths.len());
+    for result in results {
+        assert_eq!(result.expect("Batch step failed").format(), RGBA8);
+    }
+}
+
+#[test]
+fn test_pipeline() {
+    use directxtex::flags::TEX_COMPRESS_FLAGS;
+    use directxtex::pipeline::Pipeline;
+    use directxtex::FileType;
+
+    #[cfg(feature = "windows")]
+    initialize_com().expect("Failed to initialize COM");
+
+    let error = Pipeline::builder()
+        .compress(COMPRESSED, TEX_COMPRESS_FLAGS::default())
+        .convert(RGBA8, TEX_FILTER_FLAGS::default())
+        .build()
+        .unwrap_err();
+    assert!(error.to_string().contains("step 1 (Convert)"), "{error}");
+    assert!(Pipeline::builder()
+        .generate_mips(0, TEX_FILTER_FLAGS::default())
+        .save(out_file("test_pipeline.tga"), FileType::Tga)
+        .build()
+        .is_err());
+
+    let pipeline = Pipeline::builder()
+        .decompress()
+        .convert(RGBA8, TEX_FILTER_FLAGS::default())
+        .generate_mips(0, TEX_FILTER_FLAGS::default())
+        .compress(COMPRESSED, TEX_COMPRESS_FLAGS::default())
+        .save(out_file("test_pipeline.dds"), FileType::Dds)
+        .build()
+        .expect("Failed to build pipeline");
+
+    let image =
+        dds::load(in_file("test_complex.dds"), DDS_FLAGS::default()).expect("Failed to load DDS");
+    let output = pipeline.run(image).expect("Pipeline failed");
+
+    assert_eq!(output.timings.len(), pipeline.steps().len());
+    assert_eq!(output.image.format(), COMPRESSED);
+    assert!(output.image.mip_levels() > 1);
+}
+
+#[test]
+fn test_preset() {
+    use directxtex::preset::{MipPolicy, SrgbMode};
+    use directxtex::util::{DXGI_FORMAT_R8G8B8A8_UNORM, DXGI_FORMAT_R8G8B8A8_UNORM_SRGB};
+    use directxtex::TexturePreset;
+
+    #[cfg(feature = "windows")]
+    initialize_com().expect("Failed to initialize COM");
+
+    let preset = TexturePreset {
+        mips: MipPolicy::Levels(2),
+        max_size: Some(64),
+        ..TexturePreset::new(COMPRESSED)
+    };
+
+    let image = dds::load(in_file("test.dds"), DDS_FLAGS::default()).expect("Failed to load DDS");
+    let image = preset.apply(image).expect("Failed to apply preset");
+    assert_eq!(image.format(), COMPRESSED);
+    assert_eq!(image.mip_levels(), 2);
+    assert!(image.width() <= 64 && image.height() <= 64);
+
+    // Non-color data tagged _SRGB is filtered as stored, not gamma decoded first
+    let data = [0, 0, 0, 255, 255, 255, 255, 255];
+    let tagged = ScratchImage::new_2d(DXGI_FORMAT_R8G8B8A8_UNORM_SRGB, 2, 1, 1, 1, &data).unwrap();
+    let linear = TexturePreset {
+        srgb: SrgbMode::Linear,
+        ..TexturePreset::new(DXGI_FORMAT_R8G8B8A8_UNORM)
+    };
+    let image = linear.apply(tagged).expect("Failed to apply preset");
+    assert_eq!(image.format(), DXGI_FORMAT_R8G8B8A8_UNORM);
+    let red = image.image(1, 0, 0).unwrap().buffer()[0];
+    assert!((127..=128).contains(&red), "{red}");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_preset_serde() {
+    use directxtex::preset::{Filter, SrgbMode};
+    use directxtex::TexturePreset;
+
+    let preset: TexturePreset = toml::from_str(
+        r#"
+        format = "bc7_unorm"
+        srgb = "srgb"
+        filter = "cubic"
+        max_size = 2048
+        "#,
+    )
+    .expect("Failed to parse preset");
+    assert_eq!(preset.srgb, SrgbMode::Srgb);
+    assert_eq!(preset.filter, Filter::Cubic);
+    assert_eq!(preset.max_size, Some(2048));
+
+    let albedo = TexturePreset::albedo();
+    let round_trip: TexturePreset =
+        toml::from_str(&toml::to_string(&albedo).expect("Failed to serialize preset"))
+            .expect("Failed to parse serialized preset");
+    assert_eq!(round_trip, albedo);
+
+    let error = toml::from_str::<TexturePreset>(r#"format = "BC9_UNORM""#).unwrap_err();
+    assert!(error.to_string().contains("BC9_UNORM"));
+
+    // TexMetadata names its format the same way
+    let image = dds::load(in_file("test.dds"), DDS_FLAGS::default()).expect("Failed to load DDS");
+    let serialized = toml::to_string(image.metadata()).expect("Failed to serialize metadata");
+    let name = directxtex::util::format_name(image.format()).unwrap();
+    assert!(serialized.contains(&format!("format = \"{name}\"")), "{serialized}");
+    let round_trip: directxtex::TexMetadata =
+        toml::from_str(&serialized).expect("Failed to parse serialized metadata");
+    assert_eq!(round_trip, *image.metadata());
+}
+
+#[test]
+fn test_flip_rotate() {
+    use directxtex::Orientation;
+
+    #[cfg(feature = "windows")]
+    initialize_com().expect("Failed to initialize COM");
+
+    let image = dds::load(in_file("test.dds"), DDS_FLAGS::default())
+        .expect("Failed to load DDS")
+        .into_format(RGBA8)
+        .expect("Failed to convert to RGBA8")
+        .resize(64, 32, TEX_FILTER_FLAGS::default())
+        .expect("Failed to resize");
+
+    let rotated = image.rotate90().expect("Failed to rotate");
+    assert_eq!((rotated.width(), rotated.height()), (32, 64));
+    let upright = image
+        .normalize_orientation(Orientation::Rotate90)
+        .expect("Failed to normalize orientation");
+    assert_eq!(rotated.buffer(), upright.buffer());
+
+    for orientation in (1..=8).filter_map(Orientation::from_exif) {
+        let flipped = image
+            .flip_rotate(orientation.fix_flags())
+            .expect("FlipRotate failed");
+        let normalized = image
+            .normalize_orientation(orientation)
+            .expect("Failed to normalize orientation");
+        assert_eq!(flipped.buffer(), normalized.buffer(), "{orientation:?}");
+    }
+
+    let twice = image
+        .flip_vertical()
+        .and_then(|image| image.flip_vertical())
+        .expect("Failed to flip");
+    assert_eq!(twice.buffer(), image.buffer());
+
+    assert_eq!(Orientation::from_tga_descriptor(0x20), Orientation::Normal);
+    assert_eq!(Orientation::from_tga_descriptor(0x00), Orientation::FlipVertical);
+}
+
+#[cfg(all(feature = "png", not(windows)))]
+#[test]
+fn test_png() {
+    let png = directxtex::load(in_file("test.png")).expect("Failed to load PNG");
+    let blob = directxtex::png::save_to_memory(png.image0()).expect("Failed to save PNG");
+
+    let metadata = directxtex::png::metadata_from_memory(&blob).expect("Failed to read metadata");
+    assert_eq!((metadata.width, metadata.height), (png.width(), png.height()));
+
+    png.save(out_file("test_png.png"), 0)
+        .expect("Failed to save PNG");
+    let reloaded = directxtex::png::load_from_memory(&blob).expect("Failed to reload PNG");
+    assert_eq!(reloaded.buffer(), png.buffer());
+}
+
+#[cfg(all(feature = "jpeg", not(windows)))]
+#[test]
+fn test_jpeg() {
+    let image = dds::load(in_file("test.dds"), DDS_FLAGS::default())
+        .expect("Failed to load DDS")
+        .into_format(RGBA8)
+        .expect("Failed to convert to RGBA8");
+
+    image.save(out_file("test_jpeg.jpg"), 0).expect("Failed to save JPEG");
+    let jpeg = directxtex::load(out_file("test_jpeg.jpg")).expect("Failed to load JPEG");
+    assert_eq!((jpeg.width(), jpeg.height()), (image.width(), image.height()));
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn test_dynamic_image() {
+    use image::DynamicImage;
+
+    #[cfg(feature = "windows")]
+    initialize_com().expect("Failed to initialize COM");
+
+    let image = dds::load(in_file("test.dds"), DDS_FLAGS::default()).expect("Failed to load DDS");
+    let dynamic = DynamicImage::try_from(image.image0()).expect("Failed to convert to image");
+    assert_eq!(dynamic.width() as usize, image.width());
+    assert_eq!(dynamic.height() as usize, image.height());
+
+    let scratch = ScratchImage::try_from(&dynamic).expect("Failed to convert from image");
+    let round_trip = DynamicImage::try_from(&scratch).expect("Failed to convert back to image");
+    assert_eq!(round_trip.as_bytes(), dynamic.as_bytes());
+
+    let hdr = ScratchImage::load(in_file("test.hdr")).expect("Failed to load HDR");
+    let dynamic = DynamicImage::try_from(&hdr).expect("Failed to convert HDR to image");
+    assert!(matches!(dynamic, DynamicImage::ImageRgba32F(_)));
+
+    // Red and green isn't luma and alpha, both sides expand to RGBA
+    let rg = ScratchImage::new_2d(directxtex::util::format_from_raw(49), 1, 1, 1, 1, &[10, 20])
+        .expect("Failed to create image");
+    let dynamic = DynamicImage::try_from(&rg).expect("Failed to convert to image");
+    assert_eq!(dynamic.as_bytes(), [10, 20, 0, 255]);
+    let luma_alpha = image::GrayAlphaImage::from_raw(1, 1, vec![10, 20]).unwrap();
+    let luma_alpha = DynamicImage::ImageLumaA8(luma_alpha);
+    let scratch = ScratchImage::try_from(&luma_alpha).expect("Failed to convert from image");
+    assert_eq!(scratch.buffer()[..4], [10, 10, 10, 20]);
+
+    // Luma is sRGB with or without alpha
+    let luma = DynamicImage::ImageLuma8(image::GrayImage::from_raw(1, 1, vec![10]).unwrap());
+    let scratch = ScratchImage::try_from(&luma).expect("Failed to convert from image");
+    assert_eq!(scratch.format(), directxtex::util::DXGI_FORMAT_R8G8B8A8_UNORM_SRGB);
+    assert_eq!(scratch.buffer()[..4], [10, 10, 10, 255]);
+}
+
+#[test]
+#[cfg(feature = "pure-rust-dds")]
+fn test_dds_header() {
+    use directxtex::dds::header;
+
+    #[cfg(feature = "windows")]
+    initialize_com().expect("Failed to initialize COM");
+
+    for name in ["test.dds", "test_1d.dds", "test_complex.dds"] {
+        let buffer = std::fs::read(in_file(name)).expect("Failed to read DDS");
+        let expected = dds::metadata_from_memory(&buffer, DDS_FLAGS::default())
+            .expect("Failed to read metadata");
+
+        let (metadata, offset) = header::parse(&buffer).expect("Failed to parse header");
+        assert_eq!(metadata, expected, "{name}");
+        assert!(
+            offset == header::HEADER_SIZE
+                || offset == header::HEADER_SIZE + header::DX10_HEADER_SIZE
+        );
+        assert_eq!(header::metadata(in_file(name)).expect("Failed to parse header"), expected);
+
+        // DirectXTex only needs the headers for metadata
+        let encoded = header::encode(&expected).expect("Failed to encode header");
+        let reread = dds::metadata_from_memory(&encoded, DDS_FLAGS::default())
+            .expect("Failed to read metadata");
+        assert_eq!(reread, expected, "{name}");
+        let reparsed = header::metadata_from_memory(&encoded).expect("Failed to parse header");
+        assert_eq!(reparsed, expected, "{name}");
+    }
+
+    // Headers written by DirectXTex, legacy where the format allows it
+    let image = dds::load(in_file("test.dds"), DDS_FLAGS::default()).expect("Failed to load DDS");
+    let compressed = image
+        .compress(COMPRESSED, directxtex::flags::TEX_COMPRESS_FLAGS::default())
+        .expect("Failed to compress");
+    for image in [&image, &compressed] {
+        let blob = image
+            .save_dds_to_memory(DDS_FLAGS::default())
+            .expect("Failed to save DDS");
+        assert_eq!(
+            header::metadata_from_memory(blob.buffer()).expect("Failed to parse header"),
+            *image.metadata()
+        );
+    }
+
+    assert!(header::metadata_from_memory(b"DDS ").is_err());
+    assert!(header::metadata_from_memory(&[0; header::HEADER_SIZE]).is_err());
+}
+
+#[test]
+fn test_ktx2() {
+    use directxtex::ktx2::{self, Supercompression};
+
+    #[cfg(feature = "windows")]
+    initialize_com().expect("Failed to initialize COM");
+
+    let image = dds::load(in_file("test.dds"), DDS_FLAGS::default())
+        .expect("Failed to load DDS")
+        .generate_mipmaps(0, TEX_FILTER_FLAGS::default())
+        .expect("Failed to generate mipmaps");
+    let complex =
+        dds::load(in_file("test_complex.dds"), DDS_FLAGS::default()).expect("Failed to load DDS");
+
+    for image in [&image, &complex] {
+        let buffer = image
+            .save_ktx2_to_memory(Supercompression::None)
+            .expect("Failed to save KTX2");
+        assert_eq!(buffer[..12], ktx2::IDENTIFIER);
+        assert_eq!(
+            u32::from_le_bytes(buffer[12..16].try_into().unwrap()),
+            ktx2::vk_format(image.format()).unwrap()
+        );
+        assert_eq!(ktx2::metadata_from_memory(&buffer).unwrap(), *image.metadata());
+
+        let loaded = ktx2::load_from_memory(&buffer).expect("Failed to load KTX2");
+        assert_eq!(loaded.metadata(), image.metadata());
+        assert_eq!(loaded.buffer(), image.buffer());
+    }
+
+    image
+        .save_ktx2(out_file("test.ktx2"), Supercompression::None)
+        .expect("Failed to save KTX2");
+    let loaded = directxtex::load(out_file("test.ktx2")).expect("Failed to load KTX2");
+    assert_eq!(loaded.buffer(), image.buffer());
+
+    // Premultiplied alpha is read from the data format descriptor by both metadata variants
+    let premultiplied = ScratchImage::initialize(&directxtex::TexMetadata {
+        miscFlags2: 2,
+        ..*image.metadata()
+    })
+    .expect("Failed to initialize image");
+    premultiplied
+        .save_ktx2(out_file("test_premultiplied.ktx2"), Supercompression::None)
+        .expect("Failed to save KTX2");
+    let buffer = std::fs::read(out_file("test_premultiplied.ktx2")).unwrap();
+    let metadata = ktx2::metadata(out_file("test_premultiplied.ktx2")).unwrap();
+    assert_eq!(metadata, ktx2::metadata_from_memory(&buffer).unwrap());
+    assert_eq!(metadata, *premultiplied.metadata());
+
+    // A level index pointing past the end is rejected before the pixels are allocated
+    assert!(ktx2::load_from_memory(&buffer[..buffer.len() - 1]).is_err());
+
+    // More levels than the full mip chain of the base level
+    let mut levels = buffer.clone();
+    let full = image.mip_levels() as u32;
+    levels[40..44].copy_from_slice(&(full + 1).to_le_bytes());
+    assert!(ktx2::metadata_from_memory(&levels).is_err());
+    levels[40..44].copy_from_slice(&64u32.to_le_bytes());
+    assert!(ktx2::load_from_memory(&levels).is_err());
+    assert!(ktx2::metadata(out_file("missing.ktx2")).is_err());
+
+    #[cfg(feature = "zstd")]
+    {
+        let compressed = complex
+            .save_ktx2_to_memory(Supercompression::Zstd { level: 3 })
+            .expect("Failed to save KTX2");
+        let loaded = ktx2::load_from_memory(&compressed).expect("Failed to load KTX2");
+        assert_eq!(loaded.buffer(), complex.buffer());
+    }
+
+    // Formats without a VkFormat equivalent
+    let unsupported = image
+        .convert(directxtex::util::format_from_raw(24), TEX_FILTER_FLAGS::default())
+        .expect("Failed to convert");
+    assert!(unsupported.save_ktx2_to_memory(Supercompression::None).is_err());
+}
+
+#[test]
+fn test_ktx() {
+    use directxtex::ktx;
+    use directxtex::util::format_from_raw;
+
+    fn ktx1(gl: [u32; 5], width: u32, height: u32, levels: &[&[u8]]) -> Vec<u8> {
+        let mut buffer = ktx::IDENTIFIER.to_vec();
+        let header = [0x0403_0201, gl[0], gl[1], gl[2], gl[3], gl[4], width, height, 0, 0, 1];
+        for value in header.into_iter().chain([levels.len() as u32, 0]) {
+            buffer.extend_from_slice(&value.to_le_bytes());
+        }
+        for level in levels {
+            buffer.extend_from_slice(&(level.len() as u32).to_le_bytes());
+            buffer.extend_from_slice(level);
+        }
+        buffer
+    }
+
+    #[cfg(feature = "windows")]
+    initialize_com().expect("Failed to initialize COM");
+
+    // RGBA8 rows are already 4 byte aligned, so KTX1 levels are DirectXTex's images
+    let image = dds::load(in_file("test.dds"), DDS_FLAGS::default())
+        .expect("Failed to load DDS")
+        .convert(format_from_raw(28), TEX_FILTER_FLAGS::default())
+        .expect("Failed to convert")
+        .generate_mipmaps(0, TEX_FILTER_FLAGS::default())
+        .expect("Failed to generate mipmaps");
+    let levels: Vec<&[u8]> = image.images().iter().map(|image| image.buffer()).collect();
+    let buffer = ktx1(
+        [0x1401, 1, 0x1908, 0x8058, 0x1908],
+        image.metadata().width as u32,
+        image.metadata().height as u32,
+        &levels,
+    );
+
+    let metadata = ktx::metadata_from_memory(&buffer).expect("Failed to read KTX metadata");
+    assert_eq!(metadata.version, 1);
+    assert_eq!(metadata.mip_levels, image.metadata().mipLevels);
+    assert_eq!(metadata.to_metadata(), *image.metadata());
+
+    let loaded = ktx::load_from_memory(&buffer).expect("Failed to load KTX");
+    assert_eq!(loaded.metadata(), image.metadata());
+    assert_eq!(loaded.buffer(), image.buffer());
+
+    std::fs::write(out_file("test.ktx"), &buffer).expect("Failed to write KTX");
+    assert_eq!(directxtex::metadata(out_file("test.ktx")).unwrap(), *image.metadata());
+    let loaded = directxtex::load(out_file("test.ktx")).expect("Failed to load KTX");
+    assert_eq!(loaded.buffer(), image.buffer());
+
+    // ETC2 and ASTC payloads still report their dimensions
+    let etc2 = ktx1([0, 1, 0, 0x9274, 0x1907], 8, 4, &[&[0; 16], &[0; 8], &[0; 8], &[0; 8]]);
+    let metadata = ktx::metadata_from_memory(&etc2).expect("Failed to read KTX metadata");
+    assert_eq!((metadata.width, metadata.height, metadata.mip_levels), (8, 4, 4));
+    assert_eq!(metadata.format, None);
+    assert_eq!(metadata.format_name(), Some("COMPRESSED_RGB8_ETC2"));
+    let error = ktx::load_from_memory(&etc2).unwrap_err();
+    assert_eq!(
+        error.message().to_string(),
+        "unsupported glInternalFormat 0x9274 (COMPRESSED_RGB8_ETC2)"
+    );
+
+    // Unsized GL_RGBA takes its layout from glType
+    let float = ktx1([0x1406, 4, 0x1908, 0x1908, 0x1908], 1, 1, &[&[0; 16]]);
+    let metadata = ktx::metadata_from_memory(&float).expect("Failed to read KTX metadata");
+    assert_eq!(metadata.format, Some(format_from_raw(2)));
+    assert_eq!(ktx::load_from_memory(&float).expect("Failed to load KTX").buffer(), [0; 16]);
+    let packed = ktx1([0x8033, 2, 0x1908, 0x1908, 0x1908], 1, 1, &[&[0; 4]]);
+    assert_eq!(ktx::metadata_from_memory(&packed).unwrap().format, None);
+    assert_eq!(ktx::gl_format_name(0x93d7), Some("COMPRESSED_SRGB8_ALPHA8_ASTC_8x8_KHR"));
+    assert_eq!(ktx::vk_format_name(158), Some("ASTC_4x4_SRGB_BLOCK"));
+
+    // Headers claiming more than the file holds, or more mips than the base level has
+    let huge = ktx1([0x1401, 1, 0x1908, 0x8058, 0x1908], 1 << 16, 1 << 16, &[&[0; 16]]);
+    assert!(ktx::load_from_memory(&huge).is_err());
+    let deep = ktx1([0x1401, 1, 0x1908, 0x8058, 0x1908], 1, 1, &[&[0; 4]; 2]);
+    assert!(ktx::metadata_from_memory(&deep).is_err());
+    assert!(ktx::load_from_memory(&deep).is_err());
+
+    // KTX2 headers are inspected too
+    let buffer = image
+        .save_ktx2_to_memory(directxtex::ktx2::Supercompression::None)
+        .expect("Failed to save KTX2");
+    let metadata = ktx::metadata_from_memory(&buffer).expect("Failed to read KTX metadata");
+    assert_eq!(metadata.version, 2);
+    assert_eq!(metadata.to_metadata(), *image.metadata());
+
+    assert!(ktx::load_from_memory(&buffer[..32]).is_err());
+}
+
+#[test]
+fn test_tonemap() {
+    use directxtex::ops::Operator;
+    use directxtex::util::format_from_raw;
+
+    #[cfg(feature = "windows")]
+    initialize_com().expect("Failed to initialize COM");
+
+    let values = [0.0f32, 0.18, 1.0, 4.0, 100.0, -1.0, f32::NAN];
+    let alphas = [1.0f32, 0.5, 0.0, 2.0, -1.0, 1.0, 1.0];
+    let pixels: Vec<[f32; 4]> =
+        values.iter().zip(alphas).map(|(&value, alpha)| [value, value, value, alpha]).collect();
+    let image = float_image(&pixels, values.len(), 1);
+
+    for operator in [
+        Operator::Reinhard,
+        Operator::Aces,
+        Operator::Exposure {
+            exposure: 1.5,
+            gamma:    2.2,
+        },
+        Operator::Hable,
+    ] {
+        let mapped = image.tonemap(operator).expect("Failed to tonemap");
+        assert_eq!(mapped.format(), format_from_raw(29));
+
+        let mut previous = 0;
+        for (index, pixel) in mapped.buffer().chunks_exact(4).enumerate() {
+            let expected = (operator.map(values[index]) * 255.0).round() as i32;
+            assert!((i32::from(pixel[0]) - expected).abs() <= 1, "{operator:?} {index}");
+            assert_eq!(pixel[0], pixel[2]);
+            assert_eq!(pixel[3], (alphas[index].clamp(0.0, 1.0) * 255.0).round() as u8);
+            // Every operator is monotonic, black stays black
+            if index < 5 {
+                assert!(pixel[0] >= previous);
+                previous = pixel[0];
+            } else {
+                assert_eq!(pixel[0], 0);
+            }
+        }
+    }
+
+    for gamma in [0.0, -2.2, f32::NAN] {
+        let exposure = Operator::Exposure {
+            exposure: 1.0,
+            gamma,
+        };
+        assert!(image.tonemap(exposure).is_err(), "{gamma}");
+    }
+
+    let sky = directxtex::hdr::load(in_file("test.hdr")).expect("Failed to load HDR");
+    let mapped = sky.tonemap(Operator::Aces).expect("Failed to tonemap");
+    assert_eq!((mapped.width(), mapped.height()), (sky.width(), sky.height()));
+    mapped
+        .save_tga(0, out_file("test_tonemap.tga"), TGA_FLAGS::default())
+        .expect("Failed to save TGA");
+}
+
+#[test]
+fn test_equirect_cube() {
+    use directxtex::ops::{self, Sampling};
+
+    #[cfg(feature = "windows")]
+    initialize_com().expect("Failed to initialize COM");
+
+    // Red sky, blue ground
+    let (width, height) = (32, 16);
+    let sky: Vec<[f32; 4]> = (0..width * height)
+        .map(|i| if i / width < height / 2 { [4.0, 0.0, 0.0, 1.0] } else { [0.0, 0.0, 0.5, 1.0] })
+        .collect();
+    let equirect = float_image(&sky, width, height);
+
+    for sampling in [Sampling::Bilinear, Sampling::Bicubic] {
+        let cube = ops::equirect_to_cube(&equirect, 8, sampling).expect("Failed to project");
+        let metadata = cube.metadata();
+        assert_eq!((metadata.width, metadata.height, metadata.arraySize), (8, 8, 6));
+        assert_eq!(metadata.miscFlags & 0x4, 0x4);
+        assert_eq!(metadata.format, equirect.format());
+
+        // Faces +Y and -Y
+        for (x, y) in [(0, 0), (4, 4), (7, 3)] {
+            let up = float_pixel(&cube, 0, 2, x, y);
+            let down = float_pixel(&cube, 0, 3, x, y);
+            assert!((up[0] - 4.0).abs() < 1e-4 && up[2].abs() < 1e-4, "{up:?}");
+            assert!((down[2] - 0.5).abs() < 1e-4 && down[0].abs() < 1e-4, "{down:?}");
+        }
+
+        let back = ops::cube_to_equirect(&cube, width, height, sampling).expect("Failed to unwrap");
+        assert_eq!((back.width(), back.height()), (width, height));
+        for ((x, y), expected) in [((5, 1), [4.0, 0.0, 0.0, 1.0]), ((20, 14), [0.0, 0.0, 0.5, 1.0])] {
+            let actual = float_pixel(&back, 0, 0, x, y);
+            assert!(actual.iter().zip(expected).all(|(a, e)| (a - e).abs() < 1e-4), "{actual:?}");
+        }
+    }
+
+    let sky = directxtex::hdr::load(in_file("test.hdr")).expect("Failed to load HDR");
+    let cube = ops::equirect_to_cube(&sky, 32, Sampling::Bicubic).expect("Failed to project");
+    assert_eq!(cube.format(), sky.format());
+    cube.save_dds(out_file("test_cube.dds"), DDS_FLAGS::default())
+        .expect("Failed to save DDS");
+    assert!(ops::cube_to_equirect(&sky, 64, 32, Sampling::Bilinear).is_err());
+}
+
+#[test]
+fn test_ibl() {
+    use directxtex::ops::{self, Sampling};
+
+    #[cfg(feature = "windows")]
+    initialize_com().expect("Failed to initialize COM");
+
+    // White sky over a black ground
+    let (width, height) = (64, 32);
+    let sky: Vec<[f32; 4]> = (0..width * height)
+        .map(|i| if i / width < height / 2 { [1.0; 4] } else { [0.0, 0.0, 0.0, 1.0] })
+        .collect();
+    let equirect = float_image(&sky, width, height);
+    let cube = ops::equirect_to_cube(&equirect, 16, Sampling::Bilinear).expect("Failed to project");
+
+    // Only the constant and the vertical band see the sky
+    let sh = ops::sh9(&cube).expect("Failed to project SH");
+    assert!((sh[0][0] - 0.282_095 * 2.0 * std::f32::consts::PI).abs() < 0.05, "{sh:?}");
+    assert!(sh[1][0] > 0.5);
+    assert!(sh[3][0].abs() < 0.01 && sh[2][0].abs() < 0.01);
+
+    // Irradiance over pi is 1 facing the sky, 0 facing the ground and 1/2 at the horizon
+    let irradiance = ops::irradiance_cube(&cube, 8).expect("Failed to convolve");
+    let metadata = irradiance.metadata();
+    assert_eq!((metadata.width, metadata.arraySize, metadata.miscFlags & 0x4), (8, 6, 0x4));
+    assert!((float_pixel(&irradiance, 0, 2, 4, 4)[0] - 1.0).abs() < 0.05);
+    assert!(float_pixel(&irradiance, 0, 3, 4, 4)[0] < 0.05);
+    let horizon = float_pixel(&irradiance, 0, 4, 4, 3)[0] + float_pixel(&irradiance, 0, 4, 4, 4)[0];
+    assert!((horizon / 2.0 - 0.5).abs() < 0.05);
+
+    let prefiltered = ops::prefilter_ggx(&cube, 64).expect("Failed to prefilter");
+    assert_eq!(prefiltered.mip_levels(), 5);
+    assert_eq!(prefiltered.format(), cube.format());
+    // The top mip is the mirror reflection, rougher mips spread towards the horizon
+    let (top, source) = (float_pixel(&prefiltered, 0, 2, 8, 8), float_pixel(&cube, 0, 2, 8, 8));
+    assert!(top.iter().zip(source).all(|(a, b)| (a - b).abs() < 1e-4));
+    let mut previous = 1.0;
+    for mip in 1..5 {
+        let center = (16 >> mip) / 2;
+        let up = float_pixel(&prefiltered, mip, 2, center, center)[0];
+        assert!(up <= previous + 1e-4 && up > 0.9, "{mip} {up}");
+        previous = up;
+    }
+    prefiltered
+        .save_dds(out_file("test_prefiltered.dds"), DDS_FLAGS::default())
+        .expect("Failed to save DDS");
+
+    assert!(ops::sh9(&equirect).is_err());
+}
+
+#[test]
+fn test_swizzle_pack() {
+    use directxtex::ops::{self, Channel};
+    use directxtex::util::format_from_raw;
+
+    #[cfg(feature = "windows")]
+    initialize_com().expect("Failed to initialize COM");
+
+    let data: Vec<u8> = (0..16u8).flat_map(|i| [i, 100 + i, 200 + i / 2, 255 - i]).collect();
+    let rgba = ScratchImage::new_2d(format_from_raw(28), 4, 4, 1, 1, &data)
+        .expect("Failed to create image");
+
+    let swizzled = rgba.swizzle("bgra").expect("Failed to swizzle");
+    assert_eq!(swizzled.format(), rgba.format());
+    for (out, pixel) in swizzled.buffer().chunks_exact(4).zip(data.chunks_exact(4)) {
+        assert_eq!(out, [pixel[2], pixel[1], pixel[0], pixel[3]]);
+    }
+
+    // Short masks repeat their last character, and DXT5nm style constants
+    let swizzled = rgba.swizzle("rg").expect("Failed to swizzle");
+    for (out, pixel) in swizzled.buffer().chunks_exact(4).zip(data.chunks_exact(4)) {
+        assert_eq!(out, [pixel[0], pixel[1], pixel[1], pixel[1]]);
+    }
+    let swizzled = rgba.swizzle("1y0X").expect("Failed to swizzle");
+    for (out, pixel) in swizzled.buffer().chunks_exact(4).zip(data.chunks_exact(4)) {
+        assert_eq!(out, [255, pixel[1], 0, pixel[0]]);
+    }
+    for mask in ["", "rgbar", "rgbq"] {
+        assert!(rgba.swizzle(mask).is_err(), "{mask}");
+    }
+
+    // Occlusion, roughness and metalness from separate grayscale images
+    let gray = |value: u8| {
+        ScratchImage::new_2d(format_from_raw(61), 4, 4, 1, 1, &[value; 16])
+            .expect("Failed to create image")
+    };
+    let (occlusion, roughness, metalness) = (gray(10), gray(128), gray(255));
+    let packed = ops::pack_channels(
+        [
+            Some((occlusion.image0(), Channel::R)),
+            Some((roughness.image0(), Channel::R)),
+            Some((metalness.image0(), Channel::R)),
+            None,
+        ],
+        format_from_raw(28),
+    )
+    .expect("Failed to pack");
+    assert_eq!(packed.format(), format_from_raw(28));
+    assert!(packed.buffer().chunks_exact(4).all(|pixel| pixel == [10, 128, 255, 255]));
+
+    // Channels can come from anywhere, in any format
+    let packed = ops::pack_channels(
+        [None, Some((rgba.image0(), Channel::A)), None, Some((occlusion.image0(), Channel::R))],
+        format_from_raw(28),
+    )
+    .expect("Failed to pack");
+    for (out, pixel) in packed.buffer().chunks_exact(4).zip(data.chunks_exact(4)) {
+        assert_eq!(out, [0, pixel[3], 0, 10]);
+    }
+
+    let small = ScratchImage::new_2d(format_from_raw(61), 2, 2, 1, 1, &[0; 4]).unwrap();
+    assert!(ops::pack_channels(
+        [Some((rgba.image0(), Channel::R)), Some((small.image0(), Channel::R)), None, None],
+        format_from_raw(28),
+    )
+    .is_err());
+    assert!(ops::pack_channels([None, None, None, None], format_from_raw(28)).is_err());
+}
+
+#[test]
+fn test_rotate_color() {
+    use directxtex::ops::{self, ColorRotation};
+
+    fn assert_close(actual: &[f32], expected: &[f32], tolerance: f32) {
+        assert!(
+            actual.iter().zip(expected).all(|(a, e)| (a - e).abs() <= tolerance),
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    #[cfg(feature = "windows")]
+    initialize_com().expect("Failed to initialize COM");
+
+    let source = [
+        [1.0f32, 0.0, 0.0, 0.5],
+        [0.0, 1.0, 0.0, 1.0],
+        [0.0, 0.0, 1.0, 1.0],
+        [1.0, 1.0, 1.0, 0.25],
+    ];
+    let image = float_image(&source, 4, 1);
+
+    // BT.2087 primaries, white stays white and alpha is untouched
+    let rec2020 = image.rotate_color(ColorRotation::Rec709ToRec2020).expect("Failed to rotate");
+    let rotated = float_pixels(&rec2020);
+    assert_close(&rotated[0], &[0.6274, 0.0691, 0.0164, 0.5], 1e-4);
+    assert_close(&rotated[1], &[0.3293, 0.9195, 0.0880, 1.0], 1e-4);
+    assert_close(&rotated[2], &[0.0433, 0.0114, 0.8956, 1.0], 1e-4);
+    assert_close(&rotated[3], &[1.0, 1.0, 1.0, 0.25], 1e-4);
+    let back = float_pixels(&rec2020.rotate_color(ColorRotation::Rec2020ToRec709).unwrap());
+    assert_close(back.as_flattened(), source.as_flattened(), 1e-4);
+
+    let p3 = image.rotate_color(ColorRotation::Rec709ToP3).expect("Failed to rotate");
+    assert_close(&float_pixels(&p3)[0], &[0.8225, 0.0332, 0.0171, 0.5], 1e-4);
+    let back = float_pixels(&p3.rotate_color(ColorRotation::P3ToRec709).unwrap());
+    assert_close(back.as_flattened(), source.as_flattened(), 1e-4);
+
+    // ST.2084 reference points: 100 nits, 1000 nits and the 10000 nit peak
+    assert!((ops::pq_encode(0.01) - 0.5081).abs() < 1e-3);
+    assert!((ops::pq_encode(0.1) - 0.7518).abs() < 1e-3);
+    assert!((ops::pq_encode(1.0) - 1.0).abs() < 1e-6);
+    assert!((ops::pq_decode(0.5081) - 0.01).abs() < 1e-4);
+    let pq = image.rotate_color(ColorRotation::PqEncode { white_nits: 100.0 }).unwrap();
+    let pq = float_pixels(&pq);
+    assert_close(&pq[3], &[0.5081, 0.5081, 0.5081, 0.25], 1e-3);
+
+    // BT.2100 HLG reference points
+    assert_eq!(ops::hlg_encode(0.0), 0.0);
+    assert!((ops::hlg_encode(1.0 / 12.0) - 0.5).abs() < 1e-6);
+    assert!((ops::hlg_encode(1.0) - 1.0).abs() < 1e-6);
+    assert!((ops::hlg_decode(ops::hlg_encode(0.3)) - 0.3).abs() < 1e-5);
+
+    // scRGB to HDR10 and back
+    let hdr10 = image
+        .rotate_color(ColorRotation::Rec709ToHdr10 { white_nits: 80.0 })
+        .expect("Failed to rotate");
+    let back = hdr10
+        .rotate_color(ColorRotation::Hdr10ToRec709 { white_nits: 80.0 })
+        .expect("Failed to rotate");
+    assert_close(float_pixels(&back).as_flattened(), source.as_flattened(), 1e-3);
+
+    for white_nits in [0.0, -80.0, f32::NAN] {
+        assert!(image.rotate_color(ColorRotation::PqDecode { white_nits }).is_err());
+        assert!(image.rotate_color(ColorRotation::Rec709ToHdr10 { white_nits }).is_err());
+    }
+}
+
+#[test]
+fn test_pixel() {
+    use directxtex::ops::{self, Pixel};
+
+    let a = Pixel::new(1.0, 2.0, 3.0, 4.0);
+    let b = Pixel::splat(2.0);
+    assert_eq!((a.x(), a.y(), a.z(), a.w()), (1.0, 2.0, 3.0, 4.0));
+    assert_eq!(a + b, Pixel::new(3.0, 4.0, 5.0, 6.0));
+    assert_eq!(a - b, Pixel::new(-1.0, 0.0, 1.0, 2.0));
+    assert_eq!(a * b, a * 2.0);
+    assert_eq!(a / 2.0, Pixel::new(0.5, 1.0, 1.5, 2.0));
+    assert_eq!(-a, Pixel::new(-1.0, -2.0, -3.0, -4.0));
+    assert_eq!(a.lerp(b, 0.5), Pixel::new(1.5, 2.0, 2.5, 3.0));
+    assert_eq!((a - b).saturate(), Pixel::new(0.0, 0.0, 1.0, 1.0));
+    assert_eq!(<[f32; 4]>::from(a), [1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(Pixel::from([1.0, 2.0, 3.0, 4.0]), a);
+
+    let mut c = a;
+    c += b;
+    c.set_w(0.0);
+    assert_eq!(c, Pixel::new(3.0, 4.0, 5.0, 0.0));
+
+    let image = dds::load(in_file("test.dds"), DDS_FLAGS::default()).expect("Failed to load DDS");
+    let image = if image.is_compressed() { image.decompress().unwrap() } else { image };
+
+    // Single image variants match the array ones
+    let mut rows = Vec::new();
+    ops::evaluate_image(image.image0(), |pixels, y| rows.push((y, pixels.len()))).unwrap();
+    assert_eq!(rows.len(), image.height());
+    assert!(rows.iter().enumerate().all(|(i, &(y, width))| i == y && width == image.width()));
+
+    let halve = |out: &mut [Pixel], pixels: &[Pixel], _| {
+        for (out, &pixel) in out.iter_mut().zip(pixels) {
+            *out = pixel * 0.5;
+        }
+    };
+    let single = ops::transform_image(image.image0(), halve).expect("Failed to transform");
+    let array = ops::transform_images(image.images(), image.metadata(), halve).unwrap();
+    assert_eq!(single.image0().buffer(), array.image0().buffer());
+
+    // Panics in callbacks skip the remaining rows and come back as errors
+    let mut calls = 0;
+    let error = ops::evaluate_images(image.images(), image.metadata(), |_, y| {
+        calls += 1;
+        assert!(y < 1, "evaluate panic");
+    })
+    .expect_err("Evaluated through a panic");
+    assert_eq!(error.message().to_string(), "callback panicked: evaluate panic");
+    assert_eq!(calls, 2);
+    let error = ops::transform_image(image.image0(), |_, _, _| panic!("transform {}", "panic"))
+        .expect_err("Transformed through a panic");
+    assert_eq!(error.message().to_string(), "callback panicked: transform panic");
+}
+
+#[test]
+fn test_statistics() {
+    use directxtex::ops::AlphaClass;
+
+    #[cfg(feature = "windows")]
+    initialize_com().expect("Failed to initialize COM");
+
+    let denormal = f32::from_bits(1);
+    let source = [
+        [0.0f32, 1.0, f32::NAN, 1.0],
+        [1.0, 3.0, f32::INFINITY, 1.0],
+        [2.0, 5.0, denormal, 1.0],
+        [3.0, 7.0, -1.0, 1.0],
+    ];
+    let image = float_image(&source, 4, 1);
+
+    let stats = image.statistics().expect("Failed to compute statistics");
+    assert_eq!(stats.subresources.len(), 1);
+    assert_eq!(stats.overall, stats.subresources[0]);
+    let overall = &stats.overall;
+    assert_eq!(overall.pixel_count, 4);
+    let [r, g, b, a] = overall.channels;
+    assert_eq!((r.min, r.max, r.mean, r.variance), (0.0, 3.0, 1.5, 1.25));
+    assert_eq!((g.min, g.max, g.mean), (1.0, 7.0, 4.0));
+    assert!((g.std_dev() - 5.0f64.sqrt()).abs() < 1e-9);
+    assert_eq!((b.nan_count, b.infinite_count, b.denormal_count), (1, 1, 1));
+    assert_eq!((b.min, b.max), (-1.0, denormal));
+    assert_eq!(r.nan_count + r.infinite_count + r.denormal_count, 0);
+    assert!(overall.has_special_values());
+    assert_eq!(a.mean, 1.0);
+    assert_eq!(overall.alpha, AlphaClass::Opaque);
+    assert_eq!(overall.unique_alpha_values, Some(1));
+
+    let alphas = [[0.0f32, 0.0, 0.0, 0.0], [1.0, 1.0, 1.0, 1.0]];
+    let binary = float_image(&alphas, 2, 1);
+    let stats = binary.statistics().unwrap();
+    assert_eq!(stats.overall.alpha, AlphaClass::Binary);
+    assert_eq!(stats.overall.unique_alpha_values, Some(2));
+    assert!(!stats.overall.has_special_values());
+
+    // Large values next to a small spread keep their variance
+    let offset = [0.0f32, 1.0, 2.0, 3.0].map(|value| [1.0e7 + value, 0.0, 0.0, 1.0]);
+    let red = float_image(&offset, 4, 1).statistics().unwrap().overall.channels[0];
+    assert_eq!((red.mean, red.variance), (1.0e7 + 1.5, 1.25));
+
+    // Per mip breakdown
+    let image = dds::load(in_file("test.dds"), DDS_FLAGS::default()).expect("Failed to load DDS");
+    let mipped = image
+        .maybe_decompress()
+        .unwrap()
+        .generate_mipmaps(0, TEX_FILTER_FLAGS::default())
+        .unwrap();
+    let stats = mipped.statistics().unwrap();
+    assert_eq!(stats.subresources.len(), mipped.images().len());
+    let total: u64 = stats.subresources.iter().map(|stats| stats.pixel_count).sum();
+    assert_eq!(stats.overall.pixel_count, total);
+    let [red, ..] = stats.overall.channels;
+    let (sum, squares) = stats.subresources.iter().fold((0.0, 0.0), |(sum, squares), stats| {
+        let [channel, ..] = stats.channels;
+        let count = stats.pixel_count as f64;
+        let mean_squares = channel.variance + channel.mean * channel.mean;
+        (sum + channel.mean * count, squares + mean_squares * count)
+    });
+    let mean = sum / total as f64;
+    assert!((red.mean - mean).abs() < 1e-9);
+    assert!((red.variance - (squares / total as f64 - mean * mean)).abs() < 1e-9);
+    assert_eq!(stats.overall.alpha == AlphaClass::Opaque, mipped.is_alpha_all_opaque());
+}
+
+#[test]
+fn test_histogram() {
+    use directxtex::ops::{self, Channel};
+
+    #[cfg(feature = "windows")]
+    initialize_com().expect("Failed to initialize COM");
+
+    let source = [
+        [0.5f32, 0.0, 0.0, 1.0],
+        [1.5, 0.0, 0.0, 1.0],
+        [2.5, 0.0, 0.0, 1.0],
+        [3.5, 0.0, 0.0, 1.0],
+    ];
+    let image = float_image(&source, 4, 1);
+    let (images, metadata) = (image.images(), image.metadata());
+
+    let red = ops::histogram(images, metadata, Channel::R, 4, 0.0..=4.0, None).unwrap();
+    assert_eq!(red.bins, [1, 1, 1, 1]);
+    assert_eq!(red.total(), 4);
+    assert_eq!(red.percentile(0.0), Some(0.0));
+    assert_eq!(red.percentile(50.0), Some(2.0));
+    assert_eq!(red.percentile(100.0), Some(4.0));
+    assert_eq!(red.bin_range(1), 1.0..=2.0);
+
+    let red = ops::histogram(images, metadata, Channel::R, 2, 1.0..=2.0, None).unwrap();
+    assert_eq!((red.below, red.bins.as_slice(), red.above), (1, &[0, 1][..], 2));
+
+    // Grey pixels at 1, 1/4, 4 and black, in log2 luminance
+    let greys = [1.0f32, 0.25, 4.0, 0.0].map(|grey| [grey, grey, grey, 1.0]);
+    let grey = float_image(&greys, 4, 1);
+    let luminance =
+        ops::luminance_histogram(grey.images(), grey.metadata(), 8, -4.0..=4.0, true, None)
+            .unwrap();
+    assert!(luminance.log_scale);
+    assert_eq!(luminance.below, 1);
+    assert_eq!(luminance.bins, [0, 0, 1, 0, 1, 0, 1, 0]);
+    let median = luminance.percentile(50.0).unwrap();
+    assert!((median - 0.5).abs() < 1e-4, "{median}");
+
+    assert!(ops::histogram(images, metadata, Channel::R, 0, 0.0..=1.0, None).is_err());
+    assert!(ops::histogram(images, metadata, Channel::R, 4, 1.0..=1.0, None).is_err());
+    assert!(ops::histogram(images, metadata, Channel::R, 4, 0.0..=1.0, Some(1)).is_err());
+
+    // All mips or a single one
+    let mipped = image.generate_mipmaps(0, TEX_FILTER_FLAGS::default()).unwrap();
+    assert_eq!(mipped.mip_levels(), 3);
+    let (images, metadata) = (mipped.images(), mipped.metadata());
+    let all = ops::histogram(images, metadata, Channel::A, 4, 0.0..=1.0, None).unwrap();
+    assert_eq!(all.total(), 7);
+    let second = ops::histogram(images, metadata, Channel::A, 4, 0.0..=1.0, Some(1)).unwrap();
+    assert_eq!(second.total(), 2);
+    let last = ops::histogram(images, metadata, Channel::R, 4, 0.0..=4.0, Some(2)).unwrap();
+    assert_eq!(last.bins, [0, 0, 1, 0]);
+}
+
+#[test]
+fn test_diff_image() {
+    use directxtex::ops::DiffMode;
+
+    #[cfg(feature = "windows")]
+    initialize_com().expect("Failed to initialize COM");
+
+    let a = float_image(&[[0.5, 0.5, 0.5, 1.0], [1.0, 0.0, 0.0, 1.0], [0.0, 0.0, 0.0, 1.0]], 3, 1);
+    let b =
+        float_image(&[[0.5, 0.5, 0.5, 1.0], [0.75, 0.0, 0.5, 0.5], [0.0, 0.0, 0.0625, 1.0]], 3, 1);
+
+    let absolute = float_pixels(&a.diff(&b, DiffMode::Absolute).expect("Failed to diff"));
+    assert_eq!(absolute, [[0.0; 4], [0.25, 0.0, 0.5, 0.5], [0.0, 0.0, 0.0625, 0.0]]);
+
+    let scaled = float_pixels(&a.diff(&b, DiffMode::Scaled { scale: 2.0 }).unwrap());
+    assert_eq!(scaled[0], [0.0, 0.0, 0.0, 1.0]);
+    assert_eq!(scaled[1], [1.0, 1.0, 1.0, 1.0]);
+    assert_eq!(scaled[2], [0.375, 0.0, 0.0, 1.0]);
+
+    let mask = float_pixels(&a.diff(&b, DiffMode::Threshold { threshold: 0.1 }).unwrap());
+    assert_eq!(mask, [[0.0, 0.0, 0.0, 1.0], [1.0; 4], [0.0, 0.0, 0.0, 1.0]]);
+
+    let wider = float_image(&[[0.0; 4]; 4], 4, 1);
+    assert!(a.diff(&wider, DiffMode::Absolute).is_err());
+
+    // Compressed against its source shows the compression error
+    let source = dds::load(in_file("test.dds"), DDS_FLAGS::default())
+        .unwrap()
+        .maybe_decompress()
+        .unwrap();
+    let compressed = source
+        .compress(COMPRESSED, directxtex::flags::TEX_COMPRESS_FLAGS::default())
+        .unwrap();
+    let heatmap = source.diff(&compressed, DiffMode::Scaled { scale: 8.0 }).unwrap();
+    assert_eq!(heatmap.metadata().width, source.width());
+    assert_eq!(heatmap.format(), RGBAF32);
+    heatmap.save_hdr(0, out_file("diff.hdr")).expect("Failed to save heatmap");
+    assert!(source.diff(&source, DiffMode::Threshold { threshold: 0.0 }).is_ok());
+}
+
+#[test]
+fn test_golden() {
+    use std::panic::AssertUnwindSafe;
+
+    use directxtex::testing::{compare, Texel};
+
+    #[cfg(feature = "windows")]
+    initialize_com().expect("Failed to initialize COM");
+
+    let a = float_image(&[[0.0, 0.0, 0.0, 1.0], [0.5, 0.5, 0.5, 1.0]], 2, 1);
+    let b = float_image(&[[0.0, 0.0, 0.0, 1.0], [0.5, 0.5, 0.0, 1.0]], 2, 1);
+    let comparison = compare(&a, &b).expect("Failed to compare");
+    assert_eq!(comparison.max_abs, 0.5);
+    assert_eq!(comparison.mse, 0.25 / 8.0);
+    assert_eq!(comparison.worst, Texel { subresource: 0, x: 1, y: 0 });
+    assert_images_close!(a, b, 0.05, 0.5);
+    let strict = AssertUnwindSafe(|| assert_images_close!(a, b, 0.01, 0.5));
+    assert!(std::panic::catch_unwind(strict).is_err());
+    assert!(compare(&a, &image(&[[0.0; 4]])).is_err());
+
+    // Missing golden files fail unless blessing, mismatches leave the actual image next to them
+    let golden = out_file("golden/test_golden.dds");
+    std::fs::create_dir_all(out_file("golden")).expect("Failed to create the golden directory");
+    let _ = std::fs::remove_file(&golden);
+    if std::env::var_os(directxtex::testing::BLESS_VAR).is_none() {
+        let missing = AssertUnwindSafe(|| assert_golden(&a, &golden, 0.0, 0.0));
+        assert!(std::panic::catch_unwind(missing).is_err());
+        assert!(!golden.exists());
+    }
+    a.save_dds(&*golden, DDS_FLAGS::default()).expect("Failed to save the golden file");
+    assert_golden(&a, &golden, 0.0, 0.0);
+    if std::env::var_os(directxtex::testing::BLESS_VAR).is_none() {
+        let mismatch = AssertUnwindSafe(|| assert_golden(&b, &golden, 0.0, 0.0));
+        assert!(std::panic::catch_unwind(mismatch).is_err());
+        assert!(out_file("golden/test_golden.actual.dds").exists());
+    }
+}
+
+#[test]
+fn test_load_limits() {
+    use directxtex::ktx2::{self, Supercompression};
+    use directxtex::limits::{is_limit_exceeded, Limit, LimitExceeded};
+    use directxtex::LoadLimits;
+
+    #[cfg(feature = "windows")]
+    initialize_com().expect("Failed to initialize COM");
+
+    let buffer = std::fs::read(in_file("test.dds")).expect("Failed to read DDS");
+    let metadata =
+        dds::metadata_from_memory(&buffer, DDS_FLAGS::default()).expect("Failed to read metadata");
+    let limits = LoadLimits::default();
+    assert_eq!(limits.check(&metadata), Ok(()));
+    dds::load_from_memory_with_limits(&buffer, DDS_FLAGS::default(), &limits)
+        .expect("Failed to load DDS");
+    directxtex::load_with_limits(in_file("test.dds"), &limits).expect("Failed to load DDS");
+
+    let narrow = LoadLimits {
+        max_width: metadata.width - 1,
+        ..LoadLimits::UNLIMITED
+    };
+    assert_eq!(
+        narrow.check(&metadata),
+        Err(LimitExceeded {
+            limit: Limit::Width,
+            value: metadata.width as u64,
+            max: metadata.width as u64 - 1,
+        })
+    );
+    let error = dds::load_with_limits(in_file("test.dds"), DDS_FLAGS::default(), &narrow)
+        .expect_err("Loaded a DDS over the limits");
+    assert!(is_limit_exceeded(&error));
+    let error = directxtex::load_with_limits(in_file("test.dds"), &narrow)
+        .expect_err("Loaded a DDS over the limits");
+    assert!(is_limit_exceeded(&error));
+    assert!(error.to_string().contains("width"), "{error}");
+    assert!(!is_limit_exceeded(&directxtex::load(in_file("missing.dds")).unwrap_err()));
+
+    // A header claiming 65536 x 65536 is rejected before any pixels are allocated
+    let mut huge = buffer.clone();
+    huge[12..16].copy_from_slice(&65536u32.to_le_bytes());
+    huge[16..20].copy_from_slice(&65536u32.to_le_bytes());
+    let error = dds::load_from_memory_with_limits(&huge, DDS_FLAGS::default(), &limits)
+        .expect_err("Loaded a DDS over the limits");
+    assert!(is_limit_exceeded(&error));
+    let bytes = LoadLimits {
+        max_bytes: 1 << 20,
+        ..LoadLimits::UNLIMITED
+    };
+    let exceeded = bytes.check(&dds::metadata_from_memory(&huge, DDS_FLAGS::default()).unwrap());
+    assert_eq!(exceeded.map_err(|exceeded| exceeded.limit), Err(Limit::Bytes));
+
+    let image = dds::load_from_memory(&buffer, DDS_FLAGS::default()).expect("Failed to load DDS");
+    let ktx2 = image
+        .save_ktx2_to_memory(Supercompression::None)
+        .expect("Failed to save KTX2");
+    ktx2::load_from_memory_with_limits(&ktx2, &limits).expect("Failed to load KTX2");
+    let tiny = LoadLimits {
+        max_bytes: 16,
+        ..LoadLimits::default()
+    };
+    let error = ktx2::load_from_memory_with_limits(&ktx2, &tiny)
+        .expect_err("Loaded a KTX2 over the limits");
+    assert!(is_limit_exceeded(&error));
+    let error = directxtex::ktx::load_from_memory_with_limits(&ktx2, &tiny)
+        .expect_err("Loaded a KTX2 over the limits");
+    assert!(is_limit_exceeded(&error));
+
+    // Files far bigger than their header claims are rejected before they are read
+    let mut padded = buffer.clone();
+    padded.resize(buffer.len() + (2 << 20), 0);
+    std::fs::write(out_file("test_padded.dds"), &padded).expect("Failed to write DDS");
+    let error = dds::load_with_limits(out_file("test_padded.dds"), DDS_FLAGS::default(), &tiny)
+        .expect_err("Loaded a DDS over the limits");
+    assert!(is_limit_exceeded(&error));
+    assert!(error.to_string().contains("size in bytes"), "{error}");
+    directxtex::load(out_file("test_padded.dds")).expect("Failed to load without limits");
+}
+
+#[test]
+fn test_load_save_options() {
+    use directxtex::limits::is_limit_exceeded;
+    use directxtex::options::SrgbHandling;
+    use directxtex::util::format_to_raw;
+    use directxtex::{FileType, LoadLimits, LoadOptions, SaveOptions};
+
+    #[cfg(feature = "windows")]
+    initialize_com().expect("Failed to initialize COM");
+
+    let tga = directxtex::load_with(in_file("test.tga"), &LoadOptions::new())
+        .expect("Failed to load TGA");
+    assert_eq!(tga.metadata(), directxtex::load(in_file("test.tga")).unwrap().metadata());
+
+    // DXGI_FORMAT_B8G8R8A8_UNORM or DXGI_FORMAT_B8G8R8X8_UNORM
+    let bgr = directxtex::load_with(in_file("test.tga"), &LoadOptions::new().bgr())
+        .expect("Failed to load TGA");
+    assert!(matches!(format_to_raw(bgr.format()), 87 | 88));
+
+    let options = LoadOptions::new().srgb(SrgbHandling::ForceSrgb).format(RGBAF32);
+    assert_eq!(options.to_tga_flags(), TGA_FLAGS::TGA_FLAGS_FORCE_SRGB);
+    let float = ScratchImage::load_with(in_file("test.tga"), &options).expect("Failed to load TGA");
+    assert_eq!(float.format(), RGBAF32);
+    assert_eq!(float.width(), tga.width());
+
+    // The extension is only a fallback
+    let options = SaveOptions::new().file_type(FileType::Dds).format(COMPRESSED);
+    tga.save_with(out_file("test_options.bin"), &options).expect("Failed to save DDS");
+    let dds = directxtex::load_with(
+        out_file("test_options.bin"),
+        &LoadOptions::new().file_type(FileType::Dds),
+    )
+    .expect("Failed to load DDS");
+    assert_eq!(dds.format(), COMPRESSED);
+    assert_images_close!(dds, tga, 1e-3, 0.25);
+    assert!(directxtex::load_with(out_file("test_options.bin"), &LoadOptions::new()).is_err());
+
+    let options = LoadOptions::new().ignore_mips().format(RGBA8);
+    assert_eq!(options.to_dds_flags(), DDS_FLAGS::DDS_FLAGS_IGNORE_MIPS);
+    let top = directxtex::load_with(in_file("test_complex.dds"), &options).expect("Failed to load");
+    assert_eq!(top.mip_levels(), 1);
+    assert_eq!(top.format(), RGBA8);
+
+    let tiny = LoadOptions::new().limits(LoadLimits {
+        max_width: 1,
+        ..LoadLimits::default()
+    });
+    let error = directxtex::load_with(in_file("test.tga"), &tiny).expect_err("Loaded over limits");
+    assert!(is_limit_exceeded(&error));
+
+    // Single image formats take the requested item
+    let array = ScratchImage::new_2d(RGBA8, 4, 4, 2, 1, &[[0u8; 64], [255u8; 64]].concat())
+        .expect("Failed to create image");
+    array
+        .save_with(out_file("test_options.tga"), &SaveOptions::new().item(1))
+        .expect("Failed to save TGA");
+    let item = tga::load(out_file("test_options.tga"), TGA_FLAGS::default()).unwrap();
+    assert!(item.buffer().iter().all(|&byte| byte == 255));
+    assert!(array
+        .save_with(out_file("test_options.tga"), &SaveOptions::new().item(2))
+        .is_err());
+}
+
+#[test]
+fn test_file_set() {
+    use directxtex::ops::{self, Sampling};
+    use directxtex::FileType;
+
+    #[cfg(feature = "windows")]
+    initialize_com().expect("Failed to initialize COM");
+
+    let dir = out_file("file_set");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("Failed to create the output directory");
+
+    // Every item and mip of an array
+    let data: Vec<u8> = (0..2 * (16 + 4 + 1)).flat_map(|i| [i as u8, 0, 255, 255]).collect();
+    let array = ScratchImage::new_2d(RGBA8, 4, 4, 2, 3, &data).expect("Failed to create image");
+    let written = array
+        .save_all(dir.join("array_{item}_{mip}.tga"), FileType::Tga)
+        .expect("Failed to save the array");
+    assert_eq!(written.len(), 6);
+    assert!(dir.join("array_1_2.tga").exists());
+    let reloaded =
+        ScratchImage::load_all(dir.join("array_{item}_{mip}.tga")).expect("Failed to reload");
+    let layout = |image: &ScratchImage| {
+        (image.width(), image.height(), image.array_size(), image.mip_levels(), image.format())
+    };
+    assert_eq!(layout(&reloaded), layout(&array));
+    assert_eq!(reloaded.buffer(), array.buffer());
+
+    // Names that would be written twice, or placeholders the image doesn't have
+    for pattern in ["array_{mip}.tga", "array_{item}.tga", "array_{face}_{mip}.tga"] {
+        assert!(array.save_all(dir.join(pattern), FileType::Tga).is_err(), "{pattern}");
+    }
+
+    let sky: Vec<[f32; 4]> = (0..32 * 16)
+        .map(|i| if i % 32 < 16 { [2.0, 0.5, 0.0, 1.0] } else { [0.0, 0.25, 1.0, 1.0] })
+        .collect();
+    let equirect = float_image(&sky, 32, 16);
+    let cube = ops::equirect_to_cube(&equirect, 8, Sampling::Bilinear)
+        .and_then(|cube| cube.generate_mipmaps(0, TEX_FILTER_FLAGS::default()))
+        .expect("Failed to build the cubemap");
+    let written = cube
+        .save_all(dir.join("sky_{face}_{mip}.hdr"), FileType::Hdr)
+        .expect("Failed to save the cubemap");
+    assert_eq!(written.len(), 6 * cube.mip_levels());
+    let reloaded =
+        ScratchImage::load_all(dir.join("sky_{face}_{mip}.hdr")).expect("Failed to reload");
+    assert_eq!(reloaded.metadata().miscFlags & 0x4, 0x4);
+    assert_eq!((reloaded.array_size(), reloaded.mip_levels()), (6, cube.mip_levels()));
+    // RGBE keeps 8 bits of mantissa
+    assert_images_close!(reloaded, cube, 1e-4, 0.02);
+
+    #[cfg(feature = "windows")]
+    {
+        use directxtex::wic::WIC_CODEC_TIFF;
+
+        // Both items as frames of one TIFF
+        let pixels = [[7u8; 64], [9u8; 64]].concat();
+        let top = ScratchImage::new_2d(RGBA8, 4, 4, 2, 1, &pixels).unwrap();
+        let written = top
+            .save_all(dir.join("frames.tif"), FileType::Wic(WIC_CODEC_TIFF))
+            .expect("Failed to save TIFF");
+        assert_eq!(written, [dir.join("frames.tif")]);
+        let reloaded = ScratchImage::load_all(dir.join("frames.tif")).expect("Failed to reload");
+        assert_eq!(reloaded.array_size(), 2);
+        assert_images_close!(reloaded, top, 0.0, 0.0);
+    }
+}
+
+#[test]
+fn test_atlas() {
+    use directxtex::atlas::{self, AtlasOptions, Packer};
+    use directxtex::preset::MipPolicy;
+
+    let colors = [[255u8, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255], [255, 255, 0, 128]];
+    let sizes = [(16, 16), (8, 24), (12, 12), (30, 6), (5, 7), (16, 16)];
+    let sprites: Vec<ScratchImage> = sizes
+        .iter()
+        .enumerate()
+        .map(|(i, &(width, height))| {
+            let data = colors[i % colors.len()].repeat(width * height);
+            ScratchImage::new_2d(RGBA8, width, height, 1, 1, &data).unwrap()
+        })
+        .collect();
+    let pixel = |image: &ScratchImage, mip: usize, x: usize, y: usize| {
+        let image = image.image(mip, 0, 0).unwrap();
+        let start = y * image.row_pitch() + x * 4;
+        <[u8; 4]>::try_from(&image.buffer()[start..start + 4]).unwrap()
+    };
+
+    for packer in [Packer::MaxRects, Packer::Skyline] {
+        let options = AtlasOptions {
+            packer,
+            padding: 2,
+            mips: MipPolicy::Full,
+            ..AtlasOptions::default()
+        };
+        let packed = atlas::pack_images(&sprites, &options).expect("Failed to pack");
+        let layout = &packed.layout;
+        assert_eq!(packed.image.format(), RGBA8);
+        assert_eq!((packed.image.width(), packed.image.height()), (layout.width, layout.height));
+        assert_eq!(packed.image.mip_levels(), layout.mip_levels);
+        assert!(layout.mip_levels > 1);
+        assert_eq!(layout.rects.len(), sprites.len());
+
+        for (i, rect) in layout.rects.iter().enumerate() {
+            assert_eq!((rect.width, rect.height), sizes[i]);
+            assert!(rect.x >= 2 && rect.x + rect.width + 2 <= layout.width, "{packer:?} {i}");
+            assert!(rect.y >= 2 && rect.y + rect.height + 2 <= layout.height, "{packer:?} {i}");
+            assert_eq!(rect.u0, rect.x as f32 / layout.width as f32);
+            assert_eq!(rect.v1, (rect.y + rect.height) as f32 / layout.height as f32);
+            for other in &layout.rects[i + 1..] {
+                let apart = rect.x + rect.width + 4 <= other.x
+                    || other.x + other.width + 4 <= rect.x
+                    || rect.y + rect.height + 4 <= other.y
+                    || other.y + other.height + 4 <= rect.y;
+                assert!(apart, "{packer:?}: {rect:?} overlaps {other:?}");
+            }
+
+            // Extruded edges, and no neighbour bleeding in at any mip
+            let color = colors[i % colors.len()];
+            assert_eq!(pixel(&packed.image, 0, rect.x - 2, rect.y - 2), color);
+            assert_eq!(pixel(&packed.image, 0, rect.x + rect.width + 1, rect.y), color);
+            for mip in 0..layout.mip_levels {
+                let (x, y) = (rect.x + rect.width / 2, rect.y + rect.height / 2);
+                assert_eq!(pixel(&packed.image, mip, x >> mip, y >> mip), color, "mip {mip}");
+            }
+        }
+    }
+
+    // Power of two and compressed
+    let options = AtlasOptions {
+        power_of_two: true,
+        mips: MipPolicy::Full,
+        format: Some(COMPRESSED),
+        ..AtlasOptions::default()
+    };
+    let packed = atlas::pack_images(&sprites, &options).expect("Failed to pack compressed");
+    assert_eq!(packed.image.format(), COMPRESSED);
+    assert!(packed.layout.width.is_power_of_two() && packed.layout.height.is_power_of_two());
+
+    // Without mips, cells still cover whole 4x4 blocks
+    let options = AtlasOptions {
+        padding: 1,
+        mips: MipPolicy::None,
+        format: Some(COMPRESSED),
+        ..AtlasOptions::default()
+    };
+    let packed = atlas::pack_images(&sprites, &options).expect("Failed to pack compressed");
+    for rect in &packed.layout.rects {
+        let (x, y) = (rect.x - options.padding, rect.y - options.padding);
+        assert_eq!((x % 4, y % 4), (0, 0), "{rect:?}");
+    }
+
+    // Asking for more levels than the smallest cell has gets the full chain
+    let full = AtlasOptions {
+        padding: 2,
+        mips: MipPolicy::Full,
+        ..AtlasOptions::default()
+    };
+    let levels = AtlasOptions {
+        mips: MipPolicy::Levels(20),
+        ..full
+    };
+    assert_eq!(
+        atlas::pack_images(&sprites, &levels).expect("Failed to pack").layout.mip_levels,
+        atlas::pack_images(&sprites, &full).expect("Failed to pack").layout.mip_levels
+    );
+
+    // Too big for the limits, or nothing to pack
+    let options = AtlasOptions {
+        max_width: 32,
+        ..AtlasOptions::default()
+    };
+    assert!(atlas::pack_images(&sprites, &options).is_err());
+    assert!(atlas::pack(&[], &AtlasOptions::default()).is_err());
+}